@@ -1,4 +1,8 @@
+use crate::error::GameError;
+use crate::rng::{FairRandomState, RandomEventKind};
 use crate::types::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::sync::OnceLock;
 
 // Card catalog definition and helpers. Kept separate so balance tweaks stay isolated from engine.
@@ -7,7 +11,90 @@ use std::sync::OnceLock;
 const CARDS_JSON: &str = include_str!("cards.json");
 
 pub fn build_catalog() -> Vec<CardDefinition> {
-    serde_json::from_str(CARDS_JSON).expect("Failed to parse cards.json")
+    let catalog: Vec<CardDefinition> =
+        serde_json::from_str(CARDS_JSON).expect("Failed to parse cards.json");
+    if let Err(errors) = validate_catalog(&catalog) {
+        panic!("Invalid cards.json:\n{}", errors.join("\n"));
+    }
+    catalog
+}
+
+// Data-entry checks serde can't express. Collects every problem rather than stopping at the first.
+pub fn validate_catalog(catalog: &[CardDefinition]) -> Result<(), Vec<String>> {
+    let mut errors = vec![];
+    let mut seen = std::collections::HashSet::new();
+    for def in catalog {
+        if !seen.insert(def.id.as_str()) {
+            errors.push(format!("{}: duplicate id", def.id));
+        }
+    }
+    let exists = |id: &str| catalog.iter().any(|d| d.id == id);
+    for def in catalog {
+        if let CardKind::Exploit(ExploitEffect::Invest(params)) = &def.class {
+            if params.cost_score < 0 {
+                errors.push(format!("{}: Invest cost_score must not be negative", def.id));
+            }
+        }
+        if let CardKind::Exploit(ExploitEffect::Sacrifice(params)) = &def.class {
+            if params.damage <= 0 {
+                errors.push(format!("{}: Sacrifice damage must be positive", def.id));
+            }
+        }
+        if let CardKind::Exploit(ExploitEffect::ScoreTax(amount)) = &def.class {
+            if *amount <= 0 {
+                errors.push(format!("{}: ScoreTax amount must be positive", def.id));
+            }
+        }
+        let CardKind::Meme(meme) = &def.class else {
+            continue;
+        };
+        if meme.base_virality < 0 || meme.cook_rate < 0 || meme.yield_rate < 0 {
+            errors.push(format!("{}: negative virality, cook rate or yield rate", def.id));
+        }
+        for keyword in &meme.keywords {
+            match keyword {
+                Keyword::Gatekeeper(GatekeeperKeyword { max_cost: 0 }) => {
+                    errors.push(format!("{}: Gatekeeper max_cost must be at least 1", def.id));
+                }
+                Keyword::Overload(OverloadKeyword { amount: 0 }) => {
+                    errors.push(format!("{}: keyword amount must be positive", def.id));
+                }
+                Keyword::Shielded(ShieldedKeyword { amount })
+                | Keyword::Thorns(ThornsKeyword { amount })
+                | Keyword::Combo(ComboKeyword { amount })
+                    if *amount <= 0 =>
+                {
+                    errors.push(format!("{}: keyword amount must be positive", def.id));
+                }
+                _ => {}
+            }
+        }
+        for ability in &meme.abilities {
+            if let AbilityEffect::Spawn(spawn) = &ability.effect {
+                if !exists(&spawn.variant_id) {
+                    errors.push(format!("{}: spawns unknown card {}", def.id, spawn.variant_id));
+                }
+            }
+        }
+    }
+    // SpawnShitposts always makes d06.
+    let spawns_shitposts = catalog
+        .iter()
+        .any(|d| matches!(d.class, CardKind::Exploit(ExploitEffect::SpawnShitposts(_))));
+    if spawns_shitposts && !exists("d06") {
+        errors.push("SpawnShitposts needs card d06".to_string());
+    }
+    if !catalog.iter().any(|d| matches!(d.class, CardKind::Meme(_))) {
+        errors.push("catalog has no memes".to_string());
+    }
+    if !catalog.iter().any(|d| matches!(d.class, CardKind::Exploit(_))) {
+        errors.push("catalog has no exploits".to_string());
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
 }
 
 pub fn default_deck() -> Vec<String> {
@@ -23,8 +110,167 @@ pub fn default_deck() -> Vec<String> {
     .collect()
 }
 
-pub fn find_definition(id: &str) -> Option<&'static CardDefinition> {
+// Cheap memes and burn to close games out before the feed fills up.
+pub fn aggro_deck() -> Vec<String> {
+    [
+        "n01", "n09", "c02", "t07", // Memes
+        "t01", "t02", "t08", "n07", "m05", "d08", "m10", "d12",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect()
+}
+
+// Sturdy memes plus removal and feed control for the long game.
+pub fn control_deck() -> Vec<String> {
+    [
+        "c05", "m04", "n10", "m07", // Memes
+        "t03", "t04", "t09", "m03", "m06", "c06", "m02", "d13",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect()
+}
+
+// Built-in starter decks, by name.
+pub fn sample_decks() -> Vec<(String, Vec<String>)> {
+    vec![
+        ("default".to_string(), default_deck()),
+        ("aggro".to_string(), aggro_deck()),
+        ("control".to_string(), control_deck()),
+    ]
+}
+
+// Summary stats for a deck list, for deck-builder screens.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct DeckAnalysis {
+    pub total_cost: u32,
+    pub avg_cost: f32,
+    pub meme_count: usize,
+    pub exploit_count: usize,
+    pub keyword_histogram: BTreeMap<String, usize>,
+    // (cost, number of cards at that cost), cheapest first.
+    pub curve: Vec<(u8, usize)>,
+}
+
+pub fn analyze_deck(catalog: &[CardDefinition], ids: &[String]) -> Result<DeckAnalysis, GameError> {
+    let mut total_cost = 0u32;
+    let mut meme_count = 0usize;
+    let mut exploit_count = 0usize;
+    let mut keyword_histogram = BTreeMap::new();
+    let mut curve: BTreeMap<u8, usize> = BTreeMap::new();
+    for id in ids {
+        let def = catalog
+            .iter()
+            .find(|c| &c.id == id)
+            .ok_or_else(|| GameError::UnknownCard(id.clone()))?;
+        total_cost += def.cost as u32;
+        *curve.entry(def.cost).or_default() += 1;
+        match &def.class {
+            CardKind::Meme(meme) => {
+                meme_count += 1;
+                for keyword in &meme.keywords {
+                    *keyword_histogram.entry(keyword_name(keyword).to_string()).or_default() += 1;
+                }
+            }
+            CardKind::Exploit(_) => exploit_count += 1,
+        }
+    }
+    let avg_cost = if ids.is_empty() { 0.0 } else { total_cost as f32 / ids.len() as f32 };
+    Ok(DeckAnalysis {
+        total_cost,
+        avg_cost,
+        meme_count,
+        exploit_count,
+        keyword_histogram,
+        curve: curve.into_iter().collect(),
+    })
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum CardCategory {
+    Meme,
+    Exploit,
+}
+
+// Deck-builder search. Unset fields match every card; `keyword` uses the same names as the
+// DeckAnalysis histogram and only memes carry keywords.
+#[derive(Clone, Default, Serialize, Deserialize, Debug, PartialEq)]
+pub struct CardFilter {
+    #[serde(default)]
+    pub kind: Option<CardCategory>,
+    #[serde(default)]
+    pub max_cost: Option<u8>,
+    #[serde(default)]
+    pub keyword: Option<String>,
+}
+
+pub fn find_cards(catalog: &[CardDefinition], filter: &CardFilter) -> Vec<CardDefinition> {
+    catalog
+        .iter()
+        .filter(|def| {
+            matches!(
+                (&filter.kind, &def.class),
+                (None, _)
+                    | (Some(CardCategory::Meme), CardKind::Meme(_))
+                    | (Some(CardCategory::Exploit), CardKind::Exploit(_))
+            )
+        })
+        .filter(|def| filter.max_cost.is_none_or(|max| def.cost <= max))
+        .filter(|def| match (&filter.keyword, &def.class) {
+            (None, _) => true,
+            (Some(name), CardKind::Meme(meme)) => meme.keywords.iter().any(|k| keyword_name(k) == name),
+            (Some(_), CardKind::Exploit(_)) => false,
+        })
+        .cloned()
+        .collect()
+}
+
+fn keyword_name(keyword: &Keyword) -> &'static str {
+    match keyword {
+        Keyword::Haste => "Haste",
+        Keyword::Stealth => "Stealth",
+        Keyword::Fragile => "Fragile",
+        Keyword::Shielded(_) => "Shielded",
+        Keyword::Taunt => "Taunt",
+        Keyword::Anchor => "Anchor",
+        Keyword::Heavy => "Heavy",
+        Keyword::Gatekeeper(_) => "Gatekeeper",
+        Keyword::HealKitchen => "HealKitchen",
+        Keyword::Thorns(_) => "Thorns",
+        Keyword::Overload(_) => "Overload",
+        Keyword::Combo(_) => "Combo",
+    }
+}
+
+fn static_catalog() -> &'static [CardDefinition] {
     static CATALOG: OnceLock<Vec<CardDefinition>> = OnceLock::new();
-    let catalog = CATALOG.get_or_init(build_catalog);
-    catalog.iter().find(|d| d.id == id)
+    CATALOG.get_or_init(build_catalog)
+}
+
+pub fn find_definition(id: &str) -> Option<&'static CardDefinition> {
+    static_catalog().iter().find(|d| d.id == id)
+}
+
+// Up to `size` distinct card ids for a draft pack, each pick weighted by rarity. Draws come from
+// the resolution stream, so peers holding the same RNG state deal the same pack.
+pub fn generate_pack(rng: &mut FairRandomState, size: usize) -> Vec<String> {
+    let mut pool: Vec<&CardDefinition> = static_catalog().iter().collect();
+    let mut pack = vec![];
+    while pack.len() < size && !pool.is_empty() {
+        let total = pool.iter().map(|d| d.rarity.weight()).sum();
+        let mut roll = rng.generate(total, 0, RandomEventKind::DraftPack);
+        let index = pool
+            .iter()
+            .position(|d| {
+                if roll < d.rarity.weight() {
+                    return true;
+                }
+                roll -= d.rarity.weight();
+                false
+            })
+            .unwrap_or(0);
+        pack.push(pool.remove(index).id.clone());
+    }
+    pack
 }
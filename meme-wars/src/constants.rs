@@ -1,6 +1,8 @@
 // Core tuning and size constants for the game. These are kept in one place so both
 // the game engine and catalog can share them without duplication.
 pub const GAME_NAME: &str = "Meme Wars: The Feed";
+// Bump whenever WireMessage or GameSnapshot change shape; peers on another version are refused.
+pub const PROTOCOL_VERSION: u32 = 2;
 pub const FEED_SIZE: usize = 3;
 pub const STARTING_HAND: usize = 2;
 pub const MAX_HAND_SIZE: usize = 4;
@@ -13,4 +15,22 @@ pub const BASE_COOK: i32 = 1;
 pub const BASE_FEED_YIELD: i32 = 10;
 pub const FEED_YIELD_STEP: i32 = 5;
 pub const SCORE_TO_WIN: i32 = 30;
+pub const MAX_PLAN_ACTIONS: usize = 32;
+pub const MAX_SCORE_LOG: usize = 256;
+pub const MAX_PLAN_HISTORY: usize = 128;
+pub const MAX_CHAT_LOG: usize = 100;
+pub const MAX_CHAT_LEN: usize = 500;
+pub const MAX_MATCH_HISTORY: usize = 20;
+// Most score one seat's ScoreTax exploits can take over a whole game.
+pub const MAX_SCORE_TAX: i32 = 20;
+// Both reseed contributions for a previewed turn, so the copy's RNG history marks it as an estimate.
+pub const PREVIEW_RNG_SALT: &str = "preview";
+// Consecutive turns a seat must own a full feed to win under FullFeedControl.
+pub const FULL_FEED_CONTROL_TURNS: u8 = 2;
+// Tries per turn update before it is queued in pending_sync.
+pub const WIRE_SEND_ATTEMPTS: usize = 3;
+// Events (and RNG history entries) kept in client snapshots; older ones come from events_since.
+pub const SNAPSHOT_EVENT_WINDOW: usize = 64;
+// Ceiling for the debug-only stakes override.
+pub const MAX_STAKES: u8 = 64;
 pub const WS_PATH: &str = "/ws";
@@ -2,10 +2,21 @@ use crate::types::TurnPlan;
 use sha2::{Digest, Sha256};
 
 // Simple hashing helpers for commit/reveal. Kept separate so both engine and transport reuse.
-pub fn commitment_for(plan: &TurnPlan, salt: &str) -> String {
+// The turn and game seed are bound in so a commitment can't be replayed in another turn or game.
+pub fn commitment_for(plan: &TurnPlan, salt: &str, turn: u32, game_seed: u64) -> String {
     let mut hasher = Sha256::new();
     let payload = serde_json::to_vec(plan).unwrap_or_default();
     hasher.update(payload);
     hasher.update(salt.as_bytes());
+    hasher.update(turn.to_le_bytes());
+    hasher.update(game_seed.to_le_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+// Lobby passwords are salted with the lobby id so equal passwords don't share a hash.
+pub fn lobby_password_hash(lobby_id: &str, password: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(lobby_id.as_bytes());
+    hasher.update(password.as_bytes());
     format!("{:x}", hasher.finalize())
 }
@@ -0,0 +1,237 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// Structured errors shared by the engine, the wire protocol and the websocket bridge. Peers and
+// the UI can branch on the variant; `Display` keeps the human-readable messages for logs.
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum GameError {
+    NoActiveGame,
+    GameOver,
+    SeatNotFound,
+    NotSeated(String),
+    TurnMismatch(TurnParams),
+    CommitHashMismatch,
+    NotCommitPhase,
+    OpponentAlreadyCommitted,
+    CommitConflict,
+    NoCommitToRetract,
+    NoPendingStakes,
+    NotStakeCaller,
+    TooManyKitchenPlays,
+    InsufficientMana(ManaParams),
+    InsufficientScore(ScoreParams),
+    CardNotInHand(String),
+    UnknownCard(String),
+    NotAnExploit,
+    NotAMeme,
+    CardNotInKitchen(String),
+    CannotPostYet(String),
+    FeedLocked,
+    InvalidTarget,
+    MissingTarget,
+    InvalidFeedSlot,
+    FeedSlotFixed,
+    MustTargetTaunt,
+    TargetStealthed,
+    PlanTooLarge(PlanLimitParams),
+    DeckTooSmall,
+    DeckEmpty,
+    NoMemeInStartingHand,
+    OpeningHandTooExpensive,
+    StateHashMismatch,
+    RandomUnverified(String),
+    ReplayMissingStart,
+    LobbyNotFound,
+    LobbyFull,
+    WrongLobbyPassword,
+    NeedOpponent,
+    GameNotOver,
+    InvalidFeedSize,
+    SelfTarget,
+    SubscriberMismatch,
+    ChatTooLong,
+    DraftPending,
+    NotDraftPhase,
+    DiscardPending,
+    NotDiscardPhase,
+    InvalidDiscard,
+    NotLobbyPhase,
+    UnexpectedReply,
+    NestedBatch,
+    VersionMismatch(VersionParams),
+    MalformedWireMessage(String),
+    SeedCommitmentMismatch,
+    NoAgreedSeed,
+    SeedMismatch,
+    GameNotFound(String),
+    OpponentReachable,
+    OpponentSyncFailed(String),
+    PeerUnreachable(String),
+    Transport(String),
+    DebugOnly,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct TurnParams {
+    pub expected: u32,
+    pub got: u32,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct VersionParams {
+    pub ours: u32,
+    pub theirs: u32,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct ManaParams {
+    pub need: u8,
+    pub have: u8,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct ScoreParams {
+    pub need: i32,
+    pub have: i32,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct PlanLimitParams {
+    pub section: String,
+    pub len: usize,
+    pub max: usize,
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameError::NoActiveGame => write!(f, "no active game"),
+            GameError::GameOver => write!(f, "game is over"),
+            GameError::SeatNotFound => write!(f, "seat not found"),
+            GameError::NotSeated(node) => write!(f, "{} is not seated in this game", node),
+            GameError::TurnMismatch(params) => write!(
+                f,
+                "turn mismatch: game {}, got {}",
+                params.expected, params.got
+            ),
+            GameError::CommitHashMismatch => write!(f, "commit hash mismatch"),
+            GameError::NotCommitPhase => {
+                write!(f, "commits can only be retracted during the commit phase")
+            }
+            GameError::OpponentAlreadyCommitted => {
+                write!(f, "opponent already committed; cannot retract")
+            }
+            GameError::CommitConflict => {
+                write!(f, "already committed a different plan this turn; retract it first")
+            }
+            GameError::NoCommitToRetract => write!(f, "no commit to retract"),
+            GameError::NoPendingStakes => write!(f, "no pending stakes"),
+            GameError::NotStakeCaller => write!(f, "only the player who called BASED can cancel it"),
+            GameError::TooManyKitchenPlays => {
+                write!(f, "only one meme can be played from hand to kitchen per turn")
+            }
+            GameError::InsufficientMana(params) => write!(
+                f,
+                "insufficient mana: need {}, have {}",
+                params.need, params.have
+            ),
+            GameError::InsufficientScore(params) => write!(
+                f,
+                "insufficient score: need {}, have {}",
+                params.need, params.have
+            ),
+            GameError::CardNotInHand(id) => write!(f, "card {} not in hand", id),
+            GameError::UnknownCard(id) => write!(f, "card {} not found", id),
+            GameError::NotAnExploit => write!(f, "card is not an exploit"),
+            GameError::NotAMeme => write!(f, "only memes can be played to kitchen"),
+            GameError::CardNotInKitchen(id) => write!(f, "card {} is not in the kitchen", id),
+            GameError::CannotPostYet(id) => {
+                write!(f, "card {} was played this turn or is frozen and cannot post yet", id)
+            }
+            GameError::FeedLocked => write!(f, "the feed is locked this turn"),
+            GameError::InvalidTarget => write!(f, "invalid target"),
+            GameError::MissingTarget => write!(f, "exploit requires a target"),
+            GameError::InvalidFeedSlot => write!(f, "invalid feed slot"),
+            GameError::FeedSlotFixed => write!(f, "pinned or anchored feed cards cannot move"),
+            GameError::MustTargetTaunt => write!(f, "must target taunt card first"),
+            GameError::TargetStealthed => write!(f, "target is stealth"),
+            GameError::PlanTooLarge(params) => write!(
+                f,
+                "plan {} has {} entries (max {})",
+                params.section, params.len, params.max
+            ),
+            GameError::DeckTooSmall => write!(f, "deck too small for starting hand"),
+            GameError::DeckEmpty => write!(f, "deck empty"),
+            GameError::NoMemeInStartingHand => {
+                write!(f, "unable to produce a valid starting hand containing a meme")
+            }
+            GameError::OpeningHandTooExpensive => {
+                write!(f, "unable to produce a starting hand under the opening cost cap")
+            }
+            GameError::StateHashMismatch => write!(f, "state hash mismatch"),
+            GameError::RandomUnverified(reason) => {
+                write!(f, "random draw failed verification: {}", reason)
+            }
+            GameError::ReplayMissingStart => write!(f, "replay log has no game start"),
+            GameError::LobbyNotFound => write!(f, "Lobby not found"),
+            GameError::LobbyFull => write!(f, "Lobby already has an opponent"),
+            GameError::WrongLobbyPassword => write!(f, "Wrong lobby password"),
+            GameError::NeedOpponent => write!(f, "Need an opponent to start"),
+            GameError::GameNotOver => write!(f, "the current game is not finished"),
+            GameError::InvalidFeedSize => write!(f, "feed size must be at least 1"),
+            GameError::SelfTarget => write!(f, "cannot target our own node"),
+            GameError::SubscriberMismatch => write!(f, "subscribe node does not match sender"),
+            GameError::ChatTooLong => write!(f, "chat message longer than {} characters", crate::constants::MAX_CHAT_LEN),
+            GameError::DraftPending => {
+                write!(f, "both players must choose their draw before the next turn")
+            }
+            GameError::NotDraftPhase => write!(f, "there is no draw to choose from"),
+            GameError::DiscardPending => {
+                write!(f, "both players must discard down to hand size before the next turn")
+            }
+            GameError::NotDiscardPhase => write!(f, "there is nothing to discard"),
+            GameError::NotLobbyPhase => write!(f, "the game has already started"),
+            GameError::InvalidDiscard => write!(
+                f,
+                "keep exactly {} different cards from your hand",
+                crate::constants::MAX_HAND_SIZE
+            ),
+            GameError::UnexpectedReply => write!(f, "unexpected reply"),
+            GameError::NestedBatch => write!(f, "wire batches cannot contain other batches"),
+            GameError::VersionMismatch(params) => write!(
+                f,
+                "peer speaks protocol version {}, we speak {}",
+                params.theirs, params.ours
+            ),
+            GameError::MalformedWireMessage(err) => write!(f, "could not read wire message: {}", err),
+            GameError::SeedCommitmentMismatch => {
+                write!(f, "seed reveal does not match its commitment")
+            }
+            GameError::NoAgreedSeed => write!(f, "no seed has been agreed for this lobby"),
+            GameError::SeedMismatch => write!(f, "game was not dealt from the agreed seed"),
+            GameError::GameNotFound(id) => write!(f, "no game with id {}", id),
+            GameError::OpponentReachable => {
+                write!(f, "opponent has not been shown to be unreachable")
+            }
+            GameError::OpponentSyncFailed(err) => {
+                write!(f, "opponent did not receive the game: {}", err)
+            }
+            GameError::PeerUnreachable(err) => write!(
+                f,
+                "opponent unreachable, update queued to resend: {}",
+                err
+            ),
+            GameError::Transport(err) => write!(f, "send failed: {}", err),
+            GameError::DebugOnly => write!(f, "only available in debug builds"),
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
+
+impl From<GameError> for String {
+    fn from(err: GameError) -> Self {
+        err.to_string()
+    }
+}
@@ -1,13 +1,16 @@
-use crate::catalog::find_definition;
+use crate::catalog::{build_catalog, find_definition};
 use crate::constants::*;
 use crate::crypto::commitment_for;
+use crate::error::{GameError, ManaParams, PlanLimitParams, ScoreParams, TurnParams};
 use crate::rng::{
     FairRandomState, RandomEvent, RandomEventKind, StartingHandCycle, StartingHandEvent,
 };
+use crate::snapshot::{FeedSlotChange, GameDiff};
 use crate::types::*;
 use hyperware_process_lib::our;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
 
 // Game engine state and mutation logic. Functionality mirrors the previous monolithic lib.rs
 // but is organized here to make it easier to reason about individual phases.
@@ -25,13 +28,46 @@ pub struct PlayerState {
     pub score: i32,
     pub cost_discount: i32,
     pub mana_tax_next: i32,
+    // Mana owed next turn for Overload memes played this turn. Stacks across plays.
+    #[serde(default)]
+    pub overload_next: u8,
+    // Score this seat's ScoreTax exploits have taken so far this game, up to MAX_SCORE_TAX.
+    #[serde(default)]
+    pub score_tax_used: i32,
     pub commit: Option<TurnCommit>,
     pub feed_locked: bool,
-    pub pinned_slots: Vec<usize>,
+    // Instance ids of feed cards pinned in place until turn end. Tracked by card rather than slot so
+    // a removal above a pinned card can't move the pin onto its neighbour.
+    #[serde(default)]
+    pub pinned_cards: Vec<String>,
+    // Variant ids of the deck's top cards, top first, from the last scry. Only this seat's own
+    // client sees them, and the next draw clears them.
+    #[serde(default)]
+    pub revealed_deck_top: Vec<String>,
+    // Instance ids of the other seat's hand when we last cast Expose. Those cards show face up to
+    // this seat only, until our next turn resolves.
+    #[serde(default)]
+    pub exposed_hand: Option<Vec<String>>,
+    // Draft-draw games only: the cards drawn this turn end, waiting for one to be kept.
+    #[serde(default)]
+    pub pending_draw: Vec<CardInstance>,
+    pub last_stand_used: bool,
+    // Action points left this turn; `None` when the game has no action budget.
+    #[serde(default)]
+    pub action_points: Option<u8>,
+    // Times each variant has been played this game. Ordered so the state hash stays deterministic.
+    #[serde(default)]
+    pub plays_this_game: BTreeMap<String, u32>,
+    #[serde(default)]
+    pub cosmetics: Option<PlayerCosmetics>,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub struct GameState {
+    // Key in the node's games map. Lobby games are named after the host and lobby so both seats
+    // file them under the same id; empty until the game is stored.
+    #[serde(default)]
+    pub id: String,
     pub feed: Vec<CardInstance>,
     pub players: Vec<PlayerState>,
     pub turn: u32,
@@ -40,10 +76,67 @@ pub struct GameState {
     pub stakes: u8,
     pub pending_stakes: Option<String>,
     pub winner: Option<Seat>,
+    #[serde(default)]
+    pub outcome: Option<GameOutcome>,
+    pub feed_size: usize,
+    pub score_to_win: i32,
+    pub last_stand: LastStandRule,
+    #[serde(default)]
+    pub action_budget: ActionBudgetRule,
+    #[serde(default)]
+    pub win_condition: WinCondition,
+    #[serde(default)]
+    pub rules: GameRules,
+    // Consecutive turn ends each seat (host, opponent) has owned the whole occupied feed.
+    #[serde(default)]
+    pub domination_streak: [u8; 2],
+    // Whether each seat (host, opponent) resolved an empty plan last turn.
+    #[serde(default)]
+    pub passed_last_turn: [bool; 2],
+    #[serde(default)]
+    pub full_disclosure: bool,
     pub game_seed: u64,
     pub next_instance: u64,
     pub rng: FairRandomState,
     pub events: Vec<GameEvent>,
+    // Most recent score changes, oldest first; trimmed to MAX_SCORE_LOG entries.
+    #[serde(default)]
+    pub score_log: Vec<ScoreDelta>,
+    // Every resolved turn's revealed plans, host first; trimmed to MAX_PLAN_HISTORY entries.
+    #[serde(default)]
+    pub plan_history: Vec<(u32, Seat, TurnPlan)>,
+    // Per-variant tallies for the post-game summary. Derived, so left out of the state hash.
+    #[serde(default)]
+    pub card_stats: BTreeMap<String, CardStats>,
+    // Variant that last damaged each card, so its death can be credited as a kill.
+    #[serde(default)]
+    pub last_damaged_by: BTreeMap<String, String>,
+    // Variant of the exploit currently resolving, if any.
+    #[serde(skip)]
+    damage_source: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Default)]
+pub struct CardStats {
+    pub posted: u32,
+    pub score_yielded: i32,
+    pub kills: u32,
+    pub deaths: u32,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct ScoreDelta {
+    pub turn: u32,
+    pub seat: Seat,
+    pub amount: i32,
+    pub reason: ScoreReason,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum ScoreReason {
+    FeedYield,
+    Invest,
+    ScoreTax,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
@@ -55,6 +148,222 @@ pub struct GameEvent {
 pub enum GameEventKind {
     Random(RandomEvent),
     StartingHand(StartingHandEvent),
+    LastStand(LastStandEvent),
+    FeedSabotage(FeedSabotageEvent),
+    Damage(DamageEvent),
+    CardDied(CardDiedEvent),
+    Posted(PostedEvent),
+    ExploitCast(ExploitCastEvent),
+    Started(GameStartedEvent),
+    TurnResolved(TurnResolvedEvent),
+    DrawChosen(DrawChosenEvent),
+    HandDiscarded(HandDiscardedEvent),
+    Milled(MilledEvent),
+}
+
+// Everything `build_game` needs to rebuild the opening position, apart from the seed. Cosmetics
+// are left out so peers with different presentation still log the same event.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct GameStartedEvent {
+    pub host_node: String,
+    pub opponent_node: String,
+    pub host_deck: Vec<String>,
+    pub opponent_deck: Vec<String>,
+    pub first_instance: u64,
+    pub options: GameOptions,
+}
+
+// The commits as they stood when the turn resolved, plus the state hash right after it.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct TurnResolvedEvent {
+    pub turn: u32,
+    pub stakes: u8,
+    pub host_commit: Option<TurnCommit>,
+    pub opponent_commit: Option<TurnCommit>,
+    pub state_hash: String,
+}
+
+// `amount` is the Virality actually lost, after protection and shields.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct DamageEvent {
+    pub turn: u32,
+    pub target: String,
+    pub amount: i32,
+}
+
+// `zone` is where the card was when it died.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct CardDiedEvent {
+    pub turn: u32,
+    pub instance_id: String,
+    pub owner: Seat,
+    pub zone: Location,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct PostedEvent {
+    pub turn: u32,
+    pub seat: Seat,
+    pub instance_id: String,
+    pub slot: usize,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct ExploitCastEvent {
+    pub turn: u32,
+    pub seat: Seat,
+    pub card_id: String,
+    pub effect: ExploitEffect,
+}
+
+// `before`/`after` list the opponent's feed cards top-to-bottom in the slots they occupied.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct FeedSabotageEvent {
+    pub turn: u32,
+    pub seat: Seat,
+    pub slots: Vec<usize>,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct DrawChosenEvent {
+    pub turn: u32,
+    pub seat: Seat,
+    pub keep: String,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct HandDiscardedEvent {
+    pub turn: u32,
+    pub seat: Seat,
+    pub keep: Vec<String>,
+}
+
+// `card_ids` are the milled cards, top of `seat`'s deck first.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct MilledEvent {
+    pub turn: u32,
+    pub seat: Seat,
+    pub card_ids: Vec<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct LastStandEvent {
+    pub turn: u32,
+    pub seat: Seat,
+    pub card_id: String,
+}
+
+// One reason a plan would be rejected or partly skipped, with the rendered message for display.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub error: GameError,
+    pub message: String,
+}
+
+impl From<GameError> for Diagnostic {
+    fn from(error: GameError) -> Self {
+        Diagnostic {
+            message: error.to_string(),
+            error,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct ResolutionEntry {
+    pub seat: Seat,
+    pub card_id: String,
+    pub step: ResolutionStep,
+    pub reason: ResolutionReason,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum ResolutionStep {
+    Exploit,
+    Post,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum ResolutionReason {
+    Initiative,
+    Virality,
+    InitiativeTiebreak,
+}
+
+// The board as peers must agree on it. Bookkeeping that only grows (events, score log, RNG
+// history), in-flight commits and cosmetics stay out so they can't cause spurious mismatches.
+#[derive(Serialize)]
+struct HashableState<'a> {
+    turn: u32,
+    phase: &'a Phase,
+    initiative: &'a Seat,
+    stakes: u8,
+    winner: &'a Option<Seat>,
+    outcome: &'a Option<GameOutcome>,
+    domination_streak: [u8; 2],
+    feed: &'a [CardInstance],
+    players: Vec<HashablePlayer<'a>>,
+}
+
+#[derive(Serialize)]
+struct HashablePlayer<'a> {
+    seat: &'a Seat,
+    deck: &'a [CardInstance],
+    hand: &'a [CardInstance],
+    kitchen: &'a [CardInstance],
+    abyss: &'a [CardInstance],
+    mana: u8,
+    max_mana: u8,
+    score: i32,
+    cost_discount: i32,
+    mana_tax_next: i32,
+    overload_next: u8,
+    score_tax_used: i32,
+    feed_locked: bool,
+    pinned_cards: &'a [String],
+    pending_draw: &'a [CardInstance],
+    last_stand_used: bool,
+    action_points: Option<u8>,
+}
+
+impl<'a> From<&'a GameState> for HashableState<'a> {
+    fn from(game: &'a GameState) -> Self {
+        HashableState {
+            turn: game.turn,
+            phase: &game.phase,
+            initiative: &game.initiative,
+            stakes: game.stakes,
+            winner: &game.winner,
+            outcome: &game.outcome,
+            domination_streak: game.domination_streak,
+            feed: &game.feed,
+            players: game
+                .players
+                .iter()
+                .map(|p| HashablePlayer {
+                    seat: &p.seat,
+                    deck: &p.deck,
+                    hand: &p.hand,
+                    kitchen: &p.kitchen,
+                    abyss: &p.abyss,
+                    mana: p.mana,
+                    max_mana: p.max_mana,
+                    score: p.score,
+                    cost_discount: p.cost_discount,
+                    mana_tax_next: p.mana_tax_next,
+                    overload_next: p.overload_next,
+                    score_tax_used: p.score_tax_used,
+                    feed_locked: p.feed_locked,
+                    pinned_cards: &p.pinned_cards,
+                    pending_draw: &p.pending_draw,
+                    last_stand_used: p.last_stand_used,
+                    action_points: p.action_points,
+                })
+                .collect(),
+        }
+    }
 }
 
 impl GameState {
@@ -67,6 +376,13 @@ impl GameState {
         })
     }
 
+    pub fn action_points_for(&self, seat: &Seat) -> Option<u8> {
+        self.players
+            .iter()
+            .find(|p| &p.seat == seat)
+            .and_then(|p| p.action_points)
+    }
+
     pub fn player_node(&self, seat: &Seat) -> Option<String> {
         self.players
             .iter()
@@ -76,7 +392,7 @@ impl GameState {
 
     pub fn state_hash(&self) -> StateHash {
         let mut hasher = Sha256::new();
-        let data = serde_json::to_vec(self).unwrap_or_default();
+        let data = serde_json::to_vec(&HashableState::from(self)).unwrap_or_default();
         hasher.update(data);
         StateHash {
             turn: self.turn,
@@ -84,13 +400,112 @@ impl GameState {
         }
     }
 
+    // The copy shown to `viewer`'s clients. A node without a seat sees both hands face down.
+    pub fn client_view(&self, viewer: &str) -> GameState {
+        match self.players.iter().find(|player| player.node_id == viewer) {
+            Some(player) => self.redacted_for(&player.seat),
+            None => self.redacted(|_| true),
+        }
+    }
+
+    // The copy shown to `seat`. Decks and the other seat's hand are turned face down until a
+    // game both players agreed to disclose is over, along with the log entries and RNG seeds that
+    // would name those cards. Scry and Expose results only show to the seat that cast them.
+    pub fn redacted_for(&self, seat: &Seat) -> GameState {
+        self.redacted(|player| player.seat != *seat)
+    }
+
+    fn redacted(&self, is_other: impl Fn(&PlayerState) -> bool) -> GameState {
+        let disclosed = self.phase == Phase::GameOver && self.full_disclosure;
+        let exposed: Vec<String> = self
+            .players
+            .iter()
+            .filter(|p| !is_other(p))
+            .filter_map(|p| p.exposed_hand.clone())
+            .flatten()
+            .collect();
+        let mut view = self.clone();
+        for player in view.players.iter_mut() {
+            let other = is_other(player);
+            if other {
+                player.revealed_deck_top.clear();
+                player.exposed_hand = None;
+            }
+            if disclosed {
+                continue;
+            }
+            player.deck = player.deck.iter().map(face_down).collect();
+            if other {
+                player.hand = player
+                    .hand
+                    .iter()
+                    .map(|c| if exposed.contains(&c.instance_id) { c.clone() } else { face_down(c) })
+                    .collect();
+                player.pending_draw = player.pending_draw.iter().map(face_down).collect();
+            }
+        }
+        if disclosed {
+            return view;
+        }
+        let hidden: Vec<Seat> = self
+            .players
+            .iter()
+            .filter(|p| is_other(p))
+            .map(|p| p.seat.clone())
+            .collect();
+        // Blanked rather than dropped so event indices still line up with the full log.
+        for event in view.events.iter_mut() {
+            match &mut event.event {
+                GameEventKind::StartingHand(start) if hidden.contains(&start.seat) => {
+                    start.cycles.clear();
+                    start.chosen.clear();
+                }
+                GameEventKind::DrawChosen(choice) if hidden.contains(&choice.seat) => {
+                    choice.keep.clear();
+                }
+                GameEventKind::HandDiscarded(discard) if hidden.contains(&discard.seat) => {
+                    discard.keep.clear();
+                }
+                GameEventKind::Started(started) => {
+                    if hidden.contains(&Seat::Host) {
+                        started.host_deck.clear();
+                    }
+                    if hidden.contains(&Seat::Opponent) {
+                        started.opponent_deck.clear();
+                    }
+                }
+                _ => {}
+            }
+        }
+        // With the seeds and deck lists a client could replay both shuffles.
+        view.game_seed = 0;
+        view.rng.hide_seeds();
+        view
+    }
+
+    // Drops all but the newest `keep` events and RNG history entries. Only for views; the full
+    // log stays on the authoritative state and doesn't feed the state hash.
+    pub fn trim_history(&mut self, keep: usize) {
+        let excess = self.events.len().saturating_sub(keep);
+        self.events.drain(..excess);
+        let excess = self.rng.history.len().saturating_sub(keep);
+        self.rng.history.drain(..excess);
+    }
+
+    // Up to `limit` events starting at index `from` of the full log.
+    pub fn events_since(&self, from: usize, limit: usize) -> &[GameEvent] {
+        let start = from.min(self.events.len());
+        let end = start.saturating_add(limit).min(self.events.len());
+        &self.events[start..end]
+    }
+
     pub fn check_win_condition(&self) -> Option<Seat> {
         let host = self.players.iter().find(|p| p.seat == Seat::Host)?;
         let opp = self.players.iter().find(|p| p.seat == Seat::Opponent)?;
 
         // Check if either player has reached the winning score
-        let host_won = host.score >= SCORE_TO_WIN;
-        let opp_won = opp.score >= SCORE_TO_WIN;
+        let host_won = host.score >= self.score_to_win;
+        let opp_won = opp.score >= self.score_to_win;
 
         match (host_won, opp_won) {
             (true, true) => {
@@ -103,10 +518,62 @@ impl GameState {
             }
             (true, false) => Some(Seat::Host),
             (false, true) => Some(Seat::Opponent),
-            (false, false) => None,
+            (false, false) => self.domination_winner(),
         }
     }
 
+    fn domination_winner(&self) -> Option<Seat> {
+        let streak = match self.win_condition {
+            WinCondition::ScoreOnly => return None,
+            WinCondition::FeedDominationStreak(streak) => streak,
+            WinCondition::FullFeedControl => FULL_FEED_CONTROL_TURNS,
+        };
+        if streak == 0 {
+            return None;
+        }
+        [Seat::Host, Seat::Opponent]
+            .into_iter()
+            .find(|seat| self.domination_streak[streak_index(seat)] >= streak)
+    }
+
+    // The seat owning every occupied feed slot. An empty feed is dominated by no one.
+    pub fn feed_dominator(&self) -> Option<Seat> {
+        let owner = self.feed.first()?.owner.clone();
+        self.feed.iter().all(|c| c.owner == owner).then_some(owner)
+    }
+
+    pub(crate) fn update_domination_streak(&mut self) {
+        let dominator = match self.win_condition {
+            // A single lucky post into a half-empty feed doesn't count.
+            WinCondition::FullFeedControl if self.feed.len() < self.feed_size => None,
+            _ => self.feed_dominator(),
+        };
+        match dominator {
+            Some(seat) => {
+                let idx = streak_index(&seat);
+                self.domination_streak[idx] = self.domination_streak[idx].saturating_add(1);
+                self.domination_streak[1 - idx] = 0;
+            }
+            None => self.domination_streak = [0, 0],
+        }
+    }
+
+    // (current, max) mana for a seat. A revealed plan that hasn't resolved yet is already deducted,
+    // and so is any tax not yet folded in by reset_for_new_turn (a negative tax adds).
+    pub fn mana_for(&self, seat: &Seat) -> Result<(u8, u8), GameError> {
+        let player = self
+            .players
+            .iter()
+            .find(|p| &p.seat == seat)
+            .ok_or(GameError::SeatNotFound)?;
+        let pending = match player.commit.as_ref().and_then(|c| c.revealed.as_ref()) {
+            Some(plan) if self.phase != Phase::GameOver => plan_mana_cost(player, plan)?,
+            _ => 0,
+        };
+        let current = (player.mana as i32 - pending - player.mana_tax_next).clamp(0, u8::MAX as i32) as u8;
+        Ok((current, player.max_mana))
+    }
+
     pub fn plan_for(&self, seat: Seat) -> Option<TurnPlan> {
         self.players
             .iter()
@@ -115,15 +582,29 @@ impl GameState {
             .and_then(|c| c.revealed.clone())
     }
 
-    pub fn record_commit(&mut self, seat: Seat, hash: String) -> Result<(), String> {
+    pub fn record_commit(&mut self, seat: Seat, hash: String) -> Result<(), GameError> {
         if self.phase == Phase::GameOver {
-            return Err("game is over".into());
+            return Err(GameError::GameOver);
+        }
+        if self.phase == Phase::Draft {
+            return Err(GameError::DraftPending);
+        }
+        if self.phase == Phase::Discard {
+            return Err(GameError::DiscardPending);
         }
         let player = self
             .players
             .iter_mut()
             .find(|p| p.seat == seat)
-            .ok_or("seat not found")?;
+            .ok_or(GameError::SeatNotFound)?;
+        // A retransmitted commit must not wipe a reveal that arrived in between.
+        if let Some(existing) = player.commit.as_ref().filter(|c| c.turn == self.turn) {
+            return if existing.hash == hash {
+                Ok(())
+            } else {
+                Err(GameError::CommitConflict)
+            };
+        }
         player.commit = Some(TurnCommit {
             hash,
             salt: None,
@@ -136,23 +617,55 @@ impl GameState {
         Ok(())
     }
 
-    pub fn record_reveal(&mut self, seat: Seat, plan: TurnPlan, salt: String) -> Result<(), String> {
+    pub fn retract_commit(&mut self, seat: Seat) -> Result<(), GameError> {
+        if self.phase != Phase::Commit {
+            return Err(GameError::NotCommitPhase);
+        }
+        let opponent_committed = self
+            .players
+            .iter()
+            .find(|p| p.seat == seat.other())
+            .map(|p| p.commit.is_some())
+            .unwrap_or(false);
+        if opponent_committed {
+            return Err(GameError::OpponentAlreadyCommitted);
+        }
+        let player = self
+            .players
+            .iter_mut()
+            .find(|p| p.seat == seat)
+            .ok_or(GameError::SeatNotFound)?;
+        if player.commit.is_none() {
+            return Err(GameError::NoCommitToRetract);
+        }
+        player.commit = None;
+        Ok(())
+    }
+
+    pub fn record_reveal(&mut self, seat: Seat, plan: TurnPlan, salt: String) -> Result<(), GameError> {
         if self.phase == Phase::GameOver {
-            return Err("game is over".into());
+            return Err(GameError::GameOver);
         }
-        let expected_hash = commitment_for(&plan, &salt);
+        validate_plan(&plan, self.action_points_for(&seat))?;
+        let expected_hash = commitment_for(&plan, &salt, self.turn, self.game_seed);
+        // A plan can pass validation and still fail during resolution (a card no longer in hand,
+        // too little mana), so the reveal is only kept if the turn it completes resolves.
+        let saved = self.clone();
         {
             let player = self
                 .players
                 .iter_mut()
                 .find(|p| p.seat == seat)
-                .ok_or("seat not found")?;
+                .ok_or(GameError::SeatNotFound)?;
             if let Some(commit) = &player.commit {
                 if commit.turn != self.turn {
-                    return Err("commit turn mismatch".into());
+                    return Err(GameError::TurnMismatch(TurnParams {
+                        expected: self.turn,
+                        got: commit.turn,
+                    }));
                 }
                 if commit.hash != expected_hash {
-                    return Err("commit hash mismatch".into());
+                    return Err(GameError::CommitHashMismatch);
                 }
             }
             player.commit = Some(TurnCommit {
@@ -162,10 +675,14 @@ impl GameState {
                 turn: self.turn,
             });
         }
-        self.resolve_if_ready()
+        let result = self.resolve_if_ready();
+        if result.is_err() {
+            *self = saved;
+        }
+        result
     }
 
-    pub fn resolve_if_ready(&mut self) -> Result<(), String> {
+    fn resolve_if_ready(&mut self) -> Result<(), GameError> {
         if self.ready_to_resolve() {
             let host_plan = self.plan_for(Seat::Host).unwrap_or_default();
             let opp_plan = self.plan_for(Seat::Opponent).unwrap_or_default();
@@ -184,10 +701,10 @@ impl GameState {
         Ok(())
     }
 
-    pub fn call_based(&mut self, seat: Seat) -> Result<(), String> {
+    pub fn call_based(&mut self, seat: Seat) -> Result<(), GameError> {
         let caller = self
             .player_node(&seat)
-            .ok_or_else(|| "seat not found".to_string())?;
+            .ok_or(GameError::SeatNotFound)?;
         if let Some(existing) = &self.pending_stakes {
             if existing != &caller {
                 self.stakes = self.stakes.saturating_mul(2).max(1);
@@ -203,10 +720,11 @@ impl GameState {
         Ok(())
     }
 
-    pub fn accept_based(&mut self, _seat: Seat) -> Result<(), String> {
+    pub fn accept_based(&mut self, _seat: Seat) -> Result<(), GameError> {
         if self.pending_stakes.is_none() {
-            return Err("no pending stakes to accept".into());
+            return Err(GameError::NoPendingStakes);
         }
+        let saved = self.clone();
         self.stakes = self.stakes.saturating_mul(2).max(1);
         self.pending_stakes = None;
         // After accepting BASED, resolve the turn if both have revealed
@@ -214,23 +732,57 @@ impl GameState {
             let host_plan = self.plan_for(Seat::Host).unwrap_or_default();
             let opp_plan = self.plan_for(Seat::Opponent).unwrap_or_default();
             self.phase = Phase::Resolving;
-            self.resolve_turn(host_plan, opp_plan)?;
+            if let Err(err) = self.resolve_turn(host_plan, opp_plan) {
+                *self = saved;
+                return Err(err);
+            }
+        } else if self.phase != Phase::GameOver {
+            self.phase = Phase::Commit;
+        }
+        Ok(())
+    }
+
+    // Withdraws our own BASED call before the opponent answers it. Stakes stay as they were; if
+    // both plans are already revealed the turn resolves as if nobody had called.
+    pub fn cancel_based(&mut self, seat: Seat) -> Result<(), GameError> {
+        let caller = self
+            .player_node(&seat)
+            .ok_or(GameError::SeatNotFound)?;
+        match &self.pending_stakes {
+            None => return Err(GameError::NoPendingStakes),
+            Some(existing) if *existing != caller => return Err(GameError::NotStakeCaller),
+            Some(_) => {}
+        }
+        let saved = self.clone();
+        self.pending_stakes = None;
+        if self.ready_to_resolve() {
+            let host_plan = self.plan_for(Seat::Host).unwrap_or_default();
+            let opp_plan = self.plan_for(Seat::Opponent).unwrap_or_default();
+            self.phase = Phase::Resolving;
+            if let Err(err) = self.resolve_turn(host_plan, opp_plan) {
+                *self = saved;
+                return Err(err);
+            }
         } else if self.phase != Phase::GameOver {
             self.phase = Phase::Commit;
         }
         Ok(())
     }
 
-    pub fn fold_based(&mut self, seat: Seat) -> Result<(), String> {
+    pub fn fold_based(&mut self, seat: Seat) -> Result<(), GameError> {
         if self.pending_stakes.is_none() {
-            return Err("no pending stakes to fold".into());
+            return Err(GameError::NoPendingStakes);
         }
         self.pending_stakes = None;
-        self.phase = Phase::GameOver;
-        self.winner = Some(seat.other());
+        self.finish(GameOutcome::Folded(seat));
         Ok(())
     }
 
+    // Debug builds only: jump straight to a stakes multiplier without the BASED exchange.
+    pub(crate) fn override_stakes(&mut self, stakes: u8) {
+        self.stakes = stakes.clamp(1, MAX_STAKES);
+    }
+
     /// Process BASED calls from both players after reveals.
     /// If both called: double stakes. If one called: set pending_stakes.
     fn process_based_calls(&mut self, host_based: bool, opp_based: bool) {
@@ -255,88 +807,534 @@ impl GameState {
         }
     }
 
-    pub fn resolve_turn(&mut self, host_plan: TurnPlan, opponent_plan: TurnPlan) -> Result<(), String> {
-        self.phase = Phase::Resolving;
-        self.apply_turn_for_seat(Seat::Host, host_plan.clone())?;
-        self.apply_turn_for_seat(Seat::Opponent, opponent_plan.clone())?;
-        let initiative = self.initiative.clone();
-        self.resolve_exploits(&initiative, &host_plan, &opponent_plan)?;
-        self.resolve_posts(&host_plan.posts, &opponent_plan.posts)?;
-        self.apply_feed_yield();
-        self.apply_cook_and_decay();
-        self.cleanup_board();
+    // Read-only preview of how resolve_turn would sequence exploits and posts right now. Posts go
+    // through the same combo bonus and ordering as resolve_posts, but at current virality, so
+    // exploits that change virality can still reshuffle them.
+    pub fn resolution_order(&self, host_plan: &TurnPlan, opponent_plan: &TurnPlan) -> Vec<ResolutionEntry> {
+        let mut entries = vec![];
+        for (seat, plan) in exploit_order(&self.initiative, host_plan, opponent_plan) {
+            for exploit in plan.exploits.iter() {
+                entries.push(ResolutionEntry {
+                    seat: seat.clone(),
+                    card_id: exploit.card_id.clone(),
+                    step: ResolutionStep::Exploit,
+                    reason: ResolutionReason::Initiative,
+                });
+            }
+        }
+        let mut posts: Vec<(Seat, CardInstance)> = vec![];
+        for (seat, plan) in [(Seat::Host, host_plan), (Seat::Opponent, opponent_plan)] {
+            let Some(player) = self.players.iter().find(|p| p.seat == seat) else {
+                continue;
+            };
+            for post in plan.posts.iter() {
+                if posts.iter().any(|(_, c)| c.instance_id == post.card_id) {
+                    continue;
+                }
+                if let Some(card) = player
+                    .kitchen
+                    .iter()
+                    .find(|c| c.instance_id == post.card_id)
+                    .filter(|c| can_post(c, self.turn))
+                {
+                    posts.push((seat.clone(), card.clone()));
+                }
+            }
+        }
+        if self.feed_lock_active() {
+            posts.clear();
+        }
+        let posts = self.ordered_posts(posts);
+        for (idx, (seat, card)) in posts.iter().enumerate() {
+            let tied = posts.iter().enumerate().any(|(other_idx, (_, other))| {
+                other_idx != idx && other.current_virality == card.current_virality
+            });
+            entries.push(ResolutionEntry {
+                seat: seat.clone(),
+                card_id: card.instance_id.clone(),
+                step: ResolutionStep::Post,
+                reason: if tied {
+                    ResolutionReason::InitiativeTiebreak
+                } else {
+                    ResolutionReason::Virality
+                },
+            });
+        }
+        entries
+    }
+
+    pub fn resolve_turn(&mut self, host_plan: TurnPlan, opponent_plan: TurnPlan) -> Result<(), GameError> {
+        if self.phase == Phase::Draft {
+            return Err(GameError::DraftPending);
+        }
+        if self.phase == Phase::Discard {
+            return Err(GameError::DiscardPending);
+        }
+        let mut record = TurnResolvedEvent {
+            turn: self.turn,
+            stakes: self.stakes,
+            host_commit: self.commit_for(&Seat::Host),
+            opponent_commit: self.commit_for(&Seat::Opponent),
+            state_hash: String::new(),
+        };
+        // Resolve on a copy so an error partway through leaves this state, including next_instance
+        // and the RNG history, exactly as it was.
+        let mut resolved = self.clone();
+        let plans = [(Seat::Host, host_plan.clone()), (Seat::Opponent, opponent_plan.clone())];
+        resolved.resolve_turn_steps(host_plan, opponent_plan)?;
+        resolved.record_plans(record.turn, plans);
+        // Hash before logging the event so the recorded hash never has to cover itself.
+        record.state_hash = resolved.state_hash().hash;
+        resolved.push_event(GameEventKind::TurnResolved(record));
+        *self = resolved;
+        Ok(())
+    }
+
+    fn record_plans(&mut self, turn: u32, plans: [(Seat, TurnPlan); 2]) {
+        self.plan_history
+            .extend(plans.into_iter().map(|(seat, plan)| (turn, seat, plan)));
+        if self.plan_history.len() > MAX_PLAN_HISTORY {
+            let excess = self.plan_history.len() - MAX_PLAN_HISTORY;
+            self.plan_history.drain(..excess);
+        }
+    }
+
+    fn commit_for(&self, seat: &Seat) -> Option<TurnCommit> {
+        self.players
+            .iter()
+            .find(|p| &p.seat == seat)
+            .and_then(|p| p.commit.clone())
+    }
+
+    // Rebuilds a match from its event log by re-resolving every logged turn, checking the state
+    // hash after each one. The seats come from the log, so any node can replay any match.
+    pub fn replay_from(seed: u64, events: &[GameEvent]) -> Result<GameState, GameError> {
+        let started = events
+            .iter()
+            .find_map(|e| match &e.event {
+                GameEventKind::Started(started) => Some(started.clone()),
+                _ => None,
+            })
+            .ok_or(GameError::ReplayMissingStart)?;
+        let mut next_instance = started.first_instance;
+        let mut game = GameState::hosted_lobby(
+            started.host_node.clone(),
+            started.opponent_node.clone(),
+            &started.options,
+        );
+        game.start(
+            &build_catalog(),
+            &mut next_instance,
+            seed,
+            started.host_deck.clone(),
+            started.opponent_deck.clone(),
+            started.options.clone(),
+        )?;
+        if let Some(GameEventKind::Started(logged)) = game.events.first_mut().map(|e| &mut e.event) {
+            *logged = started;
+        }
+        for event in events {
+            if let GameEventKind::DrawChosen(choice) = &event.event {
+                game.choose_draw(&choice.keep)?;
+                continue;
+            }
+            if let GameEventKind::HandDiscarded(discard) = &event.event {
+                game.discard_to_hand_size(&discard.keep)?;
+                continue;
+            }
+            let GameEventKind::TurnResolved(turn) = &event.event else {
+                continue;
+            };
+            if game.phase == Phase::GameOver || game.turn != turn.turn {
+                return Err(GameError::TurnMismatch(TurnParams {
+                    expected: game.turn,
+                    got: turn.turn,
+                }));
+            }
+            game.stakes = turn.stakes;
+            game.pending_stakes = None;
+            let mut plans = vec![];
+            for (seat, commit) in [(Seat::Host, &turn.host_commit), (Seat::Opponent, &turn.opponent_commit)] {
+                plans.push(
+                    commit
+                        .as_ref()
+                        .and_then(|c| c.revealed.clone())
+                        .unwrap_or_default(),
+                );
+                let (player, _) = split_players_mut(&mut game.players, &seat);
+                player.commit = commit.clone();
+            }
+            let opponent_plan = plans.pop().unwrap_or_default();
+            let host_plan = plans.pop().unwrap_or_default();
+            game.resolve_turn(host_plan, opponent_plan)?;
+            if game.state_hash_after(turn.turn) != Some(turn.state_hash.as_str()) {
+                return Err(GameError::StateHashMismatch);
+            }
+        }
+        Ok(game)
+    }
+
+    // The hash logged when `turn` resolved, if it has.
+    pub fn state_hash_after(&self, turn: u32) -> Option<&str> {
+        self.events.iter().rev().find_map(|e| match &e.event {
+            GameEventKind::TurnResolved(resolved) if resolved.turn == turn => {
+                Some(resolved.state_hash.as_str())
+            }
+            _ => None,
+        })
+    }
+
+    fn resolve_turn_steps(&mut self, host_plan: TurnPlan, opponent_plan: TurnPlan) -> Result<(), GameError> {
+        self.phase = Phase::Resolving;
+        self.passed_last_turn = [plan_action_count(&host_plan) == 0, plan_action_count(&opponent_plan) == 0];
+        self.reseed_from_reveals();
+        let host_charges = self.apply_turn_for_seat(Seat::Host, host_plan.clone())?;
+        let opponent_charges = self.apply_turn_for_seat(Seat::Opponent, opponent_plan.clone())?;
+        // Last turn's Expose has had its turn; a fresh cast below replaces it.
+        for player in self.players.iter_mut() {
+            player.exposed_hand = None;
+        }
+        let initiative = self.initiative.clone();
+        self.resolve_exploits(
+            &initiative,
+            &host_plan,
+            &opponent_plan,
+            (&host_charges, &opponent_charges),
+        )?;
+        self.resolve_posts(&host_plan.posts, &opponent_plan.posts)?;
+        self.apply_feed_yield();
+        self.apply_cook_and_decay();
+        self.apply_feed_turn_end();
+        self.cleanup_board();
+        self.update_domination_streak();
+
+        // Check for win condition
+        if let Some(winner) = self.check_win_condition() {
+            self.finish(GameOutcome::Win(winner));
+            return Ok(());
+        }
+        // Turns count from 0, so this is the last one once turn + 1 reaches the limit.
+        if self.rules.max_turns.is_some_and(|max| self.turn + 1 >= max) {
+            self.finish(GameOutcome::TurnLimit(self.score_leader()));
+            return Ok(());
+        }
+
+        self.turn += 1;
+        self.initiative = self.initiative.other();
+        for player in self.players.iter_mut() {
+            player.commit = None;
+            player.reset_for_new_turn(&self.rules, &self.action_budget);
+            if self.rules.draft_draw {
+                player.draw_for_draft();
+            } else if self.rules.discard_phase {
+                player.draw_past_hand_size();
+            } else {
+                player.draw_card()?;
+            }
+        }
+        if let Some(outcome) = self.stalemate_outcome() {
+            self.finish(outcome);
+            return Ok(());
+        }
+        self.phase = if self.players.iter().any(|p| !p.pending_draw.is_empty()) {
+            Phase::Draft
+        } else {
+            self.after_draw_phase()
+        };
+        Ok(())
+    }
+
+    fn after_draw_phase(&self) -> Phase {
+        if self.players.iter().any(|p| p.hand.len() > MAX_HAND_SIZE) {
+            Phase::Discard
+        } else {
+            Phase::Commit
+        }
+    }
+
+    // Node of the seat holding `card_id` in hand; only that node may trim the hand.
+    pub fn hand_holder(&self, card_id: &str) -> Option<&str> {
+        self.players
+            .iter()
+            .find(|p| p.hand.iter().any(|c| c.instance_id == card_id))
+            .map(|p| p.node_id.as_str())
+    }
+
+    // Keeps exactly MAX_HAND_SIZE of an over-full hand and sends the rest to the abyss. The seat is
+    // whichever one holds the kept cards; play moves on once no hand is over the limit.
+    pub fn discard_to_hand_size(&mut self, keep: &[String]) -> Result<Seat, GameError> {
+        if self.phase != Phase::Discard {
+            return Err(GameError::NotDiscardPhase);
+        }
+        let first = keep.first().ok_or(GameError::InvalidDiscard)?;
+        let player = self
+            .players
+            .iter_mut()
+            .find(|p| p.hand.iter().any(|c| c.instance_id == *first))
+            .ok_or_else(|| GameError::UnknownCard(first.to_string()))?;
+        if player.hand.len() <= MAX_HAND_SIZE {
+            return Err(GameError::NotDiscardPhase);
+        }
+        let mut unique = keep.to_vec();
+        unique.sort();
+        unique.dedup();
+        if keep.len() != MAX_HAND_SIZE
+            || unique.len() != keep.len()
+            || !keep.iter().all(|id| player.hand.iter().any(|c| c.instance_id == *id))
+        {
+            return Err(GameError::InvalidDiscard);
+        }
+        let seat = player.seat.clone();
+        let (kept, discarded): (Vec<_>, Vec<_>) = std::mem::take(&mut player.hand)
+            .into_iter()
+            .partition(|c| keep.contains(&c.instance_id));
+        player.hand = kept;
+        for mut card in discarded {
+            card.location = Location::Abyss;
+            player.abyss.push(card);
+        }
+        self.push_event(GameEventKind::HandDiscarded(HandDiscardedEvent {
+            turn: self.turn,
+            seat: seat.clone(),
+            keep: keep.to_vec(),
+        }));
+        if self.players.iter().all(|p| p.hand.len() <= MAX_HAND_SIZE) {
+            self.phase = Phase::Commit;
+        }
+        Ok(seat)
+    }
+
+    // Node of the seat whose pending draw holds `card_id`; only that node may choose.
+    pub fn draw_chooser(&self, card_id: &str) -> Option<&str> {
+        self.players
+            .iter()
+            .find(|p| p.pending_draw.iter().any(|c| c.instance_id == card_id))
+            .map(|p| p.node_id.as_str())
+    }
+
+    // Keeps one of the drafted cards for whichever seat drew it and sends the rest to the abyss.
+    // Play moves on to the commit phase once neither seat has a choice left.
+    pub fn choose_draw(&mut self, keep: &str) -> Result<Seat, GameError> {
+        if self.phase != Phase::Draft {
+            return Err(GameError::NotDraftPhase);
+        }
+        let player = self
+            .players
+            .iter_mut()
+            .find(|p| p.pending_draw.iter().any(|c| c.instance_id == keep))
+            .ok_or_else(|| GameError::UnknownCard(keep.to_string()))?;
+        let seat = player.seat.clone();
+        for mut card in std::mem::take(&mut player.pending_draw) {
+            if card.instance_id == keep && player.hand.len() < MAX_HAND_SIZE {
+                card.location = Location::Hand;
+                player.hand.push(card);
+            } else {
+                card.location = Location::Abyss;
+                player.abyss.push(card);
+            }
+        }
+        self.push_event(GameEventKind::DrawChosen(DrawChosenEvent {
+            turn: self.turn,
+            seat: seat.clone(),
+            keep: keep.to_string(),
+        }));
+        if self.players.iter().all(|p| p.pending_draw.is_empty()) {
+            self.phase = self.after_draw_phase();
+        }
+        Ok(seat)
+    }
+
+    pub(crate) fn finish(&mut self, outcome: GameOutcome) {
+        self.phase = Phase::GameOver;
+        self.winner = match &outcome {
+            GameOutcome::Win(seat) => Some(seat.clone()),
+            GameOutcome::Draw => None,
+            GameOutcome::TurnLimit(seat) => seat.clone(),
+            GameOutcome::Folded(seat) | GameOutcome::Timeout(seat) => Some(seat.other()),
+            GameOutcome::Abandoned => None,
+        };
+        self.outcome = Some(outcome);
+    }
+
+    // With both decks and hands empty nobody can add to the board, so the higher score takes it.
+    fn stalemate_outcome(&self) -> Option<GameOutcome> {
+        if !self.players.iter().all(|p| p.deck.is_empty() && p.hand.is_empty()) {
+            return None;
+        }
+        Some(match self.score_leader() {
+            Some(seat) => GameOutcome::Win(seat),
+            None => GameOutcome::Draw,
+        })
+    }
+
+    // The seat with the higher score; `None` on a tie.
+    fn score_leader(&self) -> Option<Seat> {
+        let host = self.players.iter().find(|p| p.seat == Seat::Host)?;
+        let opp = self.players.iter().find(|p| p.seat == Seat::Opponent)?;
+        match host.score.cmp(&opp.score) {
+            std::cmp::Ordering::Greater => Some(Seat::Host),
+            std::cmp::Ordering::Less => Some(Seat::Opponent),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
 
-        // Check for win condition
-        if let Some(winner) = self.check_win_condition() {
-            self.winner = Some(winner);
-            self.phase = Phase::GameOver;
-            return Ok(());
+    // Revealed salts were committed before either side saw the other's, so together they make a
+    // fresh beacon neither player controlled. Local hot-seat turns have no salts and skip this.
+    fn reseed_from_reveals(&mut self) {
+        let salt_for = |seat: Seat| {
+            self.players
+                .iter()
+                .find(|p| p.seat == seat)
+                .and_then(|p| p.commit.as_ref())
+                .filter(|c| c.turn == self.turn)
+                .and_then(|c| c.salt.clone())
+        };
+        if let (Some(host), Some(opponent)) = (salt_for(Seat::Host), salt_for(Seat::Opponent)) {
+            self.rng.reseed(self.turn, &host, &opponent);
         }
+    }
 
-        self.turn += 1;
-        self.initiative = self.initiative.other();
-        for player in self.players.iter_mut() {
+    // Resolves `plan` for `seat` against an opponent who does nothing, on a copy. The copy's draws
+    // come from streams reseeded with PREVIEW_RNG_SALT rather than the real reveals, so anything
+    // random is only an estimate of what the committed turn will do.
+    pub fn preview_turn(&self, seat: &Seat, plan: TurnPlan) -> Result<GameState, GameError> {
+        match self.phase {
+            Phase::Commit | Phase::Reveal => {}
+            Phase::Draft => return Err(GameError::DraftPending),
+            Phase::Discard => return Err(GameError::DiscardPending),
+            Phase::GameOver => return Err(GameError::GameOver),
+            _ => return Err(GameError::NotCommitPhase),
+        }
+        let mut preview = self.clone();
+        for player in preview.players.iter_mut() {
             player.commit = None;
-            player.reset_for_new_turn();
-            player.draw_card()?;
         }
-        self.phase = Phase::Commit;
-        Ok(())
+        preview.rng.reseed(preview.turn, PREVIEW_RNG_SALT, PREVIEW_RNG_SALT);
+        let (host_plan, opponent_plan) = match seat {
+            Seat::Host => (plan, TurnPlan::default()),
+            Seat::Opponent => (TurnPlan::default(), plan),
+        };
+        preview.resolve_turn(host_plan, opponent_plan)?;
+        Ok(preview)
     }
 
-    fn apply_turn_for_seat(&mut self, seat: Seat, plan: TurnPlan) -> Result<(), String> {
-        {
-            let (player, _) = split_players_mut(&mut self.players, &seat);
-            if plan.plays_to_kitchen.len() > 1 {
-                return Err("only one meme can be played from hand to kitchen per turn".into());
+    // Every rule the plan would trip for `seat` right now, without touching state. Unlike the
+    // resolution path this doesn't stop at the first failure, and it also reports posts that
+    // resolution would silently skip (feed lock, summoning sickness).
+    pub fn diagnose(&self, seat: &Seat, plan: &TurnPlan) -> Vec<Diagnostic> {
+        let mut found: Vec<GameError> = vec![];
+        match self.phase {
+            Phase::GameOver => found.push(GameError::GameOver),
+            Phase::Commit | Phase::Reveal => {}
+            _ => found.push(GameError::NotCommitPhase),
+        }
+        let Some(player) = self.players.iter().find(|p| &p.seat == seat) else {
+            return vec![GameError::SeatNotFound.into()];
+        };
+        if let Err(err) = validate_plan(plan, player.action_points) {
+            found.push(err);
+        }
+        if plan.plays_to_kitchen.len() > 1 {
+            found.push(GameError::TooManyKitchenPlays);
+        }
+        for id in plan.plays_to_kitchen.iter() {
+            match player.hand.iter().find(|c| &c.instance_id == id) {
+                None => found.push(GameError::CardNotInHand(id.clone())),
+                Some(card) if !matches!(card.class, CardKind::Meme(_)) => found.push(GameError::NotAMeme),
+                Some(_) => {}
+            }
+        }
+        match plan_mana_cost(player, plan) {
+            Ok(cost) if cost > player.mana as i32 => found.push(GameError::InsufficientMana(ManaParams {
+                need: cost.min(u8::MAX as i32) as u8,
+                have: player.mana,
+            })),
+            Ok(_) => {}
+            Err(err) => found.push(err),
+        }
+        if !plan.posts.is_empty() && self.feed_lock_active() {
+            found.push(GameError::FeedLocked);
+        }
+        for post in plan.posts.iter() {
+            let id = &post.card_id;
+            if let Some(card) = player.kitchen.iter().find(|c| &c.instance_id == id) {
+                if !can_post(card, self.turn) {
+                    found.push(GameError::CannotPostYet(id.clone()));
+                }
+            } else if let Some(card) = player.hand.iter().find(|c| &c.instance_id == id) {
+                if !plan.plays_to_kitchen.contains(id) {
+                    found.push(GameError::CardNotInKitchen(id.clone()));
+                } else if !card.keywords.contains(&Keyword::Haste) {
+                    found.push(GameError::CannotPostYet(id.clone()));
+                }
+            } else {
+                found.push(GameError::CardNotInKitchen(id.clone()));
             }
-            let mut mana_spent = 0i32;
-            for id in plan.plays_to_kitchen.iter() {
-                let cost = card_cost(&player.hand, id, player.cost_discount)?;
-                mana_spent += cost as i32;
+        }
+        for exploit in plan.exploits.iter() {
+            if let Err(err) = self.validate_exploit_target_seat(seat, exploit) {
+                found.push(err);
             }
-            for exploit in plan.exploits.iter() {
-                let cost = card_cost(&player.hand, &exploit.card_id, player.cost_discount)?;
-                mana_spent += cost as i32;
+        }
+        found.into_iter().map(Diagnostic::from).collect()
+    }
+
+    // Checks the whole plan is affordable and charges it up front, except post costs, which
+    // resolve_posts charges once a post lands. Returns what each planned exploit cost, so a cast
+    // that can no longer happen can be refunded.
+    pub(crate) fn apply_turn_for_seat(&mut self, seat: Seat, plan: TurnPlan) -> Result<Vec<u8>, GameError> {
+        let charges = {
+            let (player, _) = split_players_mut(&mut self.players, &seat);
+            if plan.plays_to_kitchen.len() > 1 {
+                return Err(GameError::TooManyKitchenPlays);
             }
+            let mana_spent = plan_mana_cost(player, &plan)?;
             if mana_spent > player.mana as i32 {
-                return Err(format!(
-                    "{} insufficient mana: need {}, have {}",
-                    player.node_id, mana_spent, player.mana
-                ));
+                return Err(GameError::InsufficientMana(ManaParams {
+                    need: mana_spent.min(u8::MAX as i32) as u8,
+                    have: player.mana,
+                }));
             }
-            player.mana = player.mana.saturating_sub(mana_spent as u8);
-        }
+            let upfront = mana_spent - plan_post_cost(player, &plan);
+            player.mana = player.mana.saturating_sub(upfront as u8);
+            if let Some(points) = player.action_points {
+                player.action_points = Some(points.saturating_sub(plan_action_count(&plan).min(u8::MAX as usize) as u8));
+            }
+            plan.exploits
+                .iter()
+                .map(|e| card_cost(&player.hand, &e.card_id, player.effective_discount()))
+                .collect::<Result<Vec<u8>, GameError>>()?
+        };
         for id in plan.plays_to_kitchen.iter() {
             self.play_to_kitchen(&seat, id)?;
         }
         for exploit in plan.exploits.iter() {
             self.validate_exploit_target_seat(&seat, exploit)?;
         }
-        Ok(())
+        Ok(charges)
     }
 
-    fn validate_exploit_target_seat(&self, seat: &Seat, action: &ExploitAction) -> Result<(), String> {
+    pub(crate) fn validate_exploit_target_seat(&self, seat: &Seat, action: &ExploitAction) -> Result<(), GameError> {
         let player = self
             .players
             .iter()
             .find(|p| &p.seat == seat)
-            .ok_or("seat not found")?;
+            .ok_or(GameError::SeatNotFound)?;
         let opponent = self
             .players
             .iter()
             .find(|p| &p.seat == &seat.other())
-            .ok_or("opponent not found")?;
+            .ok_or(GameError::SeatNotFound)?;
         let card = player
             .hand
             .iter()
             .find(|c| c.instance_id == action.card_id)
-            .ok_or("exploit not in hand")?;
+            .ok_or_else(|| GameError::CardNotInHand(action.card_id.clone()))?;
 
         // Get the exploit effect to determine valid targets
         let effect = match &card.class {
             CardKind::Exploit(e) => e,
-            _ => return Err("card is not an exploit".into()),
+            _ => return Err(GameError::NotAnExploit),
         };
 
         // Validate target based on exploit effect type
@@ -349,16 +1347,16 @@ impl GameState {
 
                 if let Some(target) = target_in_kitchen {
                     if has_taunt(&opponent.kitchen) && !target.keywords.contains(&Keyword::Taunt) {
-                        return Err("must target taunt card first".into());
+                        return Err(GameError::MustTargetTaunt);
                     }
                     if target.keywords.contains(&Keyword::Stealth) {
-                        return Err("target is stealth".into());
+                        return Err(GameError::TargetStealthed);
                     }
                     Ok(())
                 } else if target_in_feed.is_some() {
                     Ok(())
                 } else {
-                    Err("target not found in enemy kitchen or feed".into())
+                    Err(GameError::InvalidTarget)
                 }
             }
             (ExploitEffect::Damage(_), Some(Target::EnemyKitchen)) => {
@@ -368,70 +1366,219 @@ impl GameState {
             (ExploitEffect::Damage(_), Some(Target::FeedSlot(slot))) => {
                 // Can target feed slot
                 if *slot >= self.feed.len() {
-                    return Err("invalid feed slot".into());
+                    return Err(GameError::InvalidFeedSlot);
                 }
                 Ok(())
             }
             (ExploitEffect::Damage(_), None) => {
-                return Err("damage exploit requires a target".into());
+                return Err(GameError::MissingTarget);
             }
 
             // Area damage targets enemy kitchen zone
             (ExploitEffect::AreaDamageKitchen(_), _) => {
-                // No specific target needed, targets all enemy kitchen
+                // No specific target needed, targets all enemy kitchen. Taunt doesn't apply to area
+                // effects; Stealth cards are skipped when it resolves.
                 Ok(())
             }
 
+            // ChainDamage picks its own targets when it resolves; at most the enemy kitchen zone is named
+            (ExploitEffect::ChainDamage(_), None | Some(Target::EnemyKitchen)) => Ok(()),
+            (ExploitEffect::ChainDamage(_), Some(_)) => Err(GameError::InvalidTarget),
+
             // Buff exploits target own cards
-            (ExploitEffect::Boost(_) | ExploitEffect::Protect | ExploitEffect::Double, Some(Target::Card(target_id))) => {
+            (ExploitEffect::Boost(_) | ExploitEffect::Heal(_) | ExploitEffect::Protect | ExploitEffect::Double, Some(Target::Card(target_id))) => {
                 // Must target own cards
                 let target_in_kitchen = player.kitchen.iter().find(|c| c.instance_id == *target_id);
                 let target_in_feed = self.feed.iter().find(|c| c.instance_id == *target_id && c.owner == *seat);
 
                 if target_in_kitchen.is_none() && target_in_feed.is_none() {
-                    Err("target not found in your kitchen or feed".into())
+                    Err(GameError::InvalidTarget)
                 } else {
                     Ok(())
                 }
             }
-            (ExploitEffect::Boost(_) | ExploitEffect::Protect | ExploitEffect::Double, None) => {
-                return Err("buff exploit requires a target".into());
+            (ExploitEffect::Boost(_) | ExploitEffect::Heal(_) | ExploitEffect::Protect | ExploitEffect::Double, None) => {
+                return Err(GameError::MissingTarget);
             }
 
+            // Invest buffs an own card like Boost, paid for with score
+            (ExploitEffect::Invest(params), Some(Target::Card(target_id))) => {
+                if player.score < params.cost_score {
+                    return Err(GameError::InsufficientScore(ScoreParams {
+                        need: params.cost_score,
+                        have: player.score,
+                    }));
+                }
+                let owned = player.kitchen.iter().any(|c| c.instance_id == *target_id)
+                    || self.feed.iter().any(|c| c.instance_id == *target_id && c.owner == *seat);
+                if owned {
+                    Ok(())
+                } else {
+                    Err(GameError::InvalidTarget)
+                }
+            }
+            (ExploitEffect::Invest(_), None) => Err(GameError::MissingTarget),
+            (ExploitEffect::Invest(_), Some(_)) => Err(GameError::InvalidTarget),
+
             // Debuff/removal exploits target enemy cards
-            (ExploitEffect::Debuff(_) | ExploitEffect::Execute | ExploitEffect::Silence, Some(Target::Card(target_id))) => {
+            (
+                ExploitEffect::Debuff(_) | ExploitEffect::Execute | ExploitEffect::Silence | ExploitEffect::Purge,
+                Some(Target::Card(target_id)),
+            ) => {
                 // Must target enemy cards
                 let target_in_kitchen = opponent.kitchen.iter().find(|c| c.instance_id == *target_id);
                 let target_in_feed = self.feed.iter().find(|c| c.instance_id == *target_id && c.owner == seat.other());
 
                 if let Some(target) = target_in_kitchen {
                     if has_taunt(&opponent.kitchen) && !target.keywords.contains(&Keyword::Taunt) {
-                        return Err("must target taunt card first".into());
+                        return Err(GameError::MustTargetTaunt);
                     }
                     if target.keywords.contains(&Keyword::Stealth) {
-                        return Err("target is stealth".into());
+                        return Err(GameError::TargetStealthed);
                     }
                     Ok(())
                 } else if target_in_feed.is_some() {
                     Ok(())
                 } else {
-                    Err("target not found in enemy kitchen or feed".into())
+                    Err(GameError::InvalidTarget)
                 }
             }
-            (ExploitEffect::Debuff(_) | ExploitEffect::Execute | ExploitEffect::Silence, None) => {
-                return Err("debuff/removal exploit requires a target".into());
+            (
+                ExploitEffect::Debuff(_) | ExploitEffect::Execute | ExploitEffect::Silence | ExploitEffect::Purge,
+                None,
+            ) => {
+                return Err(GameError::MissingTarget);
             }
 
             // Feed slot targeting exploits
             (ExploitEffect::PinSlot(_) | ExploitEffect::MoveUp(_) | ExploitEffect::NukeBelow(_), Some(Target::FeedSlot(slot))) => {
                 if *slot >= self.feed.len() {
-                    return Err("invalid feed slot".into());
+                    return Err(GameError::InvalidFeedSlot);
                 }
                 Ok(())
             }
             (ExploitEffect::PinSlot(_) | ExploitEffect::MoveUp(_) | ExploitEffect::NukeBelow(_), None) => {
-                return Err("feed manipulation exploit requires a target slot".into());
+                return Err(GameError::MissingTarget);
+            }
+
+            // SwapSlots carries its own slots and takes no target
+            (ExploitEffect::SwapSlots(params), None) => {
+                if params.a >= self.feed.len() || params.b >= self.feed.len() {
+                    return Err(GameError::InvalidFeedSlot);
+                }
+                Ok(())
+            }
+            (ExploitEffect::SwapSlots(_), Some(_)) => Err(GameError::InvalidTarget),
+
+            // Steal only takes enemy posts already in the feed
+            (ExploitEffect::Steal, Some(Target::Card(target_id))) => {
+                if self
+                    .feed
+                    .iter()
+                    .any(|c| c.instance_id == *target_id && c.owner == seat.other())
+                {
+                    Ok(())
+                } else {
+                    Err(GameError::InvalidTarget)
+                }
+            }
+            (ExploitEffect::Steal, None) => Err(GameError::MissingTarget),
+            (ExploitEffect::Steal, Some(_)) => Err(GameError::InvalidTarget),
+
+            // Demote, like Steal, only hits enemy posts already in the feed
+            (ExploitEffect::Demote, Some(Target::Card(target_id))) => {
+                if self
+                    .feed
+                    .iter()
+                    .any(|c| c.instance_id == *target_id && c.owner == seat.other())
+                {
+                    Ok(())
+                } else {
+                    Err(GameError::InvalidTarget)
+                }
+            }
+            (ExploitEffect::Demote, None) => Err(GameError::MissingTarget),
+            (ExploitEffect::Demote, Some(_)) => Err(GameError::InvalidTarget),
+
+            // Clone copies one of our own kitchen memes
+            (ExploitEffect::Clone, Some(Target::Card(target_id))) => {
+                if player.kitchen.iter().any(|c| c.instance_id == *target_id) {
+                    Ok(())
+                } else {
+                    Err(GameError::InvalidTarget)
+                }
+            }
+            (ExploitEffect::Clone, None) => Err(GameError::MissingTarget),
+            (ExploitEffect::Clone, Some(_)) => Err(GameError::InvalidTarget),
+
+            // Sacrifice spends one of our own kitchen memes
+            (ExploitEffect::Sacrifice(_), Some(Target::Card(target_id))) => {
+                if player.kitchen.iter().any(|c| c.instance_id == *target_id) {
+                    Ok(())
+                } else {
+                    Err(GameError::InvalidTarget)
+                }
+            }
+            (ExploitEffect::Sacrifice(_), None) => Err(GameError::MissingTarget),
+            (ExploitEffect::Sacrifice(_), Some(_)) => Err(GameError::InvalidTarget),
+
+            // CloneEnemy copies an enemy meme from their kitchen or the feed
+            (ExploitEffect::CloneEnemy, Some(Target::Card(target_id))) => {
+                let target_in_kitchen = opponent.kitchen.iter().find(|c| c.instance_id == *target_id);
+                let target_in_feed = self.feed.iter().find(|c| c.instance_id == *target_id && c.owner == seat.other());
+                let target = match (target_in_kitchen, target_in_feed) {
+                    (Some(target), _) => {
+                        if has_taunt(&opponent.kitchen) && !target.keywords.contains(&Keyword::Taunt) {
+                            return Err(GameError::MustTargetTaunt);
+                        }
+                        target
+                    }
+                    (None, Some(target)) => target,
+                    (None, None) => return Err(GameError::InvalidTarget),
+                };
+                if target.keywords.contains(&Keyword::Stealth) {
+                    return Err(GameError::TargetStealthed);
+                }
+                if !matches!(target.class, CardKind::Meme(_)) {
+                    return Err(GameError::NotAMeme);
+                }
+                Ok(())
+            }
+            (ExploitEffect::CloneEnemy, None) => Err(GameError::MissingTarget),
+            (ExploitEffect::CloneEnemy, Some(_)) => Err(GameError::InvalidTarget),
+
+            // Bounce returns an enemy kitchen meme to its owner's hand
+            (ExploitEffect::Bounce, Some(Target::Card(target_id))) => {
+                let Some(target) = opponent.kitchen.iter().find(|c| c.instance_id == *target_id) else {
+                    return Err(GameError::InvalidTarget);
+                };
+                if has_taunt(&opponent.kitchen) && !target.keywords.contains(&Keyword::Taunt) {
+                    return Err(GameError::MustTargetTaunt);
+                }
+                if target.keywords.contains(&Keyword::Stealth) {
+                    return Err(GameError::TargetStealthed);
+                }
+                if !matches!(target.class, CardKind::Meme(_)) {
+                    return Err(GameError::NotAMeme);
+                }
+                Ok(())
+            }
+            (ExploitEffect::Bounce, None) => Err(GameError::MissingTarget),
+            (ExploitEffect::Bounce, Some(_)) => Err(GameError::InvalidTarget),
+
+            // Reorders only the opponent's feed cards; no target picks the default order
+            (ExploitEffect::FeedSabotage, Some(Target::FeedOrder(order))) => {
+                self.validate_feed_order(seat, order)
+            }
+            (ExploitEffect::FeedSabotage, None) => Ok(()),
+            (ExploitEffect::FeedSabotage, Some(_)) => Err(GameError::InvalidTarget),
+
+            // Reorder needs an order for every feed slot
+            (ExploitEffect::Reorder, Some(Target::FeedOrder(order))) => {
+                self.validate_slot_order(&(0..self.feed.len()).collect::<Vec<_>>(), order)
             }
+            (ExploitEffect::Reorder, None) => Err(GameError::MissingTarget),
+            (ExploitEffect::Reorder, Some(_)) => Err(GameError::InvalidTarget),
 
             // Zone-targeting exploits (no specific target)
             (ExploitEffect::LockFeed | ExploitEffect::ShuffleFeed | ExploitEffect::WipeBottom(_), _) => {
@@ -440,13 +1587,20 @@ impl GameState {
             }
 
             // Self-targeting exploits (no target needed)
-            (ExploitEffect::ResurrectLast | ExploitEffect::DiscountNext | ExploitEffect::SpawnShitposts(_), _) => {
+            (ExploitEffect::ResurrectLast | ExploitEffect::DiscountNext | ExploitEffect::SpawnShitposts(_) | ExploitEffect::Draw(_) | ExploitEffect::Scry(_) | ExploitEffect::Expose, _) => {
                 // These don't need targets
                 Ok(())
             }
 
             // Opponent-targeting exploits (target opponent directly)
-            (ExploitEffect::Tax(_) | ExploitEffect::ManaBurn(_), _) => {
+            (
+                ExploitEffect::Tax(_)
+                | ExploitEffect::ManaBurn(_)
+                | ExploitEffect::Discard(_)
+                | ExploitEffect::Mill(_)
+                | ExploitEffect::ScoreTax(_),
+                _,
+            ) => {
                 // These target the opponent directly
                 Ok(())
             }
@@ -455,60 +1609,105 @@ impl GameState {
         }
     }
 
-    fn resolve_exploits(
+    pub(crate) fn resolve_exploits(
         &mut self,
         initiative: &Seat,
         host_plan: &TurnPlan,
         opponent_plan: &TurnPlan,
-    ) -> Result<(), String> {
-        let order = match initiative {
-            Seat::Host => vec![(Seat::Host, host_plan), (Seat::Opponent, opponent_plan)],
-            Seat::Opponent => vec![(Seat::Opponent, opponent_plan), (Seat::Host, host_plan)],
-        };
-        for (seat, plan) in order {
-            for exploit in plan.exploits.iter() {
+        charges: (&[u8], &[u8]),
+    ) -> Result<(), GameError> {
+        for (seat, plan) in exploit_order(initiative, host_plan, opponent_plan) {
+            let seat_charges = match seat {
+                Seat::Host => charges.0,
+                Seat::Opponent => charges.1,
+            };
+            for (idx, exploit) in plan.exploits.iter().enumerate() {
+                let (player, _) = split_players_mut(&mut self.players, &seat);
+                // Something earlier this turn took the card (or it was listed twice): refund it.
+                if !player.hand.iter().any(|c| c.instance_id == exploit.card_id) {
+                    let refund = seat_charges.get(idx).copied().unwrap_or(0);
+                    player.mana = player.mana.saturating_add(refund);
+                    continue;
+                }
                 self.cast_exploit(seat.clone(), exploit.clone())?;
             }
         }
         Ok(())
     }
 
-    fn cast_exploit(&mut self, seat: Seat, action: ExploitAction) -> Result<(), String> {
+    fn cast_exploit(&mut self, seat: Seat, action: ExploitAction) -> Result<(), GameError> {
         let (effect, mut card) = {
             let (player, _) = split_players_mut(&mut self.players, &seat);
             let card_idx = player
                 .hand
                 .iter()
                 .position(|c| c.instance_id == action.card_id)
-                .ok_or("exploit not found in hand")?;
+                .ok_or_else(|| GameError::CardNotInHand(action.card_id.clone()))?;
             let card = player.hand.remove(card_idx);
             player.cost_discount = 0;
+            *player
+                .plays_this_game
+                .entry(card.variant_id.clone())
+                .or_insert(0) += 1;
             match &card.class {
                 CardKind::Exploit(effect) => (effect.clone(), card),
-                _ => return Err("card is not an exploit".into()),
+                _ => return Err(GameError::NotAnExploit),
             }
         };
-        self.apply_exploit_effect(effect, &seat, action.target)?;
+        self.push_event(GameEventKind::ExploitCast(ExploitCastEvent {
+            turn: self.turn,
+            seat: seat.clone(),
+            card_id: card.instance_id.clone(),
+            effect: effect.clone(),
+        }));
+        self.damage_source = Some(card.variant_id.clone());
+        let applied = self.apply_exploit_effect(effect, &seat, action.target);
+        self.damage_source = None;
+        applied?;
         card.location = Location::Abyss;
         let (player, _) = split_players_mut(&mut self.players, &seat);
         player.abyss.push(card);
         Ok(())
     }
 
-    fn apply_exploit_effect(
+    pub(crate) fn apply_exploit_effect(
         &mut self,
         effect: ExploitEffect,
         seat: &Seat,
         target: Option<Target>,
-    ) -> Result<(), String> {
+    ) -> Result<(), GameError> {
         match effect {
             ExploitEffect::Damage(params) => {
                 self.apply_damage_targeted(seat, target.unwrap_or(params.target.clone()), params.amount)
             }
+            ExploitEffect::ChainDamage(params) => {
+                let targets = self.chain_targets(seat, params.targets);
+                for (hop, id) in targets.into_iter().enumerate() {
+                    let amount = params.amount - params.falloff * hop as i32;
+                    if amount <= 0 {
+                        break;
+                    }
+                    self.apply_damage_targeted(seat, Target::Card(id), amount)?;
+                }
+                Ok(())
+            }
             ExploitEffect::AreaDamageKitchen(amount) => {
+                // Stealth hides a card from area damage too; Taunt cards are hit like any other.
                 let (_, opp) = split_players_mut(&mut self.players, seat);
-                for card in opp.kitchen.iter_mut() {
-                    apply_damage(card, amount, false);
+                let mut hit = vec![];
+                let mut thorns = vec![];
+                for card in opp.kitchen.iter_mut().filter(|c| !c.keywords.contains(&Keyword::Stealth)) {
+                    let (_, dealt) = damage_and_measure(card, amount);
+                    hit.push(card.instance_id.clone());
+                    if dealt > 0 {
+                        thorns.extend(thorns_of(card));
+                    }
+                }
+                for id in hit {
+                    self.note_damage(&id);
+                }
+                for (variant, amount) in thorns {
+                    self.ricochet(seat, variant, amount);
                 }
                 Ok(())
             }
@@ -523,6 +1722,35 @@ impl GameState {
                 }
                 Ok(())
             }
+            ExploitEffect::Heal(amount) => {
+                let (player, _) = split_players_mut(&mut self.players, seat);
+                if let Some(Target::Card(id)) = target {
+                    if let Some(card) =
+                        find_card_mut_for_owner(&mut player.kitchen, &mut self.feed, seat, &id)
+                    {
+                        if card.current_virality < card.base_virality {
+                            card.current_virality = (card.current_virality + amount).min(card.base_virality);
+                        }
+                    }
+                }
+                Ok(())
+            }
+            ExploitEffect::Invest(params) => {
+                let Some(Target::Card(id)) = target else {
+                    return Ok(());
+                };
+                let (player, _) = split_players_mut(&mut self.players, seat);
+                // Score may have moved since validation; an unaffordable or stale Invest fizzles.
+                if player.score < params.cost_score {
+                    return Ok(());
+                }
+                let Some(card) = find_card_mut_for_owner(&mut player.kitchen, &mut self.feed, seat, &id) else {
+                    return Ok(());
+                };
+                card.current_virality += params.virality;
+                self.add_score(seat, -params.cost_score, ScoreReason::Invest);
+                Ok(())
+            }
             ExploitEffect::Debuff(amount) => {
                 let (_, opp) = split_players_mut(&mut self.players, seat);
                 if let Some(Target::Card(id)) = target {
@@ -559,6 +1787,7 @@ impl GameState {
             }
             ExploitEffect::Execute => {
                 if let Some(Target::Card(id)) = target {
+                    self.note_damage(&id);
                     let (_, opp) = split_players_mut(&mut self.players, seat);
                     if let Some(card) = remove_card(&mut opp.kitchen, &id) {
                         self.to_abyss(seat.other(), card);
@@ -579,8 +1808,11 @@ impl GameState {
                     Some(Target::FeedSlot(s)) => s,
                     _ => slot,
                 };
+                let Some(card_id) = self.feed.get(slot_to_pin).map(|c| c.instance_id.clone()) else {
+                    return Ok(());
+                };
                 let (_, opp) = split_players_mut(&mut self.players, seat);
-                opp.pinned_slots.push(slot_to_pin);
+                opp.pinned_cards.push(card_id);
                 Ok(())
             }
             ExploitEffect::MoveUp(slot) => {
@@ -590,6 +1822,7 @@ impl GameState {
                 };
                 self.shift_feed_up(slot_to_move)
             }
+            ExploitEffect::SwapSlots(params) => self.swap_feed_slots(params.a, params.b),
             ExploitEffect::LockFeed => {
                 let (_, opp) = split_players_mut(&mut self.players, seat);
                 opp.feed_locked = true;
@@ -612,10 +1845,177 @@ impl GameState {
                 opp.mana_tax_next += params.amount as i32;
                 Ok(())
             }
+            ExploitEffect::ScoreTax(amount) => {
+                let (me, opp) = split_players_mut(&mut self.players, seat);
+                let taken = amount.min(opp.score).min(MAX_SCORE_TAX - me.score_tax_used).max(0);
+                me.score_tax_used += taken;
+                if taken > 0 {
+                    self.add_score(&seat.other(), -taken, ScoreReason::ScoreTax);
+                }
+                Ok(())
+            }
             ExploitEffect::ShuffleFeed => {
                 self.fair_shuffle_feed();
                 Ok(())
             }
+            ExploitEffect::Steal => {
+                let Some(Target::Card(id)) = target else {
+                    return Ok(());
+                };
+                let Some(idx) = self
+                    .feed
+                    .iter()
+                    .position(|c| c.instance_id == id && c.owner == seat.other())
+                else {
+                    return Ok(());
+                };
+                // Removing first frees the slot, so the bottom insert never overflows the feed.
+                let mut card = self.feed.remove(idx);
+                card.owner = seat.clone();
+                self.feed.push(card);
+                self.reindex_feed();
+                Ok(())
+            }
+            ExploitEffect::Demote => {
+                let Some(Target::Card(id)) = target else {
+                    return Ok(());
+                };
+                let other = seat.other();
+                let Some(idx) = self.feed.iter().position(|c| c.instance_id == id && c.owner == other) else {
+                    return Ok(());
+                };
+                let card = self.feed.remove(idx);
+                self.reindex_feed();
+                let tokens = card.cost / 2;
+                self.to_abyss(other.clone(), card);
+                let Some(def) = find_definition("d06") else {
+                    return Ok(());
+                };
+                let spawned: Vec<CardInstance> = (0..tokens)
+                    .map(|_| self.new_instance_from_def(def, other.clone(), Location::Kitchen))
+                    .collect();
+                let (_, opponent) = split_players_mut(&mut self.players, seat);
+                opponent.kitchen.extend(spawned);
+                Ok(())
+            }
+            ExploitEffect::Clone => {
+                let Some(Target::Card(id)) = target else {
+                    return Ok(());
+                };
+                let (player, _) = split_players_mut(&mut self.players, seat);
+                let Some(def) = player
+                    .kitchen
+                    .iter()
+                    .find(|c| c.instance_id == id)
+                    .and_then(|c| find_definition(&c.variant_id))
+                else {
+                    return Ok(());
+                };
+                // A fresh instance: base virality, and it can't post until next turn.
+                let copy = self.new_instance_from_def(def, seat.clone(), Location::Kitchen);
+                let (player, _) = split_players_mut(&mut self.players, seat);
+                player.kitchen.push(copy);
+                Ok(())
+            }
+            ExploitEffect::Sacrifice(params) => {
+                let Some(Target::Card(id)) = target else {
+                    return Ok(());
+                };
+                let (player, _) = split_players_mut(&mut self.players, seat);
+                // Gone from the kitchen since validation: nothing to sacrifice, so no damage either.
+                let Some(card) = remove_card(&mut player.kitchen, &id) else {
+                    return Ok(());
+                };
+                let amount = card.current_virality.max(0) * params.damage;
+                self.to_abyss(seat.clone(), card);
+                self.apply_damage_targeted(seat, Target::EnemyKitchen, amount)
+            }
+            ExploitEffect::CloneEnemy => {
+                let Some(Target::Card(id)) = target else {
+                    return Ok(());
+                };
+                let other = seat.other();
+                let Some(def) = self
+                    .players
+                    .iter()
+                    .filter(|p| p.seat == other)
+                    .flat_map(|p| p.kitchen.iter())
+                    .chain(self.feed.iter().filter(|c| c.owner == other))
+                    .find(|c| c.instance_id == id && matches!(c.class, CardKind::Meme(_)))
+                    .and_then(|c| find_definition(&c.variant_id))
+                else {
+                    return Ok(());
+                };
+                // Base stats from the catalog, not the target's buffed virality.
+                let copy = self.new_instance_from_def(def, seat.clone(), Location::Kitchen);
+                let (player, _) = split_players_mut(&mut self.players, seat);
+                player.kitchen.push(copy);
+                Ok(())
+            }
+            ExploitEffect::Bounce => {
+                let Some(Target::Card(id)) = target else {
+                    return Ok(());
+                };
+                let (_, opponent) = split_players_mut(&mut self.players, seat);
+                let Some(idx) = opponent.kitchen.iter().position(|c| c.instance_id == id) else {
+                    return Ok(());
+                };
+                let mut card = opponent.kitchen.remove(idx);
+                card.current_virality = card.base_virality;
+                card.protected_until_end = false;
+                card.frozen_turns = 0;
+                card.played_turn = 0;
+                // Same overflow rule as drawing: a full hand discards the card.
+                if opponent.hand.len() >= MAX_HAND_SIZE {
+                    card.location = Location::Abyss;
+                    opponent.abyss.push(card);
+                } else {
+                    card.location = Location::Hand;
+                    opponent.hand.push(card);
+                }
+                self.last_damaged_by.remove(&id);
+                Ok(())
+            }
+            ExploitEffect::FeedSabotage => {
+                let order = match target {
+                    Some(Target::FeedOrder(order)) => order,
+                    _ => self.default_sabotage_order(seat),
+                };
+                // The feed may have shifted since reveal; a stale order fizzles.
+                if self.validate_feed_order(seat, &order).is_err() {
+                    return Ok(());
+                }
+                let slots = self.opponent_feed_slots(seat);
+                let cards: Vec<CardInstance> = slots.iter().map(|s| self.feed[*s].clone()).collect();
+                for (i, from) in order.iter().enumerate() {
+                    self.feed[slots[i]] = cards[*from].clone();
+                }
+                self.reindex_feed();
+                self.events.push(GameEvent {
+                    event: GameEventKind::FeedSabotage(FeedSabotageEvent {
+                        turn: self.turn,
+                        seat: seat.clone(),
+                        before: cards.iter().map(|c| c.instance_id.clone()).collect(),
+                        after: order.iter().map(|i| cards[*i].instance_id.clone()).collect(),
+                        slots,
+                    }),
+                });
+                Ok(())
+            }
+            ExploitEffect::Reorder => {
+                let Some(Target::FeedOrder(order)) = target else {
+                    return Ok(());
+                };
+                // As with FeedSabotage, an order the feed has outgrown since reveal fizzles.
+                let slots: Vec<usize> = (0..self.feed.len()).collect();
+                if self.validate_slot_order(&slots, &order).is_err() {
+                    return Ok(());
+                }
+                let cards = std::mem::take(&mut self.feed);
+                self.feed = order.iter().map(|i| cards[*i].clone()).collect();
+                self.reindex_feed();
+                Ok(())
+            }
             ExploitEffect::DiscountNext => {
                 let (player, _) = split_players_mut(&mut self.players, seat);
                 player.cost_discount = 1;
@@ -626,6 +2026,42 @@ impl GameState {
                 opp.mana = opp.mana.saturating_sub(params.amount);
                 Ok(())
             }
+            ExploitEffect::Discard(count) => {
+                let victim = seat.other();
+                for _ in 0..count {
+                    let hand_size = split_players_mut(&mut self.players, seat).1.hand.len();
+                    if hand_size == 0 {
+                        break;
+                    }
+                    let index =
+                        self.record_random(hand_size as u64, RandomEventKind::Discard(victim.clone())) as usize;
+                    let (_, opp) = split_players_mut(&mut self.players, seat);
+                    let mut card = opp.hand.remove(index);
+                    card.location = Location::Abyss;
+                    opp.abyss.push(card);
+                }
+                Ok(())
+            }
+            ExploitEffect::Mill(count) => {
+                let (_, opp) = split_players_mut(&mut self.players, seat);
+                // Whatever they scried is no longer on top.
+                opp.revealed_deck_top.clear();
+                let mut card_ids = vec![];
+                for _ in 0..count {
+                    let Some(mut card) = opp.deck.pop() else {
+                        break;
+                    };
+                    card.location = Location::Abyss;
+                    card_ids.push(card.instance_id.clone());
+                    opp.abyss.push(card);
+                }
+                self.push_event(GameEventKind::Milled(MilledEvent {
+                    turn: self.turn,
+                    seat: seat.other(),
+                    card_ids,
+                }));
+                Ok(())
+            }
             ExploitEffect::WipeBottom(count) => {
                 for _ in 0..count {
                     if let Some(card) = self.feed.pop() {
@@ -649,6 +2085,22 @@ impl GameState {
                 }
                 Ok(())
             }
+            ExploitEffect::Draw(count) => {
+                let (player, _) = split_players_mut(&mut self.players, seat);
+                for _ in 0..count {
+                    player.draw_card()?;
+                }
+                Ok(())
+            }
+            ExploitEffect::Scry(count) => {
+                self.scry(seat, count);
+                Ok(())
+            }
+            ExploitEffect::Expose => {
+                let (player, opponent) = split_players_mut(&mut self.players, seat);
+                player.exposed_hand = Some(opponent.hand.iter().map(|c| c.instance_id.clone()).collect());
+                Ok(())
+            }
             ExploitEffect::Silence => {
                 if let Some(Target::Card(id)) = target {
                     let (_, opp) = split_players_mut(&mut self.players, &seat);
@@ -661,41 +2113,109 @@ impl GameState {
                 }
                 Ok(())
             }
+            ExploitEffect::Purge => {
+                if let Some(Target::Card(id)) = target {
+                    let (_, opp) = split_players_mut(&mut self.players, seat);
+                    if let Some(card) =
+                        find_enemy_card_mut_for_owner(&mut opp.kitchen, &mut self.feed, seat, &id)
+                    {
+                        card.abilities.clear();
+                        card.keywords.clear();
+                        card.shield = 0;
+                    }
+                }
+                Ok(())
+            }
         }
     }
 
-    fn apply_damage_targeted(&mut self, seat: &Seat, target: Target, amount: i32) -> Result<(), String> {
-        match target {
+    pub(crate) fn apply_damage_targeted(&mut self, seat: &Seat, target: Target, amount: i32) -> Result<(), GameError> {
+        let hit = match target {
             Target::Card(id) => {
                 let (_, opp) = split_players_mut(&mut self.players, seat);
                 if let Some(card) = find_card_mut(&mut opp.kitchen, &id) {
-                    apply_damage(card, amount, false);
-                } else if let Some(card) = self
-                    .feed
-                    .iter_mut()
-                    .find(|c| c.instance_id == id && c.owner == seat.other())
-                {
-                    apply_damage(card, amount, false);
-                }
-                Ok(())
-            }
-            Target::FeedSlot(slot) => {
-                if let Some(card) = self.feed.get_mut(slot) {
-                    apply_damage(card, amount, false);
+                    Some(damage_and_measure(card, amount))
+                } else {
+                    self.feed
+                        .iter_mut()
+                        .find(|c| c.instance_id == id && c.owner == seat.other())
+                        .map(|card| damage_and_measure(card, amount))
                 }
-                Ok(())
             }
+            Target::FeedSlot(slot) => self
+                .feed
+                .get_mut(slot)
+                .map(|card| damage_and_measure(card, amount)),
             Target::AnyKitchen | Target::EnemyKitchen => {
                 let (_, opp) = split_players_mut(&mut self.players, seat);
-                if let Some(card) = opp.kitchen.first_mut() {
-                    apply_damage(card, amount, false);
-                }
-                Ok(())
+                opp.kitchen
+                    .first_mut()
+                    .map(|card| damage_and_measure(card, amount))
+            }
+            Target::FeedOrder(_) => None,
+        };
+        if let Some((target, dealt)) = hit {
+            self.note_damage(&target);
+            let thorns = if dealt > 0 {
+                self.feed
+                    .iter()
+                    .chain(self.players.iter().flat_map(|p| p.kitchen.iter()))
+                    .find(|c| c.instance_id == target)
+                    .and_then(thorns_of)
+            } else {
+                None
+            };
+            self.push_event(GameEventKind::Damage(DamageEvent {
+                turn: self.turn,
+                target,
+                amount: dealt,
+            }));
+            if let Some((variant, amount)) = thorns {
+                self.ricochet(seat, variant, amount);
             }
         }
+        Ok(())
+    }
+
+    // Enemy kitchen memes a chain hits, in order: Taunt cards first, then highest virality. Stealth
+    // cards are skipped; ties keep kitchen order.
+    fn chain_targets(&self, seat: &Seat, count: usize) -> Vec<String> {
+        let Some(opponent) = self.players.iter().find(|p| p.seat == seat.other()) else {
+            return vec![];
+        };
+        let mut candidates: Vec<&CardInstance> = opponent
+            .kitchen
+            .iter()
+            .filter(|c| !c.keywords.contains(&Keyword::Stealth))
+            .collect();
+        candidates.sort_by_key(|c| {
+            (
+                !c.keywords.contains(&Keyword::Taunt),
+                std::cmp::Reverse(c.current_virality),
+            )
+        });
+        candidates.into_iter().take(count).map(|c| c.instance_id.clone()).collect()
     }
 
-    fn resolve_posts(&mut self, host_posts: &[PostAction], opponent_posts: &[PostAction]) -> Result<(), String> {
+    // Thorns damage back to the attacker's top kitchen card. It doesn't trigger further Thorns.
+    fn ricochet(&mut self, attacker: &Seat, source: String, amount: i32) {
+        let (player, _) = split_players_mut(&mut self.players, attacker);
+        let Some((target, dealt)) = player
+            .kitchen
+            .first_mut()
+            .map(|card| damage_and_measure(card, amount))
+        else {
+            return;
+        };
+        self.last_damaged_by.insert(target.clone(), source);
+        self.push_event(GameEventKind::Damage(DamageEvent {
+            turn: self.turn,
+            target,
+            amount: dealt,
+        }));
+    }
+
+    pub(crate) fn resolve_posts(&mut self, host_posts: &[PostAction], opponent_posts: &[PostAction]) -> Result<(), GameError> {
         if self.feed_lock_active() {
             return Ok(());
         }
@@ -713,18 +2233,7 @@ impl GameState {
         if entries.is_empty() {
             return Ok(());
         }
-        entries.sort_by(|a, b| {
-            b.1.current_virality
-                .cmp(&a.1.current_virality)
-                .then_with(|| {
-                    if a.0 == self.initiative {
-                        std::cmp::Ordering::Less
-                    } else {
-                        std::cmp::Ordering::Greater
-                    }
-                })
-        });
-        for (seat, card) in entries {
+        for (seat, card) in self.ordered_posts(entries) {
             let mut target_index = if card.keywords.contains(&Keyword::Heavy) {
                 self.feed.len()
             } else {
@@ -744,10 +2253,22 @@ impl GameState {
                 }
             }
             let card_id = card.instance_id.clone();
+            self.card_stats.entry(card.variant_id.clone()).or_default().posted += 1;
             let insert_at = target_index.min(self.feed.len());
+            self.push_event(GameEventKind::Posted(PostedEvent {
+                turn: self.turn,
+                seat: seat.clone(),
+                instance_id: card_id.clone(),
+                slot: insert_at,
+            }));
+            // The post cost is only paid for a post that actually reaches the feed.
+            if let CardKind::Meme(meme) = &card.class {
+                let (player, _) = split_players_mut(&mut self.players, &seat);
+                player.mana = player.mana.saturating_sub(meme.post_cost);
+            }
             self.feed.insert(insert_at, card);
             self.apply_on_post_effects(&seat, card_id);
-            if self.feed.len() > FEED_SIZE {
+            if self.feed.len() > self.feed_size {
                 if let Some(removed) = self.feed.pop() {
                     let owner_seat = removed.owner.clone();
                     self.to_abyss(owner_seat, removed);
@@ -758,8 +2279,38 @@ impl GameState {
         Ok(())
     }
 
+    // The order resolve_posts plays a turn's posts in, combo bonuses included.
+    fn ordered_posts(&self, mut entries: Vec<(Seat, CardInstance)>) -> Vec<(Seat, CardInstance)> {
+        apply_combo_bonus(&mut entries);
+        self.order_posts(&mut entries);
+        entries
+    }
+
+    // Highest virality first. Ties go to the initiative seat's cards, then to the lower instance id
+    // (compared as strings), so peers agree on the order however the entries were gathered.
+    pub(crate) fn order_posts(&self, entries: &mut [(Seat, CardInstance)]) {
+        entries.sort_by(|a, b| {
+            b.1.current_virality
+                .cmp(&a.1.current_virality)
+                .then_with(|| (a.0 != self.initiative).cmp(&(b.0 != self.initiative)))
+                .then_with(|| a.1.instance_id.cmp(&b.1.instance_id))
+        });
+    }
+
+    pub(crate) fn scry(&mut self, seat: &Seat, count: u8) {
+        let (player, _) = split_players_mut(&mut self.players, seat);
+        player.revealed_deck_top = player
+            .deck
+            .iter()
+            .rev()
+            .take(count as usize)
+            .map(|c| c.variant_id.clone())
+            .collect();
+    }
+
     fn apply_on_post_effects(&mut self, seat: &Seat, instance_id: String) {
         let mut spawn_tasks: Vec<SpawnParams> = Vec::new();
+        let mut scry: Option<u8> = None;
         let mut gain_mana: u8 = 0;
         let mut ping_top: Option<i32> = None;
         let mut pending_swap = false;
@@ -800,7 +2351,8 @@ impl GameState {
                                 pending_knockback = Some(steps);
                             }
                         }
-                        AbilityEffect::BuffOtherKitchen(_) => {}
+                        AbilityEffect::BuffOtherKitchen(_) | AbilityEffect::CostAura(_) => {}
+                        AbilityEffect::Scry(count) => scry = Some(count),
                     }
                 }
                 if pending_swap {
@@ -847,6 +2399,10 @@ impl GameState {
             player.mana = player.mana.saturating_add(gain_mana);
         }
 
+        if let Some(count) = scry {
+            self.scry(seat, count);
+        }
+
         for (card_id, range) in pending_randomize {
             let bound = (range.max - range.min + 1).max(1) as u64;
             let roll = self
@@ -857,25 +2413,8 @@ impl GameState {
             }
         }
 
-        if !spawn_tasks.is_empty() {
-            for params in spawn_tasks {
-                for _ in 0..params.count {
-                    if let Some(def) = find_definition(&params.variant_id) {
-                        let target_location = match params.location {
-                            SpawnLocation::Kitchen => Location::Kitchen,
-                            SpawnLocation::Hand => Location::Hand,
-                        };
-                        let spawned =
-                            self.new_instance_from_def(def, seat.clone(), target_location.clone());
-                        let (player, _) = split_players_mut(&mut self.players, seat);
-                        match target_location {
-                            Location::Kitchen => player.kitchen.push(spawned),
-                            Location::Hand => player.hand.push(spawned),
-                            _ => {}
-                        }
-                    }
-                }
-            }
+        for params in spawn_tasks {
+            self.spawn_cards(seat, &params);
         }
 
         if let Some(amount) = ping_top {
@@ -898,16 +2437,110 @@ impl GameState {
         }
     }
 
-    fn apply_feed_yield(&mut self) {
-        for (index, card) in self.feed.iter().enumerate() {
-            let (owner, _) = split_players_mut(&mut self.players, &card.owner);
-            let points = (BASE_FEED_YIELD + (index as i32 * FEED_YIELD_STEP))
-                * card.yield_rate;
-            owner.score += points;
+    fn spawn_cards(&mut self, seat: &Seat, params: &SpawnParams) {
+        for _ in 0..params.count {
+            if let Some(def) = find_definition(&params.variant_id) {
+                let target_location = match params.location {
+                    SpawnLocation::Kitchen => Location::Kitchen,
+                    SpawnLocation::Hand => Location::Hand,
+                };
+                let spawned = self.new_instance_from_def(def, seat.clone(), target_location.clone());
+                let (player, _) = split_players_mut(&mut self.players, seat);
+                match target_location {
+                    Location::Kitchen => player.kitchen.push(spawned),
+                    Location::Hand => player.hand.push(spawned),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    // What each feed card (by instance id) would score if the turn ended with the feed as it is.
+    pub fn projected_yield(&self) -> Vec<(String, i32)> {
+        self.feed
+            .iter()
+            .enumerate()
+            .map(|(index, card)| (card.instance_id.clone(), self.staked_yield(index, card)))
+            .collect()
+    }
+
+    // What the card in feed slot `index` scores at the current stakes.
+    fn staked_yield(&self, index: usize, card: &CardInstance) -> i32 {
+        slot_yield(index, card) * i32::from(self.stakes.max(1))
+    }
+
+    // What changed since `prev`: feed slots, scores, phase and which cards came into or left view.
+    // Face-down cards carry no id, so they never show up as added or removed.
+    pub fn diff(&self, prev: &GameState) -> GameDiff {
+        let slots = self.feed.len().max(prev.feed.len());
+        let feed = (0..slots)
+            .filter(|&slot| self.feed.get(slot) != prev.feed.get(slot))
+            .map(|slot| FeedSlotChange {
+                slot,
+                card: self.feed.get(slot).cloned(),
+            })
+            .collect();
+        let scores = self
+            .players
+            .iter()
+            .filter_map(|player| {
+                let before = prev.players.iter().find(|p| p.seat == player.seat).map_or(0, |p| p.score);
+                (player.score != before).then(|| (player.seat.clone(), player.score - before))
+            })
+            .collect();
+        let now = self.visible_ids();
+        let before = prev.visible_ids();
+        let projected = self.projected_yield();
+        GameDiff {
+            turn: self.turn,
+            phase: (self.phase != prev.phase).then(|| self.phase.clone()),
+            feed,
+            scores,
+            added: now.difference(&before).cloned().collect(),
+            removed: before.difference(&now).cloned().collect(),
+            fields: changed_fields(self, prev),
+            projected_yield: (projected != prev.projected_yield()).then_some(projected),
+        }
+    }
+
+    fn visible_ids(&self) -> BTreeSet<String> {
+        self.feed
+            .iter()
+            .chain(self.players.iter().flat_map(|p| p.hand.iter().chain(&p.kitchen).chain(&p.abyss)))
+            .filter(|card| !card.instance_id.is_empty())
+            .map(|card| card.instance_id.clone())
+            .collect()
+    }
+
+    pub(crate) fn apply_feed_yield(&mut self) {
+        let yields: Vec<(Seat, String, i32)> = self
+            .feed
+            .iter()
+            .enumerate()
+            .map(|(index, card)| (card.owner.clone(), card.variant_id.clone(), self.staked_yield(index, card)))
+            .collect();
+        for (seat, variant_id, points) in yields {
+            self.card_stats.entry(variant_id).or_default().score_yielded += points;
+            self.add_score(&seat, points, ScoreReason::FeedYield);
+        }
+    }
+
+    fn add_score(&mut self, seat: &Seat, amount: i32, reason: ScoreReason) {
+        let (player, _) = split_players_mut(&mut self.players, seat);
+        player.score += amount;
+        self.score_log.push(ScoreDelta {
+            turn: self.turn,
+            seat: seat.clone(),
+            amount,
+            reason,
+        });
+        if self.score_log.len() > MAX_SCORE_LOG {
+            let excess = self.score_log.len() - MAX_SCORE_LOG;
+            self.score_log.drain(..excess);
         }
     }
 
-    fn apply_cook_and_decay(&mut self) {
+    pub(crate) fn apply_cook_and_decay(&mut self) {
         for player in self.players.iter_mut() {
             for card in player.kitchen.iter_mut() {
                 if card.frozen_turns > 0 {
@@ -928,16 +2561,63 @@ impl GameState {
             if let Some(decay) = card.volatile {
                 card.current_virality -= decay;
             }
+            card.current_virality -= self.rules.feed_decay;
         }
     }
 
-    fn cleanup_board(&mut self) {
-        self.feed.retain(|card| card.current_virality > 0);
+    // Fires OnFeedTurnEnd abilities top of feed first so the outcome never depends on post order.
+    pub(crate) fn apply_feed_turn_end(&mut self) {
+        for idx in 0..self.feed.len() {
+            let abilities = self.feed[idx].abilities.clone();
+            for ability in abilities {
+                if ability.trigger != AbilityTrigger::OnFeedTurnEnd {
+                    continue;
+                }
+                match ability.effect {
+                    AbilityEffect::BuffSelf(amount) => {
+                        self.feed[idx].current_virality += amount;
+                    }
+                    AbilityEffect::DamageBelow(amount) => {
+                        if let Some(target) = self.feed.get_mut(idx + 1) {
+                            apply_damage(target, amount, false);
+                            let target_id = target.instance_id.clone();
+                            let source = self.feed[idx].variant_id.clone();
+                            self.last_damaged_by.insert(target_id, source);
+                        }
+                    }
+                    AbilityEffect::DrainBelow(amount) => {
+                        if let Some(target) = self.feed.get_mut(idx + 1) {
+                            let drained = amount.min(target.current_virality);
+                            target.current_virality -= drained;
+                            self.feed[idx].current_virality += drained;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    pub(crate) fn cleanup_board(&mut self) {
+        if self.last_stand == LastStandRule::OncePerGame {
+            self.apply_last_stand();
+        }
+        let mut fallen: Vec<(Seat, CardInstance)> = Vec::new();
+        let mut deaths: Vec<CardDiedEvent> = Vec::new();
+        let mut dead: Vec<CardInstance> = Vec::new();
         for player in self.players.iter_mut() {
             let mut survivors = Vec::new();
             for mut card in player.kitchen.drain(..) {
                 if card.current_virality <= 0 {
+                    deaths.push(CardDiedEvent {
+                        turn: self.turn,
+                        instance_id: card.instance_id.clone(),
+                        owner: player.seat.clone(),
+                        zone: Location::Kitchen,
+                    });
                     card.location = Location::Abyss;
+                    dead.push(card.clone());
+                    fallen.push((player.seat.clone(), card.clone()));
                     player.abyss.push(card);
                 } else {
                     card.location = Location::Kitchen;
@@ -946,21 +2626,80 @@ impl GameState {
             }
             player.kitchen = survivors;
         }
+        for card in self.feed.iter().filter(|c| c.current_virality <= 0) {
+            deaths.push(CardDiedEvent {
+                turn: self.turn,
+                instance_id: card.instance_id.clone(),
+                owner: card.owner.clone(),
+                zone: card.location.clone(),
+            });
+            dead.push(card.clone());
+        }
+        for death in deaths {
+            self.push_event(GameEventKind::CardDied(death));
+        }
+        for card in dead.iter() {
+            self.record_death(card);
+        }
+        for (seat, card) in fallen {
+            self.fire_abyss_triggers(&seat, &card);
+        }
+        self.feed.retain(|card| card.current_virality > 0);
         self.reindex_feed();
     }
 
-    fn play_to_kitchen(&mut self, seat: &Seat, instance_id: &str) -> Result<(), String> {
+    fn apply_last_stand(&mut self) {
+        let turn = self.turn;
+        for player in self.players.iter_mut() {
+            if player.last_stand_used {
+                continue;
+            }
+            let strongest = player
+                .kitchen
+                .iter_mut()
+                .reduce(|best, card| if card.base_virality > best.base_virality { card } else { best });
+            if let Some(card) = strongest.filter(|c| c.current_virality <= 0) {
+                card.current_virality = 1;
+                player.last_stand_used = true;
+                self.events.push(GameEvent {
+                    event: GameEventKind::LastStand(LastStandEvent {
+                        turn,
+                        seat: player.seat.clone(),
+                        card_id: card.instance_id.clone(),
+                    }),
+                });
+            }
+        }
+    }
+
+    pub(crate) fn play_to_kitchen(&mut self, seat: &Seat, instance_id: &str) -> Result<(), GameError> {
         let mut card = {
             let (player, _) = split_players_mut(&mut self.players, seat);
             let idx = player
                 .hand
                 .iter()
                 .position(|c| c.instance_id == instance_id)
-                .ok_or("card not in hand")?;
+                .ok_or_else(|| GameError::CardNotInHand(instance_id.to_string()))?;
             player.hand.remove(idx)
         };
         if !matches!(card.class, CardKind::Meme(_)) {
-            return Err("only memes can be played to kitchen".into());
+            return Err(GameError::NotAMeme);
+        }
+        {
+            let (player, _) = split_players_mut(&mut self.players, seat);
+            *player
+                .plays_this_game
+                .entry(card.variant_id.clone())
+                .or_insert(0) += 1;
+            let overload: u8 = card
+                .keywords
+                .iter()
+                .map(|k| match k {
+                    Keyword::Overload(OverloadKeyword { amount }) => *amount,
+                    _ => 0,
+                })
+                .fold(0, u8::saturating_add);
+            player.overload_next = player.overload_next.saturating_add(overload);
         }
         card.location = Location::Kitchen;
         card.played_turn = self.turn;
@@ -1006,11 +2745,7 @@ impl GameState {
         let id = posts.first()?.card_id.clone();
         if let Some(idx) = player.kitchen.iter().position(|c| c.instance_id == id) {
             let mut card = player.kitchen.remove(idx);
-            if card.frozen_turns > 0 && !card.keywords.contains(&Keyword::Haste) {
-                player.kitchen.push(card);
-                return None;
-            }
-            if card.played_turn == self.turn && !card.keywords.contains(&Keyword::Haste) {
+            if !can_post(&card, self.turn) {
                 player.kitchen.push(card);
                 return None;
             }
@@ -1021,7 +2756,7 @@ impl GameState {
         }
     }
 
-    fn resurrect_last(&mut self, seat: &Seat) -> Result<(), String> {
+    fn resurrect_last(&mut self, seat: &Seat) -> Result<(), GameError> {
         let (player, _) = split_players_mut(&mut self.players, seat);
         if let Some(mut card) = player.abyss.pop() {
             card.location = Location::Hand;
@@ -1030,17 +2765,65 @@ impl GameState {
         Ok(())
     }
 
-    fn shift_feed_up(&mut self, slot: usize) -> Result<(), String> {
+    fn opponent_feed_slots(&self, seat: &Seat) -> Vec<usize> {
+        self.feed
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.owner == seat.other())
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    pub(crate) fn feed_slot_fixed(&self, slot: usize) -> bool {
+        self.feed
+            .get(slot)
+            .map(|c| {
+                c.keywords.contains(&Keyword::Anchor)
+                    || self.players.iter().any(|p| p.pinned_cards.contains(&c.instance_id))
+            })
+            .unwrap_or(false)
+    }
+
+    fn validate_feed_order(&self, seat: &Seat, order: &[usize]) -> Result<(), GameError> {
+        self.validate_slot_order(&self.opponent_feed_slots(seat), order)
+    }
+
+    // `order` must be a permutation of `slots` that leaves every anchored or pinned card in place.
+    fn validate_slot_order(&self, slots: &[usize], order: &[usize]) -> Result<(), GameError> {
+        let mut sorted = order.to_vec();
+        sorted.sort_unstable();
+        if sorted != (0..slots.len()).collect::<Vec<_>>() {
+            return Err(GameError::InvalidTarget);
+        }
+        for (i, from) in order.iter().enumerate() {
+            if *from != i && (self.feed_slot_fixed(slots[i]) || self.feed_slot_fixed(slots[*from])) {
+                return Err(GameError::FeedSlotFixed);
+            }
+        }
+        Ok(())
+    }
+
+    // Without a chosen order, the opponent's strongest movable posts go to the top slots,
+    // which yield the least.
+    fn default_sabotage_order(&self, seat: &Seat) -> Vec<usize> {
+        let slots = self.opponent_feed_slots(seat);
+        let mut order: Vec<usize> = (0..slots.len()).collect();
+        let movable: Vec<usize> = (0..slots.len())
+            .filter(|i| !self.feed_slot_fixed(slots[*i]))
+            .collect();
+        let mut ranked = movable.clone();
+        ranked.sort_by_key(|i| std::cmp::Reverse(self.feed[slots[*i]].current_virality));
+        for (pos, from) in movable.iter().zip(ranked) {
+            order[*pos] = from;
+        }
+        order
+    }
+
+    pub(crate) fn shift_feed_up(&mut self, slot: usize) -> Result<(), GameError> {
         if slot == 0 || slot >= self.feed.len() {
             return Ok(());
         }
-        let pinned = self.players.iter().any(|p| p.pinned_slots.contains(&slot))
-            || self
-                .feed
-                .get(slot)
-                .map(|c| c.keywords.contains(&Keyword::Anchor))
-                .unwrap_or(false);
-        if pinned {
+        if self.feed_slot_fixed(slot) {
             return Ok(());
         }
         self.feed.swap(slot - 1, slot);
@@ -1048,6 +2831,18 @@ impl GameState {
         Ok(())
     }
 
+    pub(crate) fn swap_feed_slots(&mut self, a: usize, b: usize) -> Result<(), GameError> {
+        if a == b || a >= self.feed.len() || b >= self.feed.len() {
+            return Ok(());
+        }
+        if self.feed_slot_fixed(a) || self.feed_slot_fixed(b) {
+            return Ok(());
+        }
+        self.feed.swap(a, b);
+        self.reindex_feed();
+        Ok(())
+    }
+
     pub fn new_instance_from_def(
         &mut self,
         def: &CardDefinition,
@@ -1108,17 +2903,68 @@ impl GameState {
     }
 
     fn to_abyss(&mut self, seat: Seat, mut card: CardInstance) {
+        self.push_event(GameEventKind::CardDied(CardDiedEvent {
+            turn: self.turn,
+            instance_id: card.instance_id.clone(),
+            owner: seat.clone(),
+            zone: card.location.clone(),
+        }));
         card.location = Location::Abyss;
+        self.record_death(&card);
+        self.fire_abyss_triggers(&seat, &card);
         let (player, _) = split_players_mut(&mut self.players, &seat);
         player.abyss.push(card);
     }
 
-    fn reindex_feed(&mut self) {
+    fn note_damage(&mut self, instance_id: &str) {
+        if let Some(source) = self.damage_source.clone() {
+            self.last_damaged_by.insert(instance_id.to_string(), source);
+        }
+    }
+
+    // Counts the death, crediting the kill to whatever damaged the card last.
+    fn record_death(&mut self, card: &CardInstance) {
+        self.card_stats.entry(card.variant_id.clone()).or_default().deaths += 1;
+        if let Some(source) = self.last_damaged_by.remove(&card.instance_id) {
+            self.card_stats.entry(source).or_default().kills += 1;
+        }
+    }
+
+    fn fire_abyss_triggers(&mut self, seat: &Seat, card: &CardInstance) {
+        for ability in card.abilities.iter() {
+            if ability.trigger != AbilityTrigger::OnAbyss {
+                continue;
+            }
+            match &ability.effect {
+                AbilityEffect::Spawn(params) => self.spawn_cards(seat, params),
+                AbilityEffect::GainMana(amount) => {
+                    // Deaths land mid-resolution, so the mana shows up after the next refill.
+                    let (player, _) = split_players_mut(&mut self.players, seat);
+                    player.mana_tax_next -= *amount as i32;
+                }
+                AbilityEffect::PingOpponentTop(amount) => {
+                    if let Some(target) = self.feed.first_mut() {
+                        if target.owner != *seat {
+                            apply_damage(target, *amount, false);
+                        }
+                    }
+                }
+                AbilityEffect::Scry(count) => self.scry(seat, *count),
+                _ => {}
+            }
+        }
+    }
+
+    pub(crate) fn reindex_feed(&mut self) {
         for (idx, card) in self.feed.iter_mut().enumerate() {
             card.location = Location::Feed(FeedSlot { slot: idx });
         }
     }
 
+    fn push_event(&mut self, event: GameEventKind) {
+        self.events.push(GameEvent { event });
+    }
+
     fn record_random(&mut self, bound: u64, kind: RandomEventKind) -> u64 {
         let result = self.rng.generate(bound, self.turn, kind);
         if let Some(ev) = self.rng.history.last().cloned() {
@@ -1142,7 +2988,22 @@ impl GameState {
 }
 
 impl PlayerState {
-    pub fn new(seat: Seat, node_id: String, deck: Vec<CardInstance>) -> Self {
+    // One-off discounts plus every cost aura in our kitchen. card_cost keeps the result above zero.
+    pub fn effective_discount(&self) -> i32 {
+        let auras: i32 = self
+            .kitchen
+            .iter()
+            .flat_map(|c| c.abilities.iter())
+            .filter(|a| a.trigger == AbilityTrigger::AuraKitchen)
+            .filter_map(|a| match a.effect {
+                AbilityEffect::CostAura(amount) => Some(amount as i32),
+                _ => None,
+            })
+            .sum();
+        self.cost_discount + auras
+    }
+
+    pub fn new(seat: Seat, node_id: String, deck: Vec<CardInstance>, rules: &GameRules) -> Self {
         Self {
             seat,
             node_id,
@@ -1150,41 +3011,53 @@ impl PlayerState {
             hand: vec![],
             kitchen: vec![],
             abyss: vec![],
-            mana: STARTING_MANA,
-            max_mana: STARTING_MANA,
+            mana: rules.starting_mana,
+            max_mana: rules.starting_mana,
             score: 0,
             cost_discount: 0,
             mana_tax_next: 0,
+            overload_next: 0,
+            score_tax_used: 0,
             commit: None,
             feed_locked: false,
-            pinned_slots: vec![],
+            pinned_cards: vec![],
+            revealed_deck_top: vec![],
+            exposed_hand: None,
+            pending_draw: vec![],
+            last_stand_used: false,
+            action_points: None,
+            plays_this_game: BTreeMap::new(),
+            cosmetics: None,
         }
     }
 
+    // Two-card openings, and any opening under a cost cap, are cycled until they hold a meme and
+    // fit the cap. Every rejected hand is logged so the opponent can check the process.
     pub fn draw_starting_hand(
         &mut self,
         count: usize,
+        max_cost: Option<u32>,
         events: &mut Vec<GameEvent>,
-    ) -> Result<(), String> {
+    ) -> Result<(), GameError> {
         if count == 0 {
             return Ok(());
         }
-        if count == 2 {
+        if count == 2 || max_cost.is_some() {
             let mut cycles: Vec<StartingHandCycle> = Vec::new();
             let mut safety = self.deck.len() + 2;
+            let mut found_meme = false;
             while safety > 0 {
                 safety -= 1;
-                if self.deck.len() < 2 {
-                    return Err("deck too small for starting hand".into());
-                }
-                let mut pulled = vec![
-                    self.deck.pop().ok_or("deck empty")?,
-                    self.deck.pop().ok_or("deck empty")?,
-                ];
-                pulled.reverse();
+                if self.deck.len() < count {
+                    return Err(GameError::DeckTooSmall);
+                }
+                let pulled = self.deck.split_off(self.deck.len() - count);
                 let has_meme = pulled.iter().any(|c| matches!(c.class, CardKind::Meme(_)));
+                let total_cost: u32 = pulled.iter().map(|c| c.cost as u32).sum();
+                let affordable = max_cost.is_none_or(|cap| total_cost <= cap);
+                found_meme |= has_meme;
                 let ids: Vec<String> = pulled.iter().map(|c| c.instance_id.clone()).collect();
-                if has_meme {
+                if has_meme && affordable {
                     for mut card in pulled {
                         card.location = Location::Hand;
                         card.played_turn = 0;
@@ -1204,12 +3077,27 @@ impl PlayerState {
                     });
                     return Ok(());
                 }
+                // A hand that only missed the cost cap keeps its cheapest meme on top, so the
+                // next tries pair it with the rest of the deck instead of repeating the same groups.
+                let mut pulled = pulled;
+                let cheapest_meme = pulled
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, c)| matches!(c.class, CardKind::Meme(_)))
+                    .min_by_key(|(_, c)| c.cost)
+                    .map(|(i, _)| i);
+                let kept = cheapest_meme.map(|i| pulled.remove(i));
                 for card in pulled.into_iter().rev() {
                     self.deck.insert(0, card);
                 }
+                self.deck.extend(kept);
                 cycles.push(StartingHandCycle { card_ids: ids });
             }
-            return Err("unable to produce a valid starting hand containing a meme".into());
+            return Err(if found_meme {
+                GameError::OpeningHandTooExpensive
+            } else {
+                GameError::NoMemeInStartingHand
+            });
         }
         for _ in 0..count {
             self.draw_card()?;
@@ -1217,7 +3105,8 @@ impl PlayerState {
         Ok(())
     }
 
-    pub fn draw_card(&mut self) -> Result<(), String> {
+    pub fn draw_card(&mut self) -> Result<(), GameError> {
+        self.revealed_deck_top.clear();
         if let Some(mut card) = self.deck.pop() {
             card.location = Location::Hand;
             card.played_turn = 0;
@@ -1231,15 +3120,46 @@ impl PlayerState {
         Ok(())
     }
 
-    pub fn reset_for_new_turn(&mut self) {
-        if self.max_mana < MANA_CAP {
+    // Discard-phase games: the card always reaches the hand, and the player trims it afterwards.
+    pub fn draw_past_hand_size(&mut self) {
+        self.revealed_deck_top.clear();
+        if let Some(mut card) = self.deck.pop() {
+            card.location = Location::Hand;
+            card.played_turn = 0;
+            self.hand.push(card);
+        }
+    }
+
+    // Draft-draw: the top two cards wait in `pending_draw`. A lone last card is simply drawn.
+    pub fn draw_for_draft(&mut self) {
+        self.revealed_deck_top.clear();
+        let take = self.deck.len().min(2);
+        if take < 2 {
+            let _ = self.draw_card();
+            return;
+        }
+        for _ in 0..take {
+            if let Some(mut card) = self.deck.pop() {
+                card.location = Location::Hand;
+                card.played_turn = 0;
+                self.pending_draw.push(card);
+            }
+        }
+    }
+
+    pub fn reset_for_new_turn(&mut self, rules: &GameRules, budget: &ActionBudgetRule) {
+        if self.max_mana < rules.mana_cap {
             self.max_mana += 1;
         }
-        let penalty = self.mana_tax_next.max(0) as u8;
-        self.mana = self.max_mana.saturating_sub(penalty);
+        // A negative tax is a carried-over bonus (e.g. from OnAbyss GainMana). Overload only dents
+        // this turn's mana; max_mana is untouched, so the turn after recovers.
+        let owed = self.mana_tax_next + self.overload_next as i32;
+        self.mana = (self.max_mana as i32 - owed).clamp(0, u8::MAX as i32) as u8;
         self.mana_tax_next = 0;
-        self.pinned_slots.clear();
+        self.overload_next = 0;
+        self.pinned_cards.clear();
         self.feed_locked = false;
+        self.action_points = budget.per_turn();
     }
 }
 
@@ -1251,83 +3171,166 @@ pub fn build_game(
     host_deck: Vec<String>,
     opponent_deck: Vec<String>,
     opponent_id: String,
-) -> Result<GameState, String> {
-    let (host_memes, host_exploits) = validate_deck_composition(catalog, &host_deck)?;
-    let (opp_memes, opp_exploits) = validate_deck_composition(catalog, &opponent_deck)?;
-    let host_valid =
-        host_deck.len() == MAX_DECK_SIZE && host_memes == MEME_LIMIT && host_exploits == EXPLOIT_LIMIT;
-    let opponent_valid = opponent_deck.len() == MAX_DECK_SIZE
-        && opp_memes == MEME_LIMIT
-        && opp_exploits == EXPLOIT_LIMIT;
-    let mut rng_state = FairRandomState::new(seed);
-    let mut host_deck_instances = instantiate_deck(catalog, host_deck, Seat::Host, next_instance)?;
-    rng_state.shuffle(
-        &mut host_deck_instances,
-        0,
-        RandomEventKind::ShuffleDeck(Seat::Host),
-    );
-    let mut opp_deck_instances =
-        instantiate_deck(catalog, opponent_deck, Seat::Opponent, next_instance)?;
-    rng_state.shuffle(
-        &mut opp_deck_instances,
-        0,
-        RandomEventKind::ShuffleDeck(Seat::Opponent),
-    );
-    let mut host = PlayerState::new(Seat::Host, our().node.clone(), host_deck_instances);
-    let mut opponent = PlayerState::new(Seat::Opponent, opponent_id, opp_deck_instances);
-    let mut events: Vec<GameEvent> = rng_state
-        .history
-        .iter()
-        .cloned()
-        .map(|event| GameEvent {
-            event: GameEventKind::Random(event),
-        })
-        .collect();
-    if host_valid {
-        host.draw_starting_hand(STARTING_HAND, &mut events)?;
-    }
-    if opponent_valid {
-        opponent.draw_starting_hand(STARTING_HAND, &mut events)?;
-    }
-    let mut game = GameState {
-        feed: vec![],
-        players: vec![host, opponent],
-        turn: 0,
-        initiative: Seat::Host,
-        phase: Phase::Commit,
-        stakes: 1,
-        pending_stakes: None,
-        winner: None,
-        game_seed: seed,
-        next_instance: *next_instance,
-        rng: rng_state,
-        events,
-    };
-    if !host_valid || !opponent_valid {
-        game.phase = Phase::GameOver;
-        game.winner = match (host_valid, opponent_valid) {
-            (false, true) => Some(Seat::Opponent),
-            (true, false) => Some(Seat::Host),
-            _ => None,
+    options: GameOptions,
+) -> Result<GameState, GameError> {
+    let mut game = GameState::lobby(opponent_id, &options);
+    game.start(catalog, next_instance, seed, host_deck, opponent_deck, options)?;
+    Ok(game)
+}
+
+impl GameState {
+    // A game that exists before anyone has dealt: empty decks, hands and feed, sitting in
+    // Phase::Lobby until `start` moves it to the first commit. We host it.
+    pub fn lobby(opponent_id: String, options: &GameOptions) -> GameState {
+        GameState::hosted_lobby(our().node, opponent_id, options)
+    }
+
+    // The same, hosted by `host_id`, for rebuilding a game some other node dealt.
+    pub fn hosted_lobby(host_id: String, opponent_id: String, options: &GameOptions) -> GameState {
+        let rules = &options.rules;
+        let mut game = GameState {
+            id: String::new(),
+            feed: vec![],
+            players: vec![
+                PlayerState::new(Seat::Host, host_id, vec![], rules),
+                PlayerState::new(Seat::Opponent, opponent_id, vec![], rules),
+            ],
+            turn: 0,
+            initiative: Seat::Host,
+            phase: Phase::Lobby,
+            stakes: 1,
+            pending_stakes: None,
+            winner: None,
+            outcome: None,
+            feed_size: FEED_SIZE,
+            score_to_win: SCORE_TO_WIN,
+            last_stand: LastStandRule::default(),
+            action_budget: ActionBudgetRule::default(),
+            win_condition: WinCondition::default(),
+            rules: rules.clone(),
+            domination_streak: [0, 0],
+            passed_last_turn: [false, false],
+            full_disclosure: false,
+            game_seed: 0,
+            next_instance: 0,
+            rng: FairRandomState::with_seeds(0, 0),
+            events: vec![],
+            score_log: vec![],
+            plan_history: vec![],
+            card_stats: BTreeMap::new(),
+            last_damaged_by: BTreeMap::new(),
+            damage_source: None,
         };
+        game.apply_options(options);
+        game
+    }
+
+    fn apply_options(&mut self, options: &GameOptions) {
+        self.feed_size = options.feed_size.unwrap_or(FEED_SIZE);
+        self.score_to_win = options.score_to_win.unwrap_or(SCORE_TO_WIN);
+        self.last_stand = options.last_stand.clone();
+        self.action_budget = options.action_budget.clone();
+        self.win_condition = options.win_condition.clone();
+        self.rules = options.rules.clone();
+        self.full_disclosure = options.full_disclosure;
+        let cosmetics = [options.host_cosmetics.clone(), options.opponent_cosmetics.clone()];
+        for (player, cosmetics) in self.players.iter_mut().zip(cosmetics) {
+            player.mana = options.rules.starting_mana;
+            player.max_mana = options.rules.starting_mana;
+            player.action_points = options.action_budget.per_turn();
+            player.cosmetics = cosmetics;
+        }
+    }
+
+    // Deals both decks and moves a lobby game to its first commit phase. `options` are applied
+    // again here since the opponent's half of them is only known once they join.
+    pub fn start(
+        &mut self,
+        catalog: &[CardDefinition],
+        next_instance: &mut u64,
+        seed: u64,
+        host_deck: Vec<String>,
+        opponent_deck: Vec<String>,
+        options: GameOptions,
+    ) -> Result<(), GameError> {
+        if self.phase != Phase::Lobby {
+            return Err(GameError::NotLobbyPhase);
+        }
+        let rules = &options.rules;
+        let host_valid = validate_deck_composition(catalog, &host_deck, rules)?;
+        let opponent_valid = validate_deck_composition(catalog, &opponent_deck, rules)?;
+        let started = GameStartedEvent {
+            host_node: self.players[0].node_id.clone(),
+            opponent_node: self.players[1].node_id.clone(),
+            host_deck: host_deck.clone(),
+            opponent_deck: opponent_deck.clone(),
+            first_instance: *next_instance,
+            options: GameOptions {
+                host_cosmetics: None,
+                opponent_cosmetics: None,
+                ..options.clone()
+            },
+        };
+        let mut rng_state = FairRandomState::with_seeds(seed, options.resolution_seed.unwrap_or(seed));
+        let mut host_deck_instances = instantiate_deck(catalog, host_deck, Seat::Host, next_instance)?;
+        rng_state.shuffle(
+            &mut host_deck_instances,
+            0,
+            RandomEventKind::ShuffleDeck(Seat::Host),
+        );
+        let mut opp_deck_instances =
+            instantiate_deck(catalog, opponent_deck, Seat::Opponent, next_instance)?;
+        rng_state.shuffle(
+            &mut opp_deck_instances,
+            0,
+            RandomEventKind::ShuffleDeck(Seat::Opponent),
+        );
+        self.apply_options(&options);
+        self.players[0].deck = host_deck_instances;
+        self.players[1].deck = opp_deck_instances;
+        let mut events = vec![GameEvent {
+            event: GameEventKind::Started(started),
+        }];
+        events.extend(rng_state.history.iter().cloned().map(|event| GameEvent {
+            event: GameEventKind::Random(event),
+        }));
+        if host_valid {
+            self.players[0].draw_starting_hand(rules.starting_hand, rules.max_opening_cost, &mut events)?;
+        }
+        if opponent_valid {
+            self.players[1].draw_starting_hand(rules.starting_hand, rules.max_opening_cost, &mut events)?;
+        }
+        self.phase = Phase::Commit;
+        self.game_seed = seed;
+        self.next_instance = *next_instance;
+        self.rng = rng_state;
+        self.events = events;
+        if !host_valid || !opponent_valid {
+            self.finish(match (host_valid, opponent_valid) {
+                (false, true) => GameOutcome::Win(Seat::Opponent),
+                (true, false) => GameOutcome::Win(Seat::Host),
+                _ => GameOutcome::Draw,
+            });
+        }
+        Ok(())
     }
-    Ok(game)
 }
 
-fn validate_deck_composition(catalog: &[CardDefinition], ids: &[String]) -> Result<(usize, usize), String> {
+// Whether a deck fits the format. Unknown cards are an error rather than just an invalid deck.
+fn validate_deck_composition(catalog: &[CardDefinition], ids: &[String], rules: &GameRules) -> Result<bool, GameError> {
     let mut memes = 0usize;
     let mut exploits = 0usize;
     for id in ids {
         let def = catalog
             .iter()
             .find(|c| &c.id == id)
-            .ok_or_else(|| format!("card {} not found", id))?;
+            .ok_or_else(|| GameError::UnknownCard(id.clone()))?;
         match def.class {
             CardKind::Meme(_) => memes += 1,
             CardKind::Exploit(_) => exploits += 1,
         }
     }
-    Ok((memes, exploits))
+    Ok(ids.len() == rules.deck_size && memes == rules.meme_limit && exploits == rules.exploit_limit)
 }
 
 fn instantiate_deck(
@@ -1335,14 +3338,14 @@ fn instantiate_deck(
     ids: Vec<String>,
     owner: Seat,
     next_instance: &mut u64,
-) -> Result<Vec<CardInstance>, String> {
+) -> Result<Vec<CardInstance>, GameError> {
     let mut deck = Vec::new();
     for id in ids {
         let def = catalog
             .iter()
             .find(|c| c.id == id)
             .cloned()
-            .ok_or_else(|| format!("card {} not found", id))?;
+            .ok_or_else(|| GameError::UnknownCard(id.clone()))?;
         deck.push(instantiate_card(next_instance, &def, owner.clone()));
     }
     Ok(deck)
@@ -1402,6 +3405,53 @@ fn instantiate_card(next_instance: &mut u64, def: &CardDefinition, owner: Seat)
     }
 }
 
+fn plan_mana_cost(player: &PlayerState, plan: &TurnPlan) -> Result<i32, GameError> {
+    let mut mana_spent = 0i32;
+    for id in plan.plays_to_kitchen.iter() {
+        let cost = card_cost(&player.hand, id, player.effective_discount())?;
+        mana_spent += cost as i32;
+    }
+    for exploit in plan.exploits.iter() {
+        let cost = card_cost(&player.hand, &exploit.card_id, player.effective_discount())?;
+        mana_spent += cost as i32;
+    }
+    Ok(mana_spent + plan_post_cost(player, plan))
+}
+
+fn plan_post_cost(player: &PlayerState, plan: &TurnPlan) -> i32 {
+    let mut mana_spent = 0i32;
+    for post in plan.posts.iter() {
+        // Hasty memes can be posted the turn they're played, so they may still be in hand.
+        let card = player
+            .kitchen
+            .iter()
+            .chain(player.hand.iter())
+            .find(|c| c.instance_id == post.card_id);
+        if let Some(CardKind::Meme(meme)) = card.map(|c| &c.class) {
+            mana_spent += meme.post_cost as i32;
+        }
+    }
+    mana_spent
+}
+
+fn exploit_order<'a>(
+    initiative: &Seat,
+    host_plan: &'a TurnPlan,
+    opponent_plan: &'a TurnPlan,
+) -> Vec<(Seat, &'a TurnPlan)> {
+    match initiative {
+        Seat::Host => vec![(Seat::Host, host_plan), (Seat::Opponent, opponent_plan)],
+        Seat::Opponent => vec![(Seat::Opponent, opponent_plan), (Seat::Host, host_plan)],
+    }
+}
+
+fn can_post(card: &CardInstance, turn: u32) -> bool {
+    if card.keywords.contains(&Keyword::Haste) {
+        return true;
+    }
+    card.frozen_turns == 0 && card.played_turn != turn
+}
+
 pub fn split_players_mut<'a>(
     players: &'a mut [PlayerState],
     seat: &'a Seat,
@@ -1415,11 +3465,11 @@ pub fn split_players_mut<'a>(
     }
 }
 
-fn card_cost(cards: &[CardInstance], id: &str, discount: i32) -> Result<u8, String> {
+fn card_cost(cards: &[CardInstance], id: &str, discount: i32) -> Result<u8, GameError> {
     let card = cards
         .iter()
         .find(|c| c.instance_id == id)
-        .ok_or("card not found")?;
+        .ok_or_else(|| GameError::CardNotInHand(id.to_string()))?;
     let mut cost = card.cost as i32 - discount;
     if cost < 0 {
         cost = 0;
@@ -1427,13 +3477,24 @@ fn card_cost(cards: &[CardInstance], id: &str, discount: i32) -> Result<u8, Stri
     Ok(cost as u8)
 }
 
+// Returns the card id and how much Virality it actually lost.
+fn damage_and_measure(card: &mut CardInstance, amount: i32) -> (String, i32) {
+    let before = card.current_virality;
+    apply_damage(card, amount, false);
+    (card.instance_id.clone(), before - card.current_virality)
+}
+
 fn apply_damage(card: &mut CardInstance, amount: i32, ignore_protect: bool) {
     if card.protected_until_end && !ignore_protect {
         return;
     }
+    // Shields are a one-time buffer: whatever they absorb is used up, and they persist across
+    // turns until depleted.
     let mut dmg = amount;
-    if card.shield > 0 && !ignore_protect {
-        dmg = (amount - card.shield).max(0);
+    if card.shield > 0 && !ignore_protect && amount > 0 {
+        let absorbed = amount.min(card.shield);
+        card.shield -= absorbed;
+        dmg = amount - absorbed;
     }
     if card.keywords.contains(&Keyword::Fragile) && dmg > 0 {
         card.current_virality = 0;
@@ -1442,6 +3503,63 @@ fn apply_damage(card: &mut CardInstance, amount: i32, ignore_protect: bool) {
     }
 }
 
+// Each Combo meme gets its bonus once per other Combo meme the same seat posts alongside it.
+// Applied before ordering, so the bonus counts towards where the card lands.
+fn apply_combo_bonus(entries: &mut [(Seat, CardInstance)]) {
+    let combos = |seat: &Seat, entries: &[(Seat, CardInstance)]| {
+        entries
+            .iter()
+            .filter(|(owner, card)| owner == seat && combo_amount(card).is_some())
+            .count() as i32
+    };
+    let (host, opponent) = (combos(&Seat::Host, entries), combos(&Seat::Opponent, entries));
+    for (seat, card) in entries.iter_mut() {
+        let others = if *seat == Seat::Host { host } else { opponent } - 1;
+        if let Some(amount) = combo_amount(card) {
+            card.current_virality += amount * others;
+        }
+    }
+}
+
+fn combo_amount(card: &CardInstance) -> Option<i32> {
+    card.keywords.iter().find_map(|k| match k {
+        Keyword::Combo(ComboKeyword { amount }) => Some(*amount),
+        _ => None,
+    })
+}
+
+// Top-level fields of `now` that differ from `prev`, as JSON, leaving out the ones a GameDiff
+// already carries on its own.
+fn changed_fields(now: &GameState, prev: &GameState) -> BTreeMap<String, serde_json::Value> {
+    const CARRIED: [&str; 4] = ["feed", "events", "turn", "phase"];
+    let (Ok(serde_json::Value::Object(now)), Ok(serde_json::Value::Object(mut prev))) =
+        (serde_json::to_value(now), serde_json::to_value(prev))
+    else {
+        return BTreeMap::new();
+    };
+    now.into_iter()
+        .filter(|(key, value)| !CARRIED.contains(&key.as_str()) && prev.remove(key).as_ref() != Some(value))
+        .collect()
+}
+
+// Lower slots pay more per point of yield rate.
+fn slot_yield(index: usize, card: &CardInstance) -> i32 {
+    (BASE_FEED_YIELD + (index as i32 * FEED_YIELD_STEP)) * card.yield_rate
+}
+
+// The card's variant and total Thorns amount, if it has any.
+fn thorns_of(card: &CardInstance) -> Option<(String, i32)> {
+    let amount: i32 = card
+        .keywords
+        .iter()
+        .filter_map(|k| match k {
+            Keyword::Thorns(ThornsKeyword { amount }) => Some(*amount),
+            _ => None,
+        })
+        .sum();
+    (amount > 0).then(|| (card.variant_id.clone(), amount))
+}
+
 fn find_card_mut<'a>(cards: &'a mut [CardInstance], id: &str) -> Option<&'a mut CardInstance> {
     cards.iter_mut().find(|c| c.instance_id == id)
 }
@@ -1491,11 +3609,85 @@ fn aura_amount(abilities: &[Ability]) -> Option<i32> {
     })
 }
 
-pub fn validate_state_hash(game: &GameState, remote: &StateHash) -> Result<(), String> {
+pub fn validate_state_hash(game: &GameState, remote: &StateHash) -> Result<(), GameError> {
     let local = game.state_hash();
     if local.turn != remote.turn || local.hash != remote.hash {
-        Err("state hash mismatch".into())
+        Err(GameError::StateHashMismatch)
     } else {
         Ok(())
     }
 }
+
+pub fn validate_plan(plan: &TurnPlan, action_points: Option<u8>) -> Result<(), GameError> {
+    // Plans arrive from peers, so bound every vector before anything iterates over them.
+    let sections = [
+        ("plays_to_kitchen", plan.plays_to_kitchen.len()),
+        ("posts", plan.posts.len()),
+        ("exploits", plan.exploits.len()),
+    ];
+    for (name, len) in sections {
+        if len > MAX_PLAN_ACTIONS {
+            return Err(GameError::PlanTooLarge(PlanLimitParams {
+                section: name.to_string(),
+                len,
+                max: MAX_PLAN_ACTIONS,
+            }));
+        }
+    }
+    if let Some(points) = action_points {
+        let actions = plan_action_count(plan);
+        if actions > points as usize {
+            return Err(GameError::PlanTooLarge(PlanLimitParams {
+                section: "actions".to_string(),
+                len: actions,
+                max: points as usize,
+            }));
+        }
+    }
+    Ok(())
+}
+
+// Placeholder that only keeps where a hidden card sits and whose it is.
+fn face_down(card: &CardInstance) -> CardInstance {
+    CardInstance {
+        instance_id: String::new(),
+        variant_id: "hidden".to_string(),
+        name: String::new(),
+        owner: card.owner.clone(),
+        cost: 0,
+        class: CardKind::Meme(MemeBlueprint {
+            base_virality: 0,
+            cook_rate: 0,
+            yield_rate: 0,
+            keywords: vec![],
+            abilities: vec![],
+            volatile: None,
+            initial_freeze: None,
+            post_cost: 0,
+        }),
+        base_virality: 0,
+        current_virality: 0,
+        cook_rate: 0,
+        yield_rate: 0,
+        keywords: vec![],
+        abilities: vec![],
+        volatile: None,
+        frozen_turns: 0,
+        protected_until_end: false,
+        shield: 0,
+        played_turn: 0,
+        location: card.location.clone(),
+    }
+}
+
+fn streak_index(seat: &Seat) -> usize {
+    match seat {
+        Seat::Host => 0,
+        Seat::Opponent => 1,
+    }
+}
+
+// Each play, post and exploit costs one action point.
+fn plan_action_count(plan: &TurnPlan) -> usize {
+    plan.plays_to_kitchen.len() + plan.posts.len() + plan.exploits.len()
+}
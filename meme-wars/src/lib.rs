@@ -1,30 +1,42 @@
 use hyperware_process_lib::http::server::{self, WsMessageType};
 use hyperware_process_lib::{
     homepage::add_to_homepage,
-    hyperapp::{get_server, send},
+    hyperapp::{get_server, send, source},
     our, println, Address, LazyLoadBlob, ProcessId, Request,
 };
 use rand::Rng;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 mod catalog;
 mod constants;
 mod crypto;
+mod error;
 mod game;
 mod net;
 mod rng;
 mod snapshot;
 mod types;
 
-use catalog::{build_catalog, default_deck};
-use constants::{GAME_NAME, WS_PATH};
-use crypto::commitment_for;
-use game::{build_game, validate_state_hash, GameState};
+use catalog::{
+    aggro_deck, analyze_deck, build_catalog, default_deck, find_cards, generate_pack, sample_decks, CardFilter,
+    DeckAnalysis,
+};
+use constants::{
+    GAME_NAME, PROTOCOL_VERSION, MAX_CHAT_LEN, MAX_CHAT_LOG, MAX_MATCH_HISTORY, SNAPSHOT_EVENT_WINDOW,
+    WIRE_SEND_ATTEMPTS, WS_PATH,
+};
+use crypto::{commitment_for, lobby_password_hash};
+use error::{GameError, TurnParams};
+use game::{build_game, validate_plan, validate_state_hash, Diagnostic, GameEvent, GameState, ResolutionEntry};
 use net::{
-    JoinLobbyPayload, StakeNotice, WireCommit, WireMessage, WireReply, WireReveal, WsClientMessage,
-    WsEnvelope, WsServerMessage, WsTarget,
+    check_version, JoinLobbyPayload, RematchPayload, StakeNotice, SubscribePayload, WireCommit, WireDiscard, WireDrawChoice,
+    WireEnvelope, WireMessage, WireReply,
+    WireRetract, WireReveal, WireSeedCommit, WireSeedReveal,
+    WsClientMessage, WsEnvelope, WsGameError, WsServerMessage, WsTarget,
 };
-use snapshot::GameSnapshot;
+use rng::{contribution_commitment, series_seed, shared_seed, FairRandomState, SeedHandshake};
+use snapshot::{GameSnapshot, PublicSnapshot};
 use types::*;
 
 const ICON: &str = include_str!("./icon");
@@ -32,14 +44,61 @@ const ICON: &str = include_str!("./icon");
 #[derive(Default, Serialize, Deserialize)]
 pub struct MemeWarsState {
     catalog: Vec<CardDefinition>,
-    game: Option<GameState>,
+    // Every game we're playing or watching, by GameState::id.
+    #[serde(default)]
+    games: HashMap<String, GameState>,
+    // The game on screen. Endpoints and untagged peer messages act on it.
+    #[serde(default)]
+    current_game: Option<String>,
+    #[serde(default)]
+    game_seq: u64,
     next_instance: u64,
     lobbies: Vec<Lobby>,
     lobby_seq: u64,
     discovered_lobbies: Vec<Lobby>,
+    // Nodes watching our hosted game; they receive SyncGame pushes but can never act.
+    spectators: Vec<String>,
+    // The host whose game we're watching, the only node whose SyncGame we take while unseated.
+    #[serde(default)]
+    watching: Option<String>,
+    // Our own presentation, stamped onto lobbies and games we take part in.
+    cosmetics: Option<PlayerCosmetics>,
+    // Recent chat with our opponent, oldest first; trimmed to MAX_CHAT_LOG entries.
+    chat_log: Vec<ChatMessage>,
+    // Finished games, oldest first; trimmed to MAX_MATCH_HISTORY entries.
+    #[serde(default)]
+    match_history: Vec<MatchRecord>,
+    // Turn updates the opponent hasn't acknowledged yet, with the node and game they're for,
+    // oldest first.
+    #[serde(default)]
+    pending_sync: Vec<(String, Option<String>, WireMessage)>,
+    // The seed handshake for the next lobby game, if one is under way or agreed but not yet dealt.
+    #[serde(default)]
+    seed_handshake: Option<SeedHandshake>,
+    #[serde(skip)]
+    // When set, game seeds are handed out sequentially from here instead of thread_rng.
+    seed_source: Option<u64>,
     #[serde(skip)]
     // Track all websocket paths that have been opened so we can broadcast on each.
     ws_paths: Vec<String>,
+    #[serde(skip)]
+    // Game id, turn, phase and event count as of the last broadcast, to work out what changed since.
+    broadcast_progress: Option<(String, u32, Phase, usize)>,
+    #[serde(skip)]
+    // Our view of the game as of the last broadcast, to diff the next one against.
+    broadcast_view: Option<GameState>,
+    #[serde(skip)]
+    // The last broadcast snapshot without its game, to tell whether deltas alone describe the next.
+    broadcast_frame: Option<GameSnapshot>,
+    #[serde(skip)]
+    // Set while a peer's message is applied to a game other than the one on screen.
+    background: bool,
+    #[serde(skip)]
+    // Set while a batch is applied; holds whether a broadcast was asked for before it commits.
+    deferred_broadcast: Option<bool>,
+    #[serde(skip)]
+    // Set once a client has fetched the catalog; broadcasts leave it out from then on.
+    catalog_served: bool,
 }
 
 fn process_id() -> ProcessId {
@@ -78,31 +137,57 @@ impl MemeWarsState {
 
     #[local]
     #[http]
-    async fn get_snapshot(&self) -> Result<GameSnapshot, String> {
-        Ok(self.compose_snapshot())
+    async fn get_snapshot(&self) -> Result<GameSnapshot, GameError> {
+        Ok(self.compose_client_snapshot())
+    }
+
+    // Safe to expose to observers: scores, feed and lobbies only.
+    #[local]
+    #[http]
+    async fn get_public_snapshot(&self) -> Result<PublicSnapshot, GameError> {
+        Ok(self.compose_snapshot().public())
     }
 
     #[local]
     #[http]
-    async fn new_game(&mut self, opponent: Option<String>) -> Result<GameSnapshot, String> {
+    async fn new_game(&mut self, opponent: Option<String>) -> Result<GameSnapshot, GameError> {
         let opponent_id = opponent.unwrap_or_else(|| "opponent.os".to_string());
         let seed = 42u64;
         let host_deck = default_deck();
-        let opponent_deck = default_deck();
+        // A contrasting opponent makes a better demo than a mirror match.
+        let opponent_deck = aggro_deck();
         let game =
-            build_game(&self.catalog, &mut self.next_instance, seed, host_deck, opponent_deck, opponent_id)?;
+            build_game(
+                &self.catalog,
+                &mut self.next_instance,
+                seed,
+                host_deck,
+                opponent_deck,
+                opponent_id,
+                GameOptions {
+                    host_cosmetics: self.cosmetics.clone(),
+                    ..GameOptions::default()
+                },
+            )?;
         self.next_instance = game.next_instance;
-        self.game = Some(game);
-        let snapshot = self.compose_snapshot();
+        self.install_game(game);
+        let snapshot = self.compose_client_snapshot();
         self.broadcast_snapshot();
         Ok(snapshot)
     }
 
     #[local]
     #[http]
-    async fn host_lobby(&mut self, config: LobbyConfig) -> Result<GameSnapshot, String> {
+    async fn host_lobby(&mut self, config: LobbyConfig) -> Result<GameSnapshot, GameError> {
+        if config.feed_size == Some(0) {
+            return Err(GameError::InvalidFeedSize);
+        }
         let id = format!("lobby-{}", self.lobby_seq);
         self.lobby_seq += 1;
+        let password_hash = config
+            .password
+            .filter(|p| !p.is_empty())
+            .map(|p| lobby_password_hash(&id, &p));
         let lobby = Lobby {
             id,
             host: our().node,
@@ -113,93 +198,154 @@ impl MemeWarsState {
             started: false,
             host_deck: config.deck,
             opponent_deck: vec![],
+            feed_size: config.feed_size,
+            score_to_win: config.score_to_win,
+            last_stand: config.last_stand,
+            action_budget: config.action_budget,
+            win_condition: config.win_condition,
+            rules: config.rules,
+            rematch_count: 0,
+            host_cosmetics: config.cosmetics.or_else(|| self.cosmetics.clone()),
+            opponent_cosmetics: None,
+            full_disclosure: config.full_disclosure,
+            opponent_full_disclosure: false,
+            private: password_hash.is_some(),
+            password_hash,
+            series: config.best_of.filter(|n| *n > 1).map(Series::new),
         };
+        // Nothing in play, so the new lobby's empty table stands in until someone starts it.
+        if self
+            .game()
+            .is_none_or(|g| matches!(g.phase, Phase::Lobby | Phase::GameOver))
+        {
+            let mut placeholder = GameState::lobby(String::new(), &lobby.game_options());
+            placeholder.id = lobby.game_id();
+            self.install_game(placeholder);
+        }
         self.lobbies.push(lobby);
-        let snapshot = self.compose_snapshot();
+        let snapshot = self.compose_client_snapshot();
         self.broadcast_snapshot();
         Ok(snapshot)
     }
 
     #[local]
     #[http]
-    async fn join_lobby(&mut self, params: (String, Vec<String>)) -> Result<GameSnapshot, String> {
-        let (lobby_id, deck) = params;
+    async fn join_lobby(
+        &mut self,
+        params: (String, Vec<String>, bool),
+    ) -> Result<GameSnapshot, GameError> {
+        let (lobby_id, deck, full_disclosure) = params;
         let lobby = self
             .lobbies
             .iter_mut()
             .find(|l| l.id == lobby_id)
-            .ok_or("Lobby not found")?;
-        if lobby.opponent.is_some() {
-            return Err("Lobby already has an opponent".into());
+            .ok_or(GameError::LobbyNotFound)?;
+        let node = our().node;
+        if lobby.opponent.as_ref().is_some_and(|opponent| *opponent != node) {
+            return Err(GameError::LobbyFull);
         }
-        lobby.opponent = Some(our().node);
+        lobby.opponent = Some(node);
         lobby.opponent_deck = deck;
-        let snapshot = self.compose_snapshot();
+        lobby.opponent_cosmetics = self.cosmetics.clone();
+        lobby.opponent_full_disclosure = full_disclosure;
+        let snapshot = self.compose_client_snapshot();
         self.broadcast_snapshot();
         Ok(snapshot)
     }
 
     #[local]
     #[http]
-    async fn start_lobby_game(&mut self, lobby_id: String) -> Result<GameSnapshot, String> {
+    async fn start_lobby_game(&mut self, lobby_id: String) -> Result<GameSnapshot, GameError> {
         let lobby_index = self
             .lobbies
             .iter()
             .position(|l| l.id == lobby_id)
-            .ok_or("Lobby not found")?;
+            .ok_or(GameError::LobbyNotFound)?;
         let opponent_id = self.lobbies[lobby_index]
             .opponent
             .clone()
-            .ok_or("Need an opponent to start")?;
-        let seed = rand::thread_rng().gen::<u64>();
-        let host_deck = self.lobbies[lobby_index].host_deck.clone();
-        let opponent_deck = self.lobbies[lobby_index].opponent_deck.clone();
-        let game = build_game(
-            &self.catalog,
-            &mut self.next_instance,
-            seed,
-            host_deck,
-            opponent_deck,
-            opponent_id.clone(),
-        )?;
-        self.next_instance = game.next_instance;
-        if let Some(lobby) = self.lobbies.get_mut(lobby_index) {
-            lobby.started = true;
+            .ok_or(GameError::NeedOpponent)?;
+        self.agree_seed(&lobby_id, &opponent_id).await?;
+        let previous = (self.lobbies[lobby_index].clone(), self.game().cloned());
+        let game = self.launch_lobby_game(lobby_index, None)?;
+        self.sync_lobby_game(lobby_index, previous, game).await?;
+        let snapshot = self.compose_client_snapshot();
+        self.broadcast_snapshot();
+        Ok(snapshot)
+    }
+
+    #[local]
+    #[http]
+    async fn rematch(&mut self, lobby_id: String) -> Result<GameSnapshot, GameError> {
+        if !self.lobbies.iter().any(|l| l.id == lobby_id) {
+            // We joined this lobby; ask its host to rebuild the game.
+            let host_node = self
+                .game()
+                .and_then(|g| g.players.iter().find(|p| p.seat == Seat::Host))
+                .map(|p| p.node_id.clone())
+                .ok_or(GameError::NoActiveGame)?;
+            self.agree_seed(&lobby_id, &host_node).await?;
+            let reply = self
+                .send_wire_message(&host_node, WireMessage::Rematch(RematchPayload { lobby_id }))
+                .await?;
+            return match reply {
+                WireReply::Snapshot(snapshot) => {
+                    self.discovered_lobbies = snapshot.lobbies.clone();
+                    if let Some(game) = snapshot.game.clone() {
+                        self.check_agreed_seed(&host_node, &game)?;
+                        self.next_instance = game.next_instance;
+                        self.install_game(game);
+                    }
+                    let merged = self.compose_client_snapshot();
+                    self.broadcast_snapshot();
+                    Ok(merged)
+                }
+                _ => Err(GameError::UnexpectedReply),
+            };
         }
-        self.game = Some(game.clone());
-        let _ = self
-            .send_wire_message(&opponent_id, WireMessage::SyncGame(game.clone()))
-            .await;
-        let snapshot = self.compose_snapshot();
+        let lobby_index = self
+            .lobbies
+            .iter()
+            .position(|l| l.id == lobby_id)
+            .ok_or(GameError::LobbyNotFound)?;
+        let opponent_id = self.lobbies[lobby_index]
+            .opponent
+            .clone()
+            .ok_or(GameError::NeedOpponent)?;
+        self.agree_seed(&lobby_id, &opponent_id).await?;
+        let previous = (self.lobbies[lobby_index].clone(), self.game().cloned());
+        let game = self.prepare_rematch(&lobby_id)?;
+        self.sync_lobby_game(lobby_index, previous, game).await?;
+        let snapshot = self.compose_client_snapshot();
         self.broadcast_snapshot();
         Ok(snapshot)
     }
 
     #[local]
     #[http]
-    async fn fetch_remote_lobbies(&mut self, node: String) -> Result<GameSnapshot, String> {
+    async fn fetch_remote_lobbies(&mut self, node: String) -> Result<GameSnapshot, GameError> {
         if node == our().node {
-            return Err("cannot fetch remote lobbies from self".into());
+            return Err(GameError::SelfTarget);
         }
         let reply = self
             .send_wire_message(&node, WireMessage::RequestSnapshot)
             .await?;
         if let WireReply::Snapshot(snapshot) = reply {
             self.discovered_lobbies = snapshot.lobbies.clone();
-            let merged = self.compose_snapshot();
+            let merged = self.compose_client_snapshot();
             self.broadcast_snapshot();
             return Ok(merged);
         }
-        Err("unexpected reply".into())
+        Err(GameError::UnexpectedReply)
     }
 
     #[local]
     #[http]
     async fn join_remote_lobby(
         &mut self,
-        params: (String, String, Vec<String>),
-    ) -> Result<GameSnapshot, String> {
-        let (host_node, lobby_id, deck) = params;
+        params: (String, String, Vec<String>, bool, Option<String>),
+    ) -> Result<GameSnapshot, GameError> {
+        let (host_node, lobby_id, deck, full_disclosure, password) = params;
         let reply = self
             .send_wire_message(
                 &host_node,
@@ -207,6 +353,9 @@ impl MemeWarsState {
                     lobby_id,
                     node_id: our().node.clone(),
                     deck,
+                    cosmetics: self.cosmetics.clone(),
+                    full_disclosure,
+                    password,
                 }),
             )
             .await?;
@@ -215,19 +364,19 @@ impl MemeWarsState {
                 self.discovered_lobbies = snapshot.lobbies.clone();
                 if let Some(game) = snapshot.game.clone() {
                     self.next_instance = game.next_instance;
-                    self.game = Some(game);
+                    self.install_game(game);
                 }
-                let merged = self.compose_snapshot();
+                let merged = self.compose_client_snapshot();
                 self.broadcast_snapshot();
                 Ok(merged)
             }
-            _ => Err("unexpected reply".into()),
+            _ => Err(GameError::UnexpectedReply),
         }
     }
 
     #[local]
     #[http]
-    async fn sync_remote_game(&mut self, host_node: String) -> Result<GameSnapshot, String> {
+    async fn sync_remote_game(&mut self, host_node: String) -> Result<GameSnapshot, GameError> {
         let reply = self
             .send_wire_message(&host_node, WireMessage::RequestSnapshot)
             .await?;
@@ -236,44 +385,78 @@ impl MemeWarsState {
                 self.discovered_lobbies = snapshot.lobbies.clone();
                 if let Some(game) = snapshot.game.clone() {
                     self.next_instance = game.next_instance;
-                    self.game = Some(game);
+                    self.install_game(game);
+                }
+                let merged = self.compose_client_snapshot();
+                self.broadcast_snapshot();
+                Ok(merged)
+            }
+            _ => Err(GameError::UnexpectedReply),
+        }
+    }
+
+    #[local]
+    #[http]
+    async fn spectate_remote(&mut self, host_node: String) -> Result<GameSnapshot, GameError> {
+        if host_node == our().node {
+            return Err(GameError::SelfTarget);
+        }
+        let reply = self
+            .send_wire_message(
+                &host_node,
+                WireMessage::Subscribe(SubscribePayload {
+                    node_id: our().node,
+                }),
+            )
+            .await?;
+        match reply {
+            WireReply::Snapshot(snapshot) => {
+                self.discovered_lobbies = snapshot.lobbies.clone();
+                if let Some(game) = snapshot.game.clone() {
+                    self.next_instance = game.next_instance;
+                    self.install_game(game);
                 }
-                let merged = self.compose_snapshot();
+                self.watching = Some(host_node);
+                let merged = self.compose_client_snapshot();
                 self.broadcast_snapshot();
                 Ok(merged)
             }
-            _ => Err("unexpected reply".into()),
+            _ => Err(GameError::UnexpectedReply),
         }
     }
 
     #[local]
     #[http]
-    async fn reset(&mut self) -> Result<(), String> {
+    async fn reset(&mut self) -> Result<(), GameError> {
         self.lobbies.retain(|l| !l.started);
         self.discovered_lobbies.retain(|l| !l.started);
-        self.game = None;
+        self.spectators.clear();
+        self.watching = None;
+        self.games.clear();
+        self.current_game = None;
         self.broadcast_snapshot();
         Ok(())
     }
 
     #[local]
     #[http]
-    async fn compute_commit(&self, params: (TurnPlan, String)) -> Result<String, String> {
+    async fn compute_commit(&self, params: (TurnPlan, String)) -> Result<String, GameError> {
         let (plan, salt) = params;
-        Ok(commitment_for(&plan, &salt))
+        let game = self.game().ok_or(GameError::NoActiveGame)?;
+        Ok(commitment_for(&plan, &salt, game.turn, game.game_seed))
     }
 
     #[local]
     #[http]
-    async fn commit_turn(&mut self, params: (Seat, String, u32)) -> Result<GameSnapshot, String> {
+    async fn commit_turn(&mut self, params: (Seat, String, u32)) -> Result<GameSnapshot, GameError> {
         let (seat, hash, turn) = params;
         let opponent_node = {
-            let game = self.game.as_mut().ok_or("no active game")?;
+            let game = self.game_mut().ok_or(GameError::NoActiveGame)?;
             if game.turn != turn {
-                return Err(format!(
-                    "commit turn mismatch: game {}, got {}",
-                    game.turn, turn
-                ));
+                return Err(GameError::TurnMismatch(TurnParams {
+                    expected: game.turn,
+                    got: turn,
+                }));
             }
             let node = game
                 .players
@@ -284,14 +467,98 @@ impl MemeWarsState {
             self.next_instance = game.next_instance;
             node
         };
-        if let Some(node) = opponent_node {
-            let _ = self
-                .send_wire_message(&node, WireMessage::Commit(WireCommit { seat, hash, turn }))
-                .await;
-        }
-        let snapshot = self.compose_snapshot();
+        let sent = match opponent_node {
+            Some(node) => {
+                self.send_turn_update(&node, WireMessage::Commit(WireCommit { seat, hash, turn }))
+                    .await
+            }
+            None => Ok(()),
+        };
+        let snapshot = self.compose_client_snapshot();
         self.broadcast_snapshot();
-        Ok(snapshot)
+        sent.map(|_| snapshot)
+    }
+
+    #[local]
+    #[http]
+    async fn retract_commit(&mut self, seat: Seat) -> Result<GameSnapshot, GameError> {
+        let (opponent_node, turn) = {
+            let game = self.game_mut().ok_or(GameError::NoActiveGame)?;
+            game.retract_commit(seat.clone())?;
+            (game.player_node(&seat.other()), game.turn)
+        };
+        let sent = match opponent_node {
+            Some(node) => {
+                self.send_turn_update(&node, WireMessage::RetractCommit(WireRetract { seat, turn }))
+                    .await
+            }
+            None => Ok(()),
+        };
+        let snapshot = self.compose_client_snapshot();
+        self.broadcast_snapshot();
+        sent.map(|_| snapshot)
+    }
+
+    // Draft-draw games: keep one of the cards we drew and tell the other seat.
+    #[local]
+    #[http]
+    async fn choose_draw(&mut self, keep: String) -> Result<GameSnapshot, GameError> {
+        let (opponent_node, turn) = {
+            let me = our().node;
+            let game = self.game_mut().ok_or(GameError::NoActiveGame)?;
+            // The other seat's choice is theirs to make, on their node.
+            if game.draw_chooser(&keep).is_some_and(|owner| owner != me) {
+                return Err(GameError::NotSeated(me));
+            }
+            let seat = game.choose_draw(&keep)?;
+            (game.player_node(&seat.other()), game.turn)
+        };
+        let sent = match opponent_node.filter(|n| *n != our().node) {
+            Some(node) => {
+                self.send_turn_update(&node, WireMessage::ChooseDraw(WireDrawChoice { keep, turn }))
+                    .await
+            }
+            None => Ok(()),
+        };
+        let snapshot = self.compose_client_snapshot();
+        self.broadcast_snapshot();
+        sent.map(|_| snapshot)
+    }
+
+    // Discard-phase games: keep MAX_HAND_SIZE cards of our over-full hand and tell the other seat.
+    #[local]
+    #[http]
+    async fn discard_to_hand_size(&mut self, keep: Vec<String>) -> Result<GameSnapshot, GameError> {
+        let (opponent_node, turn) = {
+            let me = our().node;
+            let game = self.game_mut().ok_or(GameError::NoActiveGame)?;
+            // Only our own hand; the other seat trims theirs on their node.
+            if keep.first().and_then(|id| game.hand_holder(id)).is_some_and(|owner| owner != me) {
+                return Err(GameError::NotSeated(me));
+            }
+            let seat = game.discard_to_hand_size(&keep)?;
+            (game.player_node(&seat.other()), game.turn)
+        };
+        let sent = match opponent_node.filter(|n| *n != our().node) {
+            Some(node) => {
+                self.send_turn_update(&node, WireMessage::DiscardToHandSize(WireDiscard { keep, turn }))
+                    .await
+            }
+            None => Ok(()),
+        };
+        let snapshot = self.compose_client_snapshot();
+        self.broadcast_snapshot();
+        sent.map(|_| snapshot)
+    }
+
+    // Resends queued turn updates; returns how many are still waiting.
+    #[local]
+    #[http]
+    async fn flush_pending_sync(&mut self) -> Result<usize, GameError> {
+        for node in self.pending_nodes() {
+            let _ = self.flush_pending_for(&node).await;
+        }
+        Ok(self.pending_sync.len())
     }
 
     #[local]
@@ -299,15 +566,15 @@ impl MemeWarsState {
     async fn reveal_turn(
         &mut self,
         params: (Seat, TurnPlan, String, u32),
-    ) -> Result<GameSnapshot, String> {
+    ) -> Result<GameSnapshot, GameError> {
         let (seat, plan, salt, turn) = params;
         let (opponent_node, prev_turn, host_is_me) = {
-            let game = self.game.as_mut().ok_or("no active game")?;
+            let game = self.game_mut().ok_or(GameError::NoActiveGame)?;
             if game.turn != turn {
-                return Err(format!(
-                    "reveal turn mismatch: game {}, got {}",
-                    game.turn, turn
-                ));
+                return Err(GameError::TurnMismatch(TurnParams {
+                    expected: game.turn,
+                    got: turn,
+                }));
             }
             let opponent_node = game
                 .players
@@ -325,31 +592,27 @@ impl MemeWarsState {
             self.next_instance = game.next_instance;
             (opponent_node, prev_turn, host_is_me)
         };
-        if let Some(node) = opponent_node.clone() {
-            let _ = self
-                .send_wire_message(
-                    &node,
-                    WireMessage::Reveal(WireReveal {
-                        seat,
-                        plan,
-                        salt,
-                        turn,
-                    }),
-                )
-                .await;
-        }
-        let snapshot = self.compose_snapshot();
-        self.broadcast_snapshot();
-        if host_is_me {
-            if let (Some(node), Some(game_state)) = (opponent_node, self.game.clone()) {
-                if game_state.turn > prev_turn {
-                    let _ = self
-                        .send_wire_message(&node, WireMessage::DebugState(game_state))
-                        .await;
-                }
+        let reveal = WireMessage::Reveal(WireReveal {
+            seat,
+            plan,
+            salt,
+            turn,
+        });
+        // When our reveal resolved the turn, the host's resulting state rides along in the same
+        // request so the opponent checks it against their own resolution straight away.
+        let message = match self.game().cloned() {
+            Some(game_state) if host_is_me && game_state.turn > prev_turn => {
+                WireMessage::Batch(vec![reveal, WireMessage::DebugState(game_state)])
             }
-        }
-        Ok(snapshot)
+            _ => reveal,
+        };
+        let sent = match opponent_node {
+            Some(node) => self.send_turn_update(&node, message).await,
+            None => Ok(()),
+        };
+        let snapshot = self.compose_client_snapshot();
+        self.broadcast_snapshot();
+        sent.map(|_| snapshot)
     }
 
     #[local]
@@ -357,12 +620,14 @@ impl MemeWarsState {
     async fn play_local_turn(
         &mut self,
         params: (TurnPlan, TurnPlan),
-    ) -> Result<GameSnapshot, String> {
+    ) -> Result<GameSnapshot, GameError> {
         let (host, opponent) = params;
-        let game = self.game.as_mut().ok_or("no active game")?;
+        let game = self.game_mut().ok_or(GameError::NoActiveGame)?;
+        validate_plan(&host, game.action_points_for(&Seat::Host))?;
+        validate_plan(&opponent, game.action_points_for(&Seat::Opponent))?;
         game.resolve_turn(host, opponent)?;
         self.next_instance = game.next_instance;
-        let snapshot = self.compose_snapshot();
+        let snapshot = self.compose_client_snapshot();
         self.broadcast_snapshot();
         Ok(snapshot)
     }
@@ -370,805 +635,5206 @@ impl MemeWarsState {
     #[local]
     #[remote]
     #[http]
-    async fn handle_wire_message(&mut self, message: WireMessage) -> Result<WireReply, String> {
-        match message {
-            WireMessage::Commit(payload) => {
-                let game = self.game.as_mut().ok_or("no active game")?;
-                if game.turn != payload.turn {
-                    return Err(format!(
-                        "wire commit turn mismatch: game {}, got {}",
-                        game.turn, payload.turn
-                    ));
-                }
-                game.record_commit(payload.seat, payload.hash)?;
-                let snapshot = self.compose_snapshot();
-                self.broadcast_snapshot();
-                Ok(WireReply::Snapshot(snapshot))
-            }
-            WireMessage::Reveal(payload) => {
-                let game = self.game.as_mut().ok_or("no active game")?;
-                if game.turn != payload.turn {
-                    return Err(format!(
-                        "wire reveal turn mismatch: game {}, got {}",
-                        game.turn, payload.turn
-                    ));
-                }
-                game.record_reveal(payload.seat, payload.plan, payload.salt)?;
-                self.next_instance = game.next_instance;
-                let snapshot = self.compose_snapshot();
-                self.broadcast_snapshot();
-                Ok(WireReply::Snapshot(snapshot))
-            }
-            WireMessage::RequestStateHash => {
-                let game = self.game.as_ref().ok_or("no active game")?;
-                Ok(WireReply::StateHash(game.state_hash()))
-            }
-            WireMessage::StateHash(remote) => {
-                self.validate_state_hash(&remote)?;
-                Ok(WireReply::Ack)
-            }
-            WireMessage::DebugState(remote_game) => {
-                if let Some(local) = self.game.as_ref() {
-                    let local_hash = local.state_hash();
-                    let remote_hash = remote_game.state_hash();
-                    if local_hash != remote_hash {
-                        println!(
-                            "⚠️ state mismatch: local turn {} hash {}, remote turn {} hash {}",
-                            local_hash.turn, local_hash.hash, remote_hash.turn, remote_hash.hash
-                        );
-                    } else {
-                        println!(
-                            "✅ state match debug check turn {} hash {}",
-                            remote_hash.turn, remote_hash.hash
-                        );
-                    }
-                } else {
-                    println!("⚠️ debug state received but no local game");
-                }
-                Ok(WireReply::Ack)
-            }
-            WireMessage::CallBased(payload) => {
-                let game = self.game.as_mut().ok_or("no active game")?;
-                game.call_based(payload.seat)?;
-                let snapshot = self.compose_snapshot();
-                self.broadcast_snapshot();
-                Ok(WireReply::Snapshot(snapshot))
-            }
-            WireMessage::AcceptBased(payload) => {
-                let game = self.game.as_mut().ok_or("no active game")?;
-                game.accept_based(payload.seat)?;
-                let snapshot = self.compose_snapshot();
-                self.broadcast_snapshot();
-                Ok(WireReply::Snapshot(snapshot))
-            }
-            WireMessage::FoldBased(payload) => {
-                let game = self.game.as_mut().ok_or("no active game")?;
-                game.fold_based(payload.seat)?;
-                let snapshot = self.compose_snapshot();
-                self.broadcast_snapshot();
-                Ok(WireReply::Snapshot(snapshot))
-            }
-            WireMessage::JoinLobby(payload) => {
-                let lobby = self
-                    .lobbies
-                    .iter_mut()
-                    .find(|l| l.id == payload.lobby_id)
-                    .ok_or("Lobby not found")?;
-                if lobby.opponent.is_some() {
-                    return Err("Lobby already has an opponent".into());
-                }
-                lobby.opponent = Some(payload.node_id);
-                lobby.opponent_deck = payload.deck;
-                let snapshot = self.compose_snapshot();
-                self.broadcast_snapshot();
-                Ok(WireReply::Snapshot(snapshot))
-            }
-            WireMessage::RequestSnapshot => {
-                let snapshot = self.compose_snapshot();
-                Ok(WireReply::Snapshot(snapshot))
-            }
-            WireMessage::SyncGame(game) => {
-                self.next_instance = game.next_instance;
-                self.game = Some(game);
-                let snapshot = self.compose_snapshot();
-                self.broadcast_snapshot();
-                Ok(WireReply::Snapshot(snapshot))
+    async fn handle_wire_envelope(&mut self, envelope: WireEnvelope) -> Result<WireReply, GameError> {
+        let game_id = envelope.game_id.clone();
+        let message = envelope.open()?;
+        // A game we hold but aren't showing is updated in the background. Untagged messages go to
+        // the game on screen, as they did before games had ids; a game we don't hold is an error
+        // unless the message doesn't need one.
+        let shown = self.current_game.clone();
+        let id = match game_id {
+            Some(id) if !self.games.contains_key(&id) && message.is_game_scoped() => {
+                return Err(GameError::GameNotFound(id));
             }
+            Some(id) if shown.as_ref() != Some(&id) && self.games.contains_key(&id) => id,
+            _ => return self.dispatch_wire_message(message),
+        };
+        self.current_game = Some(id);
+        self.background = true;
+        let reply = self.dispatch_wire_message(message);
+        self.background = false;
+        self.current_game = shown;
+        reply
+    }
+
+    // Unversioned entry point for our own websocket actions. Peers must go through
+    // handle_wire_envelope so their version is checked first.
+    #[local]
+    #[http]
+    async fn handle_wire_message(&mut self, message: WireMessage) -> Result<WireReply, GameError> {
+        match self.dispatch_wire_message(message)? {
+            // Our own UI gets the game as our seat sees it.
+            WireReply::Snapshot(snapshot) => Ok(WireReply::Snapshot(Box::new(snapshot.for_client(&our().node)))),
+            reply => Ok(reply),
         }
     }
 
     #[local]
     #[http]
-    async fn get_state_hash(&self) -> Result<Option<StateHash>, String> {
-        Ok(self.game.as_ref().map(|g| g.state_hash()))
+    async fn select_game(&mut self, game_id: String) -> Result<GameSnapshot, GameError> {
+        self.focus_game(game_id)?;
+        Ok(self.compose_client_snapshot())
     }
 
     #[local]
     #[http]
-    async fn send_wire(&mut self, params: (String, WireMessage)) -> Result<WireReply, String> {
-        let (node, message) = params;
-        self.send_wire_message(&node, message).await
+    async fn set_cosmetics(&mut self, cosmetics: PlayerCosmetics) -> Result<GameSnapshot, GameError> {
+        self.apply_cosmetics(cosmetics);
+        let snapshot = self.compose_client_snapshot();
+        self.broadcast_snapshot();
+        Ok(snapshot)
     }
 
-    #[ws]
-    async fn websocket(
-        &mut self,
-        channel_id: u32,
-        message_type: WsMessageType,
-        blob: LazyLoadBlob,
-    ) {
-        if !matches!(message_type, WsMessageType::Text | WsMessageType::Binary) {
-            return;
-        }
-        println!("WS recv chan={} bytes={}", channel_id, blob.bytes.len());
-        let payload = String::from_utf8_lossy(&blob.bytes).to_string();
-        let parsed: Result<WsEnvelope<WsClientMessage>, _> = serde_json::from_str(&payload);
-        match parsed {
-            Ok(envelope) => {
-                let request_id = envelope.id.clone();
-                println!(
-                    "WS parsed message={:?} id={:?}",
-                    envelope.message, request_id
-                );
-                match self.process_ws_message(envelope.message).await {
-                    Ok(response_msg) => {
-                        let envelope = WsEnvelope {
-                            id: request_id,
-                            message: response_msg,
-                        };
-                        println!("WS responding ok id={:?}", envelope.id);
-                        self.push_ws_message(WsTarget::Channel(channel_id), envelope);
-                    }
-                    Err(err) => {
-                        println!("WS handler error id={:?} err={}", request_id, err);
-                        let envelope = WsEnvelope {
-                            id: request_id,
-                            message: WsServerMessage::Error(err),
-                        };
-                        self.push_ws_message(WsTarget::Channel(channel_id), envelope);
-                    }
-                }
-            }
-            Err(e) => {
-                println!("WS parse error: {}", e);
-                let envelope = WsEnvelope {
-                    id: None,
-                    message: WsServerMessage::Error(format!("invalid ws payload: {}", e)),
-                };
-                self.push_ws_message(WsTarget::Channel(channel_id), envelope);
-            }
+    // Loads a finished or recorded match for scrubbing; refuses to replace a game still in play.
+    #[local]
+    #[http]
+    async fn load_replay(&mut self, params: (u64, Vec<GameEvent>)) -> Result<GameSnapshot, GameError> {
+        let (seed, events) = params;
+        if self
+            .game()
+            .is_some_and(|g| !matches!(g.phase, Phase::Lobby | Phase::GameOver))
+        {
+            return Err(GameError::GameNotOver);
         }
+        self.install_game(GameState::replay_from(seed, &events)?);
+        let snapshot = self.compose_client_snapshot();
+        self.broadcast_snapshot();
+        Ok(snapshot)
     }
-}
 
-impl MemeWarsState {
-    fn compose_snapshot(&self) -> GameSnapshot {
-        let mut lobbies = self.lobbies.clone();
-        for lob in &self.discovered_lobbies {
-            if !lobbies
-                .iter()
-                .any(|existing| existing.id == lob.id && existing.host == lob.host)
-            {
-                lobbies.push(lob.clone());
-            }
-        }
-        // Filter out lobbies where the game is over
-        let game_over = self
-            .game
-            .as_ref()
-            .map(|g| g.phase == Phase::GameOver)
-            .unwrap_or(false);
-        if game_over {
-            lobbies.retain(|l| !l.started);
-        }
-        GameSnapshot {
-            catalog: self.catalog.clone(),
-            game: self.game.clone(),
-            lobbies,
+    // Balance testing only. The endpoint list can't be cfg-gated, so release builds refuse at runtime.
+    #[local]
+    #[http]
+    async fn set_stakes(&mut self, stakes: u8) -> Result<GameSnapshot, GameError> {
+        if !cfg!(debug_assertions) {
+            return Err(GameError::DebugOnly);
         }
+        let game = self.game_mut().ok_or(GameError::NoActiveGame)?;
+        game.override_stakes(stakes);
+        let snapshot = self.compose_client_snapshot();
+        self.broadcast_snapshot();
+        Ok(snapshot)
     }
 
-    fn push_ws_message(&self, target: WsTarget, envelope: WsEnvelope<WsServerMessage>) {
-        if let Some(server) = get_server() {
-            if let Ok(bytes) = serde_json::to_vec(&envelope) {
-                match target {
-                    WsTarget::Channel(channel_id) => {
-                        let blob = LazyLoadBlob {
-                            mime: None,
-                            bytes: bytes.clone(),
+    #[local]
+    #[http]
+    async fn diagnose(&self, params: (Seat, TurnPlan)) -> Result<Vec<Diagnostic>, GameError> {
+        let (seat, plan) = params;
+        let game = self.game().ok_or(GameError::NoActiveGame)?;
+        // Diagnosing the other seat's plan would tell us what they hold.
+        let me = our().node;
+        if game.player_node(&seat).as_deref() != Some(me.as_str()) {
+            return Err(GameError::NotSeated(me));
+        }
+        Ok(game.diagnose(&seat, &plan))
+    }
+
+    // Dry run of a turn against a passing opponent. Nothing here is stored or broadcast.
+    #[local]
+    #[http]
+    async fn preview_turn(&self, params: (Seat, TurnPlan)) -> Result<GameSnapshot, GameError> {
+        let (seat, plan) = params;
+        let preview = self.game().ok_or(GameError::NoActiveGame)?.preview_turn(&seat, plan)?;
+        let mut snapshot = self.compose_client_snapshot();
+        snapshot.total_events = preview.events.len();
+        snapshot.projected_yield = preview.projected_yield();
+        let mut view = preview.client_view(&our().node);
+        view.trim_history(SNAPSHOT_EVENT_WINDOW);
+        snapshot.game = Some(view);
+        Ok(snapshot)
+    }
+
+    #[local]
+    #[http]
+    async fn sample_decks(&self) -> Result<Vec<(String, Vec<String>)>, GameError> {
+        Ok(sample_decks())
+    }
+
+    #[local]
+    #[http]
+    async fn analyze_deck(&self, deck: Vec<String>) -> Result<DeckAnalysis, GameError> {
+        analyze_deck(&self.catalog, &deck)
+    }
+
+    // Snapshots can leave the catalog out once a client has fetched it from here.
+    #[local]
+    #[http]
+    async fn get_catalog(&mut self) -> Result<Vec<CardDefinition>, GameError> {
+        self.catalog_served = true;
+        Ok(self.catalog.clone())
+    }
+
+    #[local]
+    #[http]
+    async fn find_cards(&self, filter: CardFilter) -> Result<Vec<CardDefinition>, GameError> {
+        Ok(find_cards(&self.catalog, &filter))
+    }
+
+    // A pack dealt from `seed`, for trying out packs until draft lobbies deal their own.
+    #[local]
+    #[http]
+    async fn draft_pack(&self, params: (u64, usize)) -> Result<Vec<String>, GameError> {
+        let (seed, size) = params;
+        Ok(generate_pack(&mut FairRandomState::with_seeds(seed, seed), size))
+    }
+
+    #[local]
+    #[http]
+    async fn get_match_history(&self) -> Result<Vec<MatchRecord>, GameError> {
+        Ok(self.match_history.clone())
+    }
+
+    // Pages through the full event log; snapshots only carry the newest SNAPSHOT_EVENT_WINDOW.
+    #[local]
+    #[http]
+    async fn events_since(&self, params: (usize, usize)) -> Result<Vec<GameEvent>, GameError> {
+        let (from, limit) = params;
+        self.client_events_since(from, limit)
+    }
+
+    #[local]
+    #[http]
+    async fn get_state_hash(&self) -> Result<Option<StateHash>, GameError> {
+        Ok(self.game().map(|g| g.state_hash()))
+    }
+
+    #[local]
+    #[http]
+    async fn mana(&self, seat: Seat) -> Result<(u8, u8), GameError> {
+        let game = self.game().ok_or(GameError::NoActiveGame)?;
+        game.mana_for(&seat)
+    }
+
+    #[local]
+    #[http]
+    async fn all_mana(&self) -> Result<Vec<(Seat, u8, u8)>, GameError> {
+        let game = self.game().ok_or(GameError::NoActiveGame)?;
+        let mut out = vec![];
+        for seat in [Seat::Host, Seat::Opponent] {
+            let (current, max) = game.mana_for(&seat)?;
+            out.push((seat, current, max));
+        }
+        Ok(out)
+    }
+
+    // Round trip to the seated opponent in milliseconds. A reply also flushes any queued updates.
+    #[local]
+    #[http]
+    async fn ping_opponent(&mut self) -> Result<u64, GameError> {
+        let node = self.opponent_node().ok_or(GameError::NoActiveGame)?;
+        let nonce: u64 = rand::thread_rng().gen();
+        let started = std::time::Instant::now();
+        let latency = match self.send_wire_message(&node, WireMessage::Ping { nonce }).await? {
+            WireReply::Pong { nonce: echoed } if echoed == nonce => {
+                started.elapsed().as_millis() as u64
+            }
+            _ => return Err(GameError::UnexpectedReply),
+        };
+        let _ = self.flush_pending_for(&node).await;
+        Ok(latency)
+    }
+
+    // Ends a game whose opponent has gone quiet. Only once an update to them has run out of retries
+    // and a fresh ping goes unanswered too.
+    #[local]
+    #[http]
+    async fn resolve_disconnect(&mut self, resolution: DisconnectResolution) -> Result<GameSnapshot, GameError> {
+        let node = self.opponent_node().ok_or(GameError::NoActiveGame)?;
+        if !self.pending_sync.iter().any(|(n, _, _)| *n == node) || self.ping_opponent().await.is_ok() {
+            return Err(GameError::OpponentReachable);
+        }
+        self.settle_disconnect(resolution)?;
+        let snapshot = self.compose_client_snapshot();
+        self.broadcast_snapshot();
+        Ok(snapshot)
+    }
+
+    #[local]
+    #[http]
+    async fn verify_rng(&self) -> Result<bool, GameError> {
+        let game = self.game().ok_or(GameError::NoActiveGame)?;
+        Ok(game.rng.verify(game.game_seed))
+    }
+
+    #[local]
+    #[http]
+    async fn resolution_order(
+        &self,
+        params: (TurnPlan, TurnPlan),
+    ) -> Result<Vec<ResolutionEntry>, GameError> {
+        let (host_plan, opponent_plan) = params;
+        let game = self.game().ok_or(GameError::NoActiveGame)?;
+        Ok(game.resolution_order(&host_plan, &opponent_plan))
+    }
+
+    #[local]
+    #[http]
+    async fn send_wire(&mut self, params: (String, WireMessage)) -> Result<WireReply, GameError> {
+        let (node, message) = params;
+        self.send_wire_message(&node, message).await
+    }
+
+    #[ws]
+    async fn websocket(
+        &mut self,
+        channel_id: u32,
+        message_type: WsMessageType,
+        blob: LazyLoadBlob,
+    ) {
+        if !matches!(message_type, WsMessageType::Text | WsMessageType::Binary) {
+            return;
+        }
+        println!("WS recv chan={} bytes={}", channel_id, blob.bytes.len());
+        let payload = String::from_utf8_lossy(&blob.bytes).to_string();
+        let parsed = WsEnvelope::<WsClientMessage>::parse(&payload);
+        match parsed {
+            Ok(envelope) => {
+                let request_id = envelope.id.clone();
+                println!(
+                    "WS parsed message={:?} id={:?}",
+                    envelope.message, request_id
+                );
+                let focused = match envelope.game_id {
+                    Some(game_id) => self.focus_game(game_id),
+                    None => Ok(()),
+                };
+                let result = match focused {
+                    Ok(()) => self.process_ws_message(envelope.message).await,
+                    Err(err) => Err(err),
+                };
+                match result {
+                    Ok(response_msg) => {
+                        let envelope = WsEnvelope {
+                            id: request_id,
+                            game_id: None,
+                            message: response_msg,
                         };
-                        println!(
-                            "WS push to channel {} message={:?}",
-                            channel_id, envelope.message
-                        );
-                        server::send_ws_push(channel_id, WsMessageType::Text, blob)
+                        println!("WS responding ok id={:?}", envelope.id);
+                        self.push_ws_message(WsTarget::Channel(channel_id), envelope);
                     }
-                    WsTarget::Broadcast => {
-                        println!(
-                            "WS broadcast message={:?} paths={:?}",
-                            envelope.message, self.ws_paths
-                        );
-                        let mut paths = self.ws_paths.clone();
-                        if !paths.iter().any(|p| p == WS_PATH) {
-                            paths.push(WS_PATH.to_string());
-                        }
-                        for path in paths {
-                            let blob = LazyLoadBlob {
-                                mime: None,
-                                bytes: bytes.clone(),
-                            };
-                            let _ = server.ws_push_all_channels(&path, WsMessageType::Text, blob);
-                        }
+                    Err(err) => {
+                        println!("WS handler error id={:?} err={}", request_id, err);
+                        let envelope = WsEnvelope {
+                            id: request_id,
+                            game_id: None,
+                            message: WsServerMessage::GameError(WsGameError {
+                                message: err.to_string(),
+                                error: err,
+                            }),
+                        };
+                        self.push_ws_message(WsTarget::Channel(channel_id), envelope);
                     }
                 }
             }
+            Err(e) => {
+                println!("WS parse error: {}", e);
+                let envelope = WsEnvelope {
+                    id: None,
+                    game_id: None,
+                    message: WsServerMessage::Error(format!("invalid ws payload: {}", e)),
+                };
+                self.push_ws_message(WsTarget::Channel(channel_id), envelope);
+            }
         }
     }
+}
 
-    fn broadcast_snapshot(&self) {
-        let snapshot = self.compose_snapshot();
-        let envelope = WsEnvelope {
-            id: None,
-            message: WsServerMessage::Snapshot(snapshot),
-        };
-        self.push_ws_message(WsTarget::Broadcast, envelope);
+impl MemeWarsState {
+    fn dispatch_wire_message(&mut self, message: WireMessage) -> Result<WireReply, GameError> {
+        match message {
+            WireMessage::Batch(messages) => self.apply_wire_batch(messages),
+            message => self.apply_wire_message(message),
+        }
     }
 
-    async fn process_ws_message(
-        &mut self,
-        msg: WsClientMessage,
-    ) -> Result<WsServerMessage, String> {
-        println!("processing ws message {:?}", msg);
-        match msg {
-            WsClientMessage::GetSnapshot => Ok(WsServerMessage::Snapshot(self.compose_snapshot())),
-            WsClientMessage::NewGame { opponent } => {
-                let snapshot = self.new_game(opponent).await?;
-                Ok(WsServerMessage::Snapshot(snapshot))
-            }
-            WsClientMessage::HostLobby(config) => {
-                let snapshot = self.host_lobby(config).await?;
-                Ok(WsServerMessage::Snapshot(snapshot))
+    // Applies a batch in order and answers with the last reply. If any message fails, the game,
+    // lobbies and chat go back to how they were before the batch so a half-applied batch can't
+    // desync the peers. Broadcasts, and the match history they record, wait until it commits.
+    fn apply_wire_batch(&mut self, messages: Vec<WireMessage>) -> Result<WireReply, GameError> {
+        let (saved, next_instance) = (self.game().cloned(), self.next_instance);
+        let (lobbies, chat_log) = (self.lobbies.clone(), self.chat_log.clone());
+        self.deferred_broadcast = Some(false);
+        let mut reply = Ok(WireReply::Ack);
+        for message in messages {
+            reply = self.apply_wire_message(message);
+            if reply.is_err() {
+                self.restore_game(saved);
+                self.next_instance = next_instance;
+                self.lobbies = lobbies;
+                self.chat_log = chat_log;
+                break;
             }
-            WsClientMessage::JoinLobby { lobby_id, deck } => {
-                let snapshot = self.join_lobby((lobby_id, deck)).await?;
-                Ok(WsServerMessage::Snapshot(snapshot))
+        }
+        if self.deferred_broadcast.take() == Some(true) && reply.is_ok() {
+            self.broadcast_snapshot();
+        }
+        reply
+    }
+
+    fn apply_wire_message(&mut self, message: WireMessage) -> Result<WireReply, GameError> {
+        match message {
+            WireMessage::Batch(_) => Err(GameError::NestedBatch),
+            WireMessage::Commit(payload) => {
+                self.ensure_source_is(&payload.seat)?;
+                let game = self.game_mut().ok_or(GameError::NoActiveGame)?;
+                if game.turn != payload.turn {
+                    return Err(GameError::TurnMismatch(TurnParams {
+                        expected: game.turn,
+                        got: payload.turn,
+                    }));
+                }
+                game.record_commit(payload.seat, payload.hash)?;
+                let snapshot = self.compose_snapshot();
+                self.broadcast_snapshot();
+                Ok(WireReply::Snapshot(snapshot.into()))
             }
-            WsClientMessage::StartLobbyGame { lobby_id } => {
-                let snapshot = self.start_lobby_game(lobby_id).await?;
-                Ok(WsServerMessage::Snapshot(snapshot))
+            WireMessage::RetractCommit(payload) => {
+                self.ensure_source_is(&payload.seat)?;
+                let game = self.game_mut().ok_or(GameError::NoActiveGame)?;
+                if game.turn != payload.turn {
+                    return Err(GameError::TurnMismatch(TurnParams {
+                        expected: game.turn,
+                        got: payload.turn,
+                    }));
+                }
+                game.retract_commit(payload.seat)?;
+                let snapshot = self.compose_snapshot();
+                self.broadcast_snapshot();
+                Ok(WireReply::Snapshot(snapshot.into()))
             }
-            WsClientMessage::FetchRemoteLobbies { host_node } => {
-                let snapshot = self.fetch_remote_lobbies(host_node).await?;
-                Ok(WsServerMessage::Snapshot(snapshot))
+            WireMessage::Reveal(payload) => {
+                self.ensure_source_is(&payload.seat)?;
+                let game = self.game_mut().ok_or(GameError::NoActiveGame)?;
+                if game.turn != payload.turn {
+                    return Err(GameError::TurnMismatch(TurnParams {
+                        expected: game.turn,
+                        got: payload.turn,
+                    }));
+                }
+                game.record_reveal(payload.seat, payload.plan, payload.salt)?;
+                self.next_instance = game.next_instance;
+                let snapshot = self.compose_snapshot();
+                self.broadcast_snapshot();
+                Ok(WireReply::Snapshot(snapshot.into()))
             }
-            WsClientMessage::JoinRemoteLobby {
-                host_node,
-                lobby_id,
-                deck,
-            } => {
-                let snapshot = self.join_remote_lobby((host_node, lobby_id, deck)).await?;
-                Ok(WsServerMessage::Snapshot(snapshot))
+            WireMessage::RequestStateHash => {
+                let game = self.game().ok_or(GameError::NoActiveGame)?;
+                Ok(WireReply::StateHash(game.state_hash()))
             }
-            WsClientMessage::SyncRemoteGame { host_node } => {
-                let snapshot = self.sync_remote_game(host_node).await?;
-                Ok(WsServerMessage::Snapshot(snapshot))
+            WireMessage::StateHash(remote) => {
+                self.validate_state_hash(&remote)?;
+                Ok(WireReply::Ack)
             }
-            WsClientMessage::CommitTurn {
-                seat,
-                plan,
-                salt,
-                turn,
-            } => {
-                let snapshot = commit_turn_with_plan(self, seat, plan, salt, turn).await?;
-                Ok(WsServerMessage::Snapshot(snapshot))
+            WireMessage::DebugState(remote_game) => {
+                if let Some(local) = self.game() {
+                    let local_hash = local.state_hash();
+                    let remote_hash = remote_game.state_hash();
+                    if local_hash != remote_hash {
+                        println!(
+                            "⚠️ state mismatch: local turn {} hash {}, remote turn {} hash {}",
+                            local_hash.turn, local_hash.hash, remote_hash.turn, remote_hash.hash
+                        );
+                    } else {
+                        println!(
+                            "✅ state match debug check turn {} hash {}",
+                            remote_hash.turn, remote_hash.hash
+                        );
+                    }
+                } else {
+                    println!("⚠️ debug state received but no local game");
+                }
+                Ok(WireReply::Ack)
             }
-            WsClientMessage::RevealTurn {
-                seat,
-                plan,
-                salt,
-                turn,
-            } => {
-                let snapshot = self.reveal_turn((seat, plan, salt, turn)).await?;
-                Ok(WsServerMessage::Snapshot(snapshot))
+            WireMessage::CallBased(payload) => {
+                self.ensure_source_is(&payload.seat)?;
+                let game = self.game_mut().ok_or(GameError::NoActiveGame)?;
+                game.call_based(payload.seat)?;
+                let snapshot = self.compose_snapshot();
+                self.broadcast_snapshot();
+                Ok(WireReply::Snapshot(snapshot.into()))
             }
-            WsClientMessage::Reset => {
-                self.reset().await?;
-                Ok(WsServerMessage::Snapshot(self.compose_snapshot()))
+            WireMessage::AcceptBased(payload) => {
+                self.ensure_source_is(&payload.seat)?;
+                let game = self.game_mut().ok_or(GameError::NoActiveGame)?;
+                game.accept_based(payload.seat)?;
+                let snapshot = self.compose_snapshot();
+                self.broadcast_snapshot();
+                Ok(WireReply::Snapshot(snapshot.into()))
             }
-            WsClientMessage::PlayLocalTurn {
-                host_plan,
-                opponent_plan,
-            } => {
-                let opponent = opponent_plan.unwrap_or_default();
-                let snapshot = self.play_local_turn((host_plan, opponent)).await?;
-                Ok(WsServerMessage::Snapshot(snapshot))
+            WireMessage::FoldBased(payload) => {
+                self.ensure_source_is(&payload.seat)?;
+                let game = self.game_mut().ok_or(GameError::NoActiveGame)?;
+                game.fold_based(payload.seat)?;
+                let snapshot = self.compose_snapshot();
+                self.broadcast_snapshot();
+                Ok(WireReply::Snapshot(snapshot.into()))
             }
-            WsClientMessage::CallBased { seat } => {
-                let seat_clone = seat.clone();
-                let opponent_node = self
+            WireMessage::CancelBased(payload) => {
+                self.ensure_source_is(&payload.seat)?;
+                let game = self.game_mut().ok_or(GameError::NoActiveGame)?;
+                game.cancel_based(payload.seat)?;
+                let snapshot = self.compose_snapshot();
+                self.broadcast_snapshot();
+                Ok(WireReply::Snapshot(snapshot.into()))
+            }
+            WireMessage::JoinLobby(payload) => {
+                let node = payload.node_id.clone();
+                self.admit_to_lobby(payload)?;
+                let snapshot = self.compose_remote_snapshot(&node);
+                self.broadcast_snapshot();
+                Ok(WireReply::Snapshot(snapshot.into()))
+            }
+            WireMessage::RequestSnapshot => {
+                let node = source().node;
+                let snapshot = self.compose_remote_snapshot(&node);
+                // The player across the table deals from the full game (SyncGame sends it too);
+                // any other node only gets the view its seat, or lack of one, allows.
+                let seated = snapshot
                     .game
                     .as_ref()
-                    .and_then(|g| g.player_node(&seat.other()));
-                let reply = self
-                    .handle_wire_message(WireMessage::CallBased(StakeNotice { seat: seat.clone() }))
-                    .await?;
-                if let Some(node) = opponent_node {
-                    let _ = self
-                        .send_wire_message(
-                            &node,
-                            WireMessage::CallBased(StakeNotice { seat: seat_clone }),
-                        )
-                        .await;
+                    .is_some_and(|g| g.players.iter().any(|p| p.node_id == node));
+                let snapshot = if seated { snapshot } else { snapshot.for_client(&node) };
+                Ok(WireReply::Snapshot(snapshot.into()))
+            }
+            WireMessage::SyncGame(game) => {
+                let node = source().node;
+                let us = our().node;
+                let seat_of = |n: &str| game.players.iter().find(|p| p.node_id == n).map(|p| p.seat.clone());
+                match (seat_of(&us), seat_of(&node)) {
+                    // The node across the table deals and updates the games we play in.
+                    (Some(ours), Some(theirs)) if ours != theirs => self.check_agreed_seed(&node, &game)?,
+                    // Unseated, we only take the seatless view from the host we subscribed to.
+                    (None, _) if self.watching.as_deref() == Some(node.as_str()) => {}
+                    _ => return Err(GameError::NotSeated(node)),
                 }
-                if let WireReply::Snapshot(snapshot) = reply {
-                    Ok(WsServerMessage::Snapshot(snapshot))
-                } else {
-                    Ok(WsServerMessage::Ack)
+                self.next_instance = game.next_instance;
+                self.install_game(game);
+                let snapshot = self.compose_snapshot();
+                self.broadcast_snapshot();
+                Ok(WireReply::Snapshot(snapshot.into()))
+            }
+            WireMessage::Rematch(payload) => {
+                // Only the seat that joined our lobby asks us for a rematch.
+                self.ensure_source_is(&Seat::Opponent)?;
+                // The opponent runs the seed handshake first so we can't pick the shuffle alone.
+                if self.agreed_seed(&payload.lobby_id, &source().node).is_none() {
+                    return Err(GameError::NoAgreedSeed);
                 }
+                self.prepare_rematch(&payload.lobby_id)?;
+                let snapshot = self.compose_snapshot();
+                self.broadcast_snapshot();
+                Ok(WireReply::Snapshot(snapshot.into()))
             }
-            WsClientMessage::AcceptBased { seat } => {
-                let seat_clone = seat.clone();
-                let opponent_node = self
-                    .game
-                    .as_ref()
-                    .and_then(|g| g.player_node(&seat.other()));
-                let reply = self
-                    .handle_wire_message(WireMessage::AcceptBased(StakeNotice {
-                        seat: seat.clone(),
-                    }))
-                    .await?;
-                if let Some(node) = opponent_node {
-                    let _ = self
-                        .send_wire_message(
-                            &node,
-                            WireMessage::AcceptBased(StakeNotice { seat: seat_clone }),
-                        )
-                        .await;
+            WireMessage::Chat(mut chat) => {
+                self.ensure_seated_source()?;
+                if chat.text.chars().count() > MAX_CHAT_LEN {
+                    return Err(GameError::ChatTooLong);
                 }
-                if let WireReply::Snapshot(snapshot) = reply {
-                    Ok(WsServerMessage::Snapshot(snapshot))
-                } else {
-                    Ok(WsServerMessage::Ack)
+                chat.from = source().node;
+                self.record_chat(chat);
+                Ok(WireReply::Ack)
+            }
+            WireMessage::Ping { nonce } => Ok(WireReply::Pong { nonce }),
+            WireMessage::SeedCommit(payload) => {
+                let value = self.accept_seed_commit(&source().node, payload)?;
+                Ok(WireReply::SeedValue { value })
+            }
+            WireMessage::SeedReveal(payload) => {
+                self.accept_seed_reveal(&source().node, payload)?;
+                Ok(WireReply::Ack)
+            }
+            WireMessage::ChooseDraw(payload) => {
+                let node = source().node;
+                let game = self.game_mut().ok_or(GameError::NoActiveGame)?;
+                if game.turn != payload.turn {
+                    return Err(GameError::TurnMismatch(TurnParams {
+                        expected: game.turn,
+                        got: payload.turn,
+                    }));
+                }
+                // Only the seat that drew a card may choose it.
+                if game.draw_chooser(&payload.keep).is_some_and(|owner| owner != node) {
+                    return Err(GameError::NotSeated(node));
                 }
+                game.choose_draw(&payload.keep)?;
+                let snapshot = self.compose_snapshot();
+                self.broadcast_snapshot();
+                Ok(WireReply::Snapshot(snapshot.into()))
             }
-            WsClientMessage::FoldBased { seat } => {
-                let seat_clone = seat.clone();
-                let opponent_node = self
-                    .game
-                    .as_ref()
-                    .and_then(|g| g.player_node(&seat.other()));
-                let reply = self
-                    .handle_wire_message(WireMessage::FoldBased(StakeNotice { seat: seat.clone() }))
-                    .await?;
-                if let Some(node) = opponent_node {
-                    let _ = self
-                        .send_wire_message(
-                            &node,
-                            WireMessage::FoldBased(StakeNotice { seat: seat_clone }),
-                        )
-                        .await;
+            WireMessage::DiscardToHandSize(payload) => {
+                let node = source().node;
+                let game = self.game_mut().ok_or(GameError::NoActiveGame)?;
+                if game.turn != payload.turn {
+                    return Err(GameError::TurnMismatch(TurnParams {
+                        expected: game.turn,
+                        got: payload.turn,
+                    }));
                 }
-                if let WireReply::Snapshot(snapshot) = reply {
-                    Ok(WsServerMessage::Snapshot(snapshot))
-                } else {
-                    Ok(WsServerMessage::Ack)
+                // Only the seat holding the cards may trim its hand.
+                let owner = payload.keep.first().and_then(|id| game.hand_holder(id));
+                if owner.is_some_and(|owner| owner != node) {
+                    return Err(GameError::NotSeated(node));
+                }
+                game.discard_to_hand_size(&payload.keep)?;
+                let snapshot = self.compose_snapshot();
+                self.broadcast_snapshot();
+                Ok(WireReply::Snapshot(snapshot.into()))
+            }
+            WireMessage::VerifyRandom(event) => {
+                let game = self.game().ok_or(GameError::NoActiveGame)?;
+                game.rng.verify_event(&event).map_err(GameError::RandomUnverified)?;
+                Ok(WireReply::Ack)
+            }
+            WireMessage::Subscribe(payload) => {
+                if payload.node_id != source().node {
+                    return Err(GameError::SubscriberMismatch);
+                }
+                let snapshot = self.compose_remote_snapshot(&payload.node_id).for_client("");
+                if !self.spectators.contains(&payload.node_id) {
+                    self.spectators.push(payload.node_id);
+                }
+                Ok(WireReply::Snapshot(snapshot.into()))
+            }
+        }
+    }
+
+    fn compose_snapshot(&self) -> GameSnapshot {
+        self.compose_snapshot_with(true)
+    }
+
+    // What our own UI gets over HTTP: the game as our seat sees it.
+    fn compose_client_snapshot(&self) -> GameSnapshot {
+        self.compose_snapshot().for_client(&our().node)
+    }
+
+    // A page of the event log as our seat sees it, at most SNAPSHOT_EVENT_WINDOW long.
+    fn client_events_since(&self, from: usize, limit: usize) -> Result<Vec<GameEvent>, GameError> {
+        let game = self.game().ok_or(GameError::NoActiveGame)?;
+        let view = game.client_view(&our().node);
+        Ok(view.events_since(from, limit.min(SNAPSHOT_EVENT_WINDOW)).to_vec())
+    }
+
+    // Clients that connect later ask for a snapshot with the catalog before anything else.
+    fn compose_broadcast_snapshot(&self) -> GameSnapshot {
+        self.compose_snapshot_with(!self.catalog_served)
+    }
+
+    // Without the catalog the snapshot carries an empty one; clients keep the copy they have.
+    fn compose_snapshot_with(&self, include_catalog: bool) -> GameSnapshot {
+        let mut lobbies = self.lobbies.clone();
+        for lob in &self.discovered_lobbies {
+            if !lobbies
+                .iter()
+                .any(|existing| existing.id == lob.id && existing.host == lob.host)
+            {
+                lobbies.push(lob.clone());
+            }
+        }
+        // Filter out lobbies where the game is over, or that a fresh lobby has since replaced
+        let game_over = self
+            .game()
+            .map(|g| matches!(g.phase, Phase::Lobby | Phase::GameOver))
+            .unwrap_or(false);
+        if game_over {
+            lobbies.retain(|l| !l.started);
+        }
+        for lobby in lobbies.iter_mut() {
+            lobby.password_hash = None;
+        }
+        GameSnapshot {
+            catalog: if include_catalog { self.catalog.clone() } else { Vec::new() },
+            game: self.game().cloned(),
+            lobbies,
+            chat_log: self.chat_log.clone(),
+            total_events: self.game().map(|g| g.events.len()).unwrap_or(0),
+            projected_yield: self.game().map(|g| g.projected_yield()).unwrap_or_default(),
+            version: PROTOCOL_VERSION,
+            game_ids: {
+                let mut ids: Vec<String> = self.games.keys().cloned().collect();
+                ids.sort();
+                ids
+            },
+        }
+    }
+
+    // What another node may see: private lobbies only show to the opponent already in them.
+    fn compose_remote_snapshot(&self, node: &str) -> GameSnapshot {
+        let mut snapshot = self.compose_snapshot();
+        snapshot
+            .lobbies
+            .retain(|l| !l.private || l.opponent.as_deref() == Some(node));
+        // A peer may see the deck it brought, never anyone else's.
+        for lobby in snapshot.lobbies.iter_mut() {
+            lobby.host_deck.clear();
+            if lobby.opponent.as_deref() != Some(node) {
+                lobby.opponent_deck.clear();
+            }
+        }
+        // Which other games we're in is none of the peer's business.
+        snapshot.game_ids.clear();
+        // A lobby placeholder is only our own table; peers learn about the lobby from `lobbies`.
+        if snapshot.game.as_ref().is_some_and(|g| g.phase == Phase::Lobby) {
+            snapshot.game = None;
+            snapshot.total_events = 0;
+        }
+        snapshot
+    }
+
+    fn game(&self) -> Option<&GameState> {
+        self.games.get(self.current_game.as_ref()?)
+    }
+
+    fn game_mut(&mut self) -> Option<&mut GameState> {
+        self.games.get_mut(self.current_game.as_ref()?)
+    }
+
+    // Files `game` under its id, naming it first if it has none, and puts it on screen. The game
+    // it replaces on screen is dropped if it was only a lobby table or is over.
+    fn install_game(&mut self, mut game: GameState) {
+        if game.id.is_empty() {
+            game.id = format!("{}/game-{}", our().node, self.game_seq);
+            self.game_seq += 1;
+        }
+        if let Some(shown) = self.current_game.take().filter(|id| *id != game.id) {
+            if self
+                .games
+                .get(&shown)
+                .is_some_and(|g| matches!(g.phase, Phase::Lobby | Phase::GameOver))
+            {
+                self.games.remove(&shown);
+            }
+        }
+        self.current_game = Some(game.id.clone());
+        self.games.insert(game.id.clone(), game);
+    }
+
+    // Puts back a game saved with `self.game().cloned()`, or clears the screen if there was none.
+    fn restore_game(&mut self, game: Option<GameState>) {
+        match game {
+            Some(game) => {
+                self.current_game = Some(game.id.clone());
+                self.games.insert(game.id.clone(), game);
+            }
+            None => self.current_game = None,
+        }
+    }
+
+    fn focus_game(&mut self, game_id: String) -> Result<(), GameError> {
+        if !self.games.contains_key(&game_id) {
+            return Err(GameError::GameNotFound(game_id));
+        }
+        if self.current_game.as_ref() != Some(&game_id) {
+            self.current_game = Some(game_id);
+            self.broadcast_snapshot();
+        }
+        Ok(())
+    }
+
+    // The unreachable check is the caller's; this still insists on an undelivered update.
+    fn settle_disconnect(&mut self, resolution: DisconnectResolution) -> Result<(), GameError> {
+        let node = self.opponent_node().ok_or(GameError::NoActiveGame)?;
+        if !self.pending_sync.iter().any(|(n, _, _)| *n == node) {
+            return Err(GameError::OpponentReachable);
+        }
+        let me = our().node;
+        let game_id = self.current_game.clone();
+        let game = self.game_mut().ok_or(GameError::NoActiveGame)?;
+        if game.phase == Phase::GameOver {
+            return Err(GameError::GameOver);
+        }
+        let gone = game
+            .players
+            .iter()
+            .find(|p| p.node_id != me)
+            .map(|p| p.seat.clone())
+            .ok_or(GameError::SeatNotFound)?;
+        game.finish(match resolution {
+            DisconnectResolution::ClaimWin => GameOutcome::Timeout(gone),
+            DisconnectResolution::Abandon => GameOutcome::Abandoned,
+        });
+        // Nothing queued for a finished game is worth delivering.
+        self.pending_sync.retain(|(n, g, _)| *n != node || *g != game_id);
+        Ok(())
+    }
+
+    // The other seat's node in the current game, when we hold one of the seats.
+    fn opponent_node(&self) -> Option<String> {
+        let game = self.game()?;
+        let me = our().node;
+        if !game.players.iter().any(|p| p.node_id == me) {
+            return None;
+        }
+        game.players
+            .iter()
+            .find(|p| p.node_id != me && !p.node_id.is_empty())
+            .map(|p| p.node_id.clone())
+    }
+
+    fn admit_to_lobby(&mut self, payload: JoinLobbyPayload) -> Result<(), GameError> {
+        let lobby = self
+            .lobbies
+            .iter_mut()
+            .find(|l| l.id == payload.lobby_id)
+            .ok_or(GameError::LobbyNotFound)?;
+        if let Some(expected) = lobby.password_hash.as_ref() {
+            let attempt = payload.password.as_deref().unwrap_or_default();
+            if lobby_password_hash(&lobby.id, attempt) != *expected {
+                return Err(GameError::WrongLobbyPassword);
+            }
+        }
+        // A retried join from the seated opponent just refreshes their details.
+        if lobby.opponent.as_ref().is_some_and(|opponent| *opponent != payload.node_id) {
+            return Err(GameError::LobbyFull);
+        }
+        lobby.opponent = Some(payload.node_id);
+        lobby.opponent_deck = payload.deck;
+        lobby.opponent_cosmetics = payload.cosmetics;
+        lobby.opponent_full_disclosure = payload.full_disclosure;
+        Ok(())
+    }
+
+    fn push_ws_message(&self, target: WsTarget, mut envelope: WsEnvelope<WsServerMessage>) {
+        if let WsServerMessage::Snapshot(snapshot) = envelope.message {
+            envelope.message = WsServerMessage::Snapshot(Box::new(snapshot.for_client(&our().node)));
+        }
+        if let Some(server) = get_server() {
+            if let Ok(bytes) = serde_json::to_vec(&envelope) {
+                match target {
+                    WsTarget::Channel(channel_id) => {
+                        let blob = LazyLoadBlob {
+                            mime: None,
+                            bytes: bytes.clone(),
+                        };
+                        println!(
+                            "WS push to channel {} message={:?}",
+                            channel_id, envelope.message
+                        );
+                        server::send_ws_push(channel_id, WsMessageType::Text, blob)
+                    }
+                    WsTarget::Broadcast => {
+                        println!(
+                            "WS broadcast message={:?} paths={:?}",
+                            envelope.message, self.ws_paths
+                        );
+                        let mut paths = self.ws_paths.clone();
+                        if !paths.iter().any(|p| p == WS_PATH) {
+                            paths.push(WS_PATH.to_string());
+                        }
+                        for path in paths {
+                            let blob = LazyLoadBlob {
+                                mime: None,
+                                bytes: bytes.clone(),
+                            };
+                            let _ = server.ws_push_all_channels(&path, WsMessageType::Text, blob);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn compose_chat(&self, text: String) -> Result<ChatMessage, GameError> {
+        if text.chars().count() > MAX_CHAT_LEN {
+            return Err(GameError::ChatTooLong);
+        }
+        let game = self.game().ok_or(GameError::NoActiveGame)?;
+        Ok(ChatMessage {
+            from: our().node,
+            text,
+            turn: game.turn,
+        })
+    }
+
+    // Logs a chat line and pushes it to every local ws channel.
+    fn record_chat(&mut self, chat: ChatMessage) {
+        self.chat_log.push(chat.clone());
+        if self.chat_log.len() > MAX_CHAT_LOG {
+            let excess = self.chat_log.len() - MAX_CHAT_LOG;
+            self.chat_log.drain(..excess);
+        }
+        // Mid-batch, the chat goes out with the snapshot once the batch commits.
+        if let Some(requested) = self.deferred_broadcast.as_mut() {
+            *requested = true;
+            return;
+        }
+        let envelope = WsEnvelope {
+            id: None,
+            game_id: None,
+            message: WsServerMessage::Chat(chat),
+        };
+        self.push_ws_message(WsTarget::Broadcast, envelope);
+    }
+
+    // Every path that can end a game broadcasts afterwards, so this runs from broadcast_snapshot.
+    // A game is only recorded once, keyed by its seed and players.
+    fn record_game_over(&mut self) {
+        let Some(game) = self
+            .game()
+            .filter(|g| g.phase == Phase::GameOver && g.outcome != Some(GameOutcome::Abandoned))
+        else {
+            return;
+        };
+        let players: Vec<String> = game.players.iter().map(|p| p.node_id.clone()).collect();
+        if self
+            .match_history
+            .last()
+            .is_some_and(|r| r.game_seed == game.game_seed && r.players == players)
+        {
+            return;
+        }
+        let ended_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let winner = game.winner.clone();
+        self.match_history.push(MatchRecord {
+            game_seed: game.game_seed,
+            players: players.clone(),
+            winner: winner.clone(),
+            final_scores: game.players.iter().map(|p| p.score).collect(),
+            turns: game.turn,
+            ended_at,
+            outcome: game.outcome.clone(),
+        });
+        if self.match_history.len() > MAX_MATCH_HISTORY {
+            let excess = self.match_history.len() - MAX_MATCH_HISTORY;
+            self.match_history.drain(..excess);
+        }
+        self.advance_series(&players, winner.as_ref());
+    }
+
+    // Tallies a finished game into its lobby's series. Until someone clinches, the next game starts
+    // straight away as a rematch (loser first) and is pushed to the opponent without waiting.
+    fn advance_series(&mut self, players: &[String], winner: Option<&Seat>) {
+        let Some(lobby) = self.lobbies.iter_mut().find(|l| {
+            l.started && Some(&l.host) == players.first() && l.opponent.as_ref() == players.get(1)
+        }) else {
+            return;
+        };
+        let Some(series) = lobby.series.as_mut() else {
+            return;
+        };
+        series.record(winner);
+        if series.clinched_by().is_some() {
+            return;
+        }
+        let lobby_id = lobby.id.clone();
+        let opponent = lobby.opponent.clone();
+        match self.prepare_rematch(&lobby_id) {
+            Ok(game) => {
+                if let Some(node) = opponent.filter(|n| *n != our().node) {
+                    self.push_wire_message(&node, WireMessage::SyncGame(game));
                 }
             }
+            Err(e) => println!("series rematch in {} failed: {}", lobby_id, e),
+        }
+    }
+
+    fn broadcast_snapshot(&mut self) {
+        if let Some(requested) = self.deferred_broadcast.as_mut() {
+            *requested = true;
+            return;
+        }
+        self.record_game_over();
+        // The UI isn't showing this game; it gets a snapshot when it switches over.
+        if self.background {
+            return;
+        }
+        let snapshot = self.compose_broadcast_snapshot();
+        let messages = self.progress_messages(&snapshot);
+        if messages.is_empty() {
+            let envelope = WsEnvelope {
+                id: None,
+                game_id: None,
+                message: WsServerMessage::Snapshot(snapshot.into()),
+            };
+            self.push_ws_message(WsTarget::Broadcast, envelope);
+        }
+        for message in messages {
+            self.push_ws_message(WsTarget::Broadcast, WsEnvelope { id: None, game_id: None, message });
+        }
+        self.push_to_spectators();
+    }
+
+    // Deltas since the previous broadcast, sent in place of `snapshot`. Empty when they wouldn't
+    // bring a client all the way there: a new or replaced game, a change outside the game, or
+    // events logged without a turn resolving.
+    fn progress_messages(&mut self, snapshot: &GameSnapshot) -> Vec<WsServerMessage> {
+        let frame = GameSnapshot {
+            catalog: Vec::new(),
+            game: None,
+            total_events: 0,
+            projected_yield: Vec::new(),
+            ..snapshot.clone()
+        };
+        let same_frame = self.broadcast_frame.replace(frame.clone()) == Some(frame);
+        // Looked up field by field so the progress fields below can still be updated.
+        let Some(game) = self.current_game.as_ref().and_then(|id| self.games.get(id)) else {
+            self.broadcast_progress = None;
+            self.broadcast_view = None;
+            return vec![];
+        };
+        let previous = self
+            .broadcast_progress
+            .replace((game.id.clone(), game.turn, game.phase.clone(), game.events.len()));
+        let view = game.client_view(&our().node);
+        let mut trimmed = view.clone();
+        trimmed.trim_history(SNAPSHOT_EVENT_WINDOW);
+        let previous_view = self.broadcast_view.replace(trimmed.clone());
+        let Some((_, turn, phase, seen)) = previous.filter(|(id, ..)| *id == game.id) else {
+            return vec![];
+        };
+        if !same_frame || turn > game.turn || seen > game.events.len() {
+            return vec![];
+        }
+        if game.turn == turn && game.events.len() > seen {
+            return vec![];
+        }
+        let mut messages = vec![];
+        if game.turn > turn {
+            messages.push(WsServerMessage::TurnResolved {
+                turn: game.turn,
+                from: seen,
+                events: view.events[seen..].to_vec(),
+            });
+        }
+        if game.phase != phase {
+            messages.push(WsServerMessage::PhaseChanged(game.phase.clone()));
+        }
+        if let Some(diff) = previous_view.map(|before| trimmed.diff(&before)).filter(|diff| !diff.is_empty()) {
+            messages.push(WsServerMessage::Diff(diff));
+        }
+        messages
+    }
+
+    fn push_to_spectators(&self) {
+        let Some(game) = self.game() else {
+            return;
+        };
+        // Spectators get the seatless view. Fire-and-forget: a slow or offline spectator must not
+        // stall the players.
+        let view = game.client_view("");
+        for node in self.spectators.iter() {
+            self.push_wire_message(node, WireMessage::SyncGame(view.clone()));
+        }
+    }
+
+    // Sends without waiting for a reply; failures are only logged.
+    fn push_wire_message(&self, node: &str, message: WireMessage) {
+        let Ok(body) = wire_request_body(&message, self.current_game.clone()) else {
+            return;
+        };
+        let address = Address {
+            node: node.to_string(),
+            process: process_id(),
+        };
+        if let Err(e) = Request::to(address).body(body).send() {
+            println!("push to {} failed: {}", node, e);
+        }
+    }
+
+    // Deals the lobby's decks from the seed agreed with the opponent. The first game moves the hosted placeholder out of
+    // Phase::Lobby; rematches start from an empty one. `initiative` overrides who acts first.
+    fn launch_lobby_game(
+        &mut self,
+        lobby_index: usize,
+        initiative: Option<Seat>,
+    ) -> Result<GameState, GameError> {
+        let opponent_id = self.lobbies[lobby_index]
+            .opponent
+            .clone()
+            .ok_or(GameError::NeedOpponent)?;
+        let lobby_id = self.lobbies[lobby_index].id.clone();
+        // Series games started without a handshake follow on from the game they replace.
+        let previous_seed = self
+            .games
+            .get(&self.lobbies[lobby_index].game_id())
+            .filter(|g| g.phase == Phase::GameOver)
+            .map(|g| g.game_seed);
+        let seed = match (self.agreed_seed(&lobby_id, &opponent_id), previous_seed) {
+            (Some(seed), _) => {
+                self.seed_handshake = None;
+                seed
+            }
+            (None, Some(previous)) => series_seed(previous),
+            (None, None) => self.next_seed(),
+        };
+        let lobby = &self.lobbies[lobby_index];
+        let options = lobby.game_options();
+        let game_id = lobby.game_id();
+        let mut game = self
+            .games
+            .get(&game_id)
+            .filter(|g| g.phase == Phase::Lobby)
+            .cloned()
+            .unwrap_or_else(|| GameState::lobby(opponent_id.clone(), &options));
+        game.id = game_id;
+        game.players[1].node_id = opponent_id;
+        game.start(
+            &self.catalog,
+            &mut self.next_instance,
+            seed,
+            lobby.host_deck.clone(),
+            lobby.opponent_deck.clone(),
+            options,
+        )?;
+        if let Some(seat) = initiative {
+            game.initiative = seat;
+        }
+        self.next_instance = game.next_instance;
+        self.lobbies[lobby_index].started = true;
+        self.install_game(game.clone());
+        Ok(game)
+    }
+
+    // Pushes a freshly launched game to the opponent and waits for the ack. If it never arrives,
+    // the lobby and any previous game are restored so the host isn't left in a one-sided game.
+    async fn sync_lobby_game(
+        &mut self,
+        lobby_index: usize,
+        previous: (Lobby, Option<GameState>),
+        game: GameState,
+    ) -> Result<(), GameError> {
+        let opponent_id = self.lobbies[lobby_index]
+            .opponent
+            .clone()
+            .ok_or(GameError::NeedOpponent)?;
+        let reply = self
+            .send_wire_message(&opponent_id, WireMessage::SyncGame(game))
+            .await;
+        self.settle_game_sync(lobby_index, previous, reply)
+    }
+
+    fn settle_game_sync(
+        &mut self,
+        lobby_index: usize,
+        previous: (Lobby, Option<GameState>),
+        reply: Result<WireReply, GameError>,
+    ) -> Result<(), GameError> {
+        let err = match reply {
+            Ok(WireReply::Snapshot(_)) => return Ok(()),
+            Ok(_) => GameError::UnexpectedReply,
+            Err(err) => err,
+        };
+        let (lobby, game) = previous;
+        self.games.remove(&lobby.game_id());
+        self.lobbies[lobby_index] = lobby;
+        if let Some(game) = game.as_ref() {
+            self.next_instance = game.next_instance;
+        }
+        self.restore_game(game);
+        self.broadcast_snapshot();
+        Err(GameError::OpponentSyncFailed(err.to_string()))
+    }
+
+    fn next_seed(&mut self) -> u64 {
+        match self.seed_source.as_mut() {
+            Some(seed) => {
+                let next = *seed;
+                *seed = seed.wrapping_add(1);
+                next
+            }
+            None => rand::thread_rng().gen::<u64>(),
+        }
+    }
+
+    // Runs the seed handshake with `peer` as initiator: commit, learn their value, then reveal.
+    async fn agree_seed(&mut self, lobby_id: &str, peer: &str) -> Result<u64, GameError> {
+        let (commit, reveal) = self.open_seed_handshake(lobby_id, peer);
+        let their_value = match self.send_wire_message(peer, WireMessage::SeedCommit(commit)).await? {
+            WireReply::SeedValue { value } => value,
+            _ => return Err(GameError::UnexpectedReply),
+        };
+        match self.send_wire_message(peer, WireMessage::SeedReveal(reveal)).await? {
+            WireReply::Ack => self.finish_seed_handshake(their_value),
+            _ => Err(GameError::UnexpectedReply),
+        }
+    }
+
+    fn open_seed_handshake(&mut self, lobby_id: &str, peer: &str) -> (WireSeedCommit, WireSeedReveal) {
+        let value = self.next_seed();
+        let salt = format!("{:016x}", rand::thread_rng().gen::<u64>());
+        self.seed_handshake = Some(SeedHandshake {
+            lobby_id: lobby_id.to_string(),
+            peer: peer.to_string(),
+            value,
+            commitment: None,
+            agreed: None,
+        });
+        let commit = WireSeedCommit {
+            lobby_id: lobby_id.to_string(),
+            commitment: contribution_commitment(value, &salt),
+        };
+        let reveal = WireSeedReveal {
+            lobby_id: lobby_id.to_string(),
+            value,
+            salt,
+        };
+        (commit, reveal)
+    }
+
+    fn finish_seed_handshake(&mut self, their_value: u64) -> Result<u64, GameError> {
+        let handshake = self.seed_handshake.as_mut().ok_or(GameError::NoAgreedSeed)?;
+        let seed = shared_seed(handshake.value, their_value);
+        handshake.agreed = Some(seed);
+        Ok(seed)
+    }
+
+    // A new commit from the node across the table replaces whatever handshake we had; nothing is
+    // agreed until the matching reveal arrives from the same node.
+    fn accept_seed_commit(&mut self, peer: &str, payload: WireSeedCommit) -> Result<u64, GameError> {
+        let me = our().node;
+        let seated = self.lobbies.iter().chain(self.discovered_lobbies.iter()).any(|l| {
+            l.id == payload.lobby_id
+                && ((l.host == me && l.opponent.as_deref() == Some(peer))
+                    || (l.host == peer && l.opponent.as_deref() == Some(me.as_str())))
+        });
+        if !seated {
+            return Err(GameError::NotSeated(peer.to_string()));
+        }
+        let value = self.next_seed();
+        self.seed_handshake = Some(SeedHandshake {
+            lobby_id: payload.lobby_id,
+            peer: peer.to_string(),
+            value,
+            commitment: Some(payload.commitment),
+            agreed: None,
+        });
+        Ok(value)
+    }
+
+    fn accept_seed_reveal(&mut self, peer: &str, payload: WireSeedReveal) -> Result<u64, GameError> {
+        let handshake = self
+            .seed_handshake
+            .as_mut()
+            .filter(|h| h.peer == peer && h.lobby_id == payload.lobby_id)
+            .ok_or(GameError::NoAgreedSeed)?;
+        if handshake.commitment.as_deref()
+            != Some(contribution_commitment(payload.value, &payload.salt).as_str())
+        {
+            return Err(GameError::SeedCommitmentMismatch);
+        }
+        let seed = shared_seed(payload.value, handshake.value);
+        handshake.agreed = Some(seed);
+        Ok(seed)
+    }
+
+    fn agreed_seed(&self, lobby_id: &str, peer: &str) -> Option<u64> {
+        self.seed_handshake
+            .as_ref()
+            .filter(|h| h.lobby_id == lobby_id && h.peer == peer)
+            .and_then(|h| h.agreed)
+    }
+
+    // A game from `peer` that deals us in afresh, one we don't hold or a re-deal of one we do, must
+    // use the seed we agreed with them, or the series seed of the game it replaces. Updates to a
+    // game already dealt keep its seed and pass.
+    fn check_agreed_seed(&mut self, peer: &str, game: &GameState) -> Result<(), GameError> {
+        if let Some(held) = self.games.get(&game.id) {
+            if game.game_seed == held.game_seed || game.game_seed == series_seed(held.game_seed) {
+                return Ok(());
+            }
+        }
+        let seed = self
+            .seed_handshake
+            .as_ref()
+            .filter(|h| h.peer == peer)
+            .and_then(|h| h.agreed)
+            .ok_or(GameError::NoAgreedSeed)?;
+        self.seed_handshake = None;
+        if game.game_seed != seed {
+            return Err(GameError::SeedMismatch);
+        }
+        Ok(())
+    }
+
+    // The loser of the finished game takes initiative in the rematch.
+    fn prepare_rematch(&mut self, lobby_id: &str) -> Result<GameState, GameError> {
+        let lobby_index = self
+            .lobbies
+            .iter()
+            .position(|l| l.id == lobby_id)
+            .ok_or(GameError::LobbyNotFound)?;
+        let finished = self.game().ok_or(GameError::NoActiveGame)?;
+        if finished.phase != Phase::GameOver {
+            return Err(GameError::GameNotOver);
+        }
+        let initiative = finished.winner.as_ref().map(|w| w.other());
+        let game = self.launch_lobby_game(lobby_index, initiative)?;
+        self.lobbies[lobby_index].rematch_count += 1;
+        Ok(game)
+    }
+
+    fn apply_cosmetics(&mut self, cosmetics: PlayerCosmetics) {
+        let node = our().node;
+        for lobby in self.lobbies.iter_mut() {
+            if lobby.host == node {
+                lobby.host_cosmetics = Some(cosmetics.clone());
+            } else if lobby.opponent.as_ref() == Some(&node) {
+                lobby.opponent_cosmetics = Some(cosmetics.clone());
+            }
+        }
+        if let Some(game) = self.game_mut() {
+            if let Some(player) = game.players.iter_mut().find(|p| p.node_id == node) {
+                player.cosmetics = Some(cosmetics.clone());
+            }
+        }
+        self.cosmetics = Some(cosmetics);
+    }
+
+    // A peer may only act for the seat it holds.
+    fn ensure_source_is(&self, seat: &Seat) -> Result<(), GameError> {
+        let node = source().node;
+        let game = self.game().ok_or(GameError::NoActiveGame)?;
+        if game.player_node(seat).as_deref() == Some(node.as_str()) {
+            Ok(())
+        } else {
+            Err(GameError::NotSeated(node))
+        }
+    }
+
+    fn ensure_seated_source(&self) -> Result<(), GameError> {
+        let node = source().node;
+        let game = self.game().ok_or(GameError::NoActiveGame)?;
+        if game.players.iter().any(|p| p.node_id == node) {
+            Ok(())
+        } else {
+            Err(GameError::NotSeated(node))
+        }
+    }
+
+    async fn process_ws_message(
+        &mut self,
+        msg: WsClientMessage,
+    ) -> Result<WsServerMessage, GameError> {
+        println!("processing ws message {:?}", msg);
+        match msg {
+            WsClientMessage::GetSnapshot => {
+                self.catalog_served = true;
+                Ok(WsServerMessage::Snapshot(self.compose_snapshot().into()))
+            }
+            WsClientMessage::GetSnapshotWithoutCatalog => {
+                Ok(WsServerMessage::Snapshot(self.compose_snapshot_with(false).into()))
+            }
+            WsClientMessage::NewGame { opponent } => {
+                let snapshot = self.new_game(opponent).await?;
+                Ok(WsServerMessage::Snapshot(snapshot.into()))
+            }
+            WsClientMessage::HostLobby(config) => {
+                let snapshot = self.host_lobby(config).await?;
+                Ok(WsServerMessage::Snapshot(snapshot.into()))
+            }
+            WsClientMessage::JoinLobby {
+                lobby_id,
+                deck,
+                full_disclosure,
+            } => {
+                let snapshot = self.join_lobby((lobby_id, deck, full_disclosure)).await?;
+                Ok(WsServerMessage::Snapshot(snapshot.into()))
+            }
+            WsClientMessage::StartLobbyGame { lobby_id } => {
+                let snapshot = self.start_lobby_game(lobby_id).await?;
+                Ok(WsServerMessage::Snapshot(snapshot.into()))
+            }
+            WsClientMessage::FetchRemoteLobbies { host_node } => {
+                let snapshot = self.fetch_remote_lobbies(host_node).await?;
+                Ok(WsServerMessage::Snapshot(snapshot.into()))
+            }
+            WsClientMessage::JoinRemoteLobby {
+                host_node,
+                lobby_id,
+                deck,
+                full_disclosure,
+                password,
+            } => {
+                let snapshot = self
+                    .join_remote_lobby((host_node, lobby_id, deck, full_disclosure, password))
+                    .await?;
+                Ok(WsServerMessage::Snapshot(snapshot.into()))
+            }
+            WsClientMessage::SyncRemoteGame { host_node } => {
+                let snapshot = self.sync_remote_game(host_node).await?;
+                Ok(WsServerMessage::Snapshot(snapshot.into()))
+            }
+            WsClientMessage::SpectateRemote { host_node } => {
+                let snapshot = self.spectate_remote(host_node).await?;
+                Ok(WsServerMessage::Snapshot(snapshot.into()))
+            }
+            WsClientMessage::Rematch { lobby_id } => {
+                let snapshot = self.rematch(lobby_id).await?;
+                Ok(WsServerMessage::Snapshot(snapshot.into()))
+            }
+            WsClientMessage::CommitTurn {
+                seat,
+                plan,
+                salt,
+                turn,
+            } => {
+                let snapshot = commit_turn_with_plan(self, seat, plan, salt, turn).await?;
+                Ok(WsServerMessage::Snapshot(snapshot.into()))
+            }
+            WsClientMessage::RetractCommit { seat } => {
+                let snapshot = self.retract_commit(seat).await?;
+                Ok(WsServerMessage::Snapshot(snapshot.into()))
+            }
+            WsClientMessage::RevealTurn {
+                seat,
+                plan,
+                salt,
+                turn,
+            } => {
+                let snapshot = self.reveal_turn((seat, plan, salt, turn)).await?;
+                Ok(WsServerMessage::Snapshot(snapshot.into()))
+            }
+            WsClientMessage::Reset => {
+                self.reset().await?;
+                Ok(WsServerMessage::Snapshot(self.compose_snapshot().into()))
+            }
+            WsClientMessage::PlayLocalTurn {
+                host_plan,
+                opponent_plan,
+            } => {
+                let opponent = opponent_plan.unwrap_or_default();
+                let snapshot = self.play_local_turn((host_plan, opponent)).await?;
+                Ok(WsServerMessage::Snapshot(snapshot.into()))
+            }
+            WsClientMessage::Unknown => Ok(WsServerMessage::UnsupportedMessage),
+            WsClientMessage::ChooseDraw { keep } => {
+                let snapshot = self.choose_draw(keep).await?;
+                Ok(WsServerMessage::Snapshot(snapshot.into()))
+            }
+            WsClientMessage::DiscardToHandSize { keep } => {
+                let snapshot = self.discard_to_hand_size(keep).await?;
+                Ok(WsServerMessage::Snapshot(snapshot.into()))
+            }
+            WsClientMessage::PingOpponent => {
+                let latency = self.ping_opponent().await?;
+                Ok(WsServerMessage::Latency(latency))
+            }
+            WsClientMessage::ResolveDisconnect { resolution } => {
+                let snapshot = self.resolve_disconnect(resolution).await?;
+                Ok(WsServerMessage::Snapshot(snapshot.into()))
+            }
+            WsClientMessage::SendChat { text } => {
+                let chat = self.compose_chat(text)?;
+                let opponent_node = self.game().and_then(|g| {
+                    g.players
+                        .iter()
+                        .find(|p| p.node_id != chat.from)
+                        .map(|p| p.node_id.clone())
+                });
+                self.record_chat(chat.clone());
+                if let Some(node) = opponent_node {
+                    let _ = self.send_wire_message(&node, WireMessage::Chat(chat)).await;
+                }
+                Ok(WsServerMessage::Ack)
+            }
+            WsClientMessage::CallBased { seat } => {
+                let seat_clone = seat.clone();
+                let opponent_node = self.game().and_then(|g| g.player_node(&seat.other()));
+                let reply = self
+                    .handle_wire_message(WireMessage::CallBased(StakeNotice { seat: seat.clone() }))
+                    .await?;
+                if let Some(node) = opponent_node {
+                    let _ = self
+                        .send_wire_message(
+                            &node,
+                            WireMessage::CallBased(StakeNotice { seat: seat_clone }),
+                        )
+                        .await;
+                }
+                if let WireReply::Snapshot(snapshot) = reply {
+                    Ok(WsServerMessage::Snapshot(snapshot))
+                } else {
+                    Ok(WsServerMessage::Ack)
+                }
+            }
+            WsClientMessage::AcceptBased { seat } => {
+                let seat_clone = seat.clone();
+                let opponent_node = self.game().and_then(|g| g.player_node(&seat.other()));
+                let reply = self
+                    .handle_wire_message(WireMessage::AcceptBased(StakeNotice {
+                        seat: seat.clone(),
+                    }))
+                    .await?;
+                if let Some(node) = opponent_node {
+                    let _ = self
+                        .send_wire_message(
+                            &node,
+                            WireMessage::AcceptBased(StakeNotice { seat: seat_clone }),
+                        )
+                        .await;
+                }
+                if let WireReply::Snapshot(snapshot) = reply {
+                    Ok(WsServerMessage::Snapshot(snapshot))
+                } else {
+                    Ok(WsServerMessage::Ack)
+                }
+            }
+            WsClientMessage::FoldBased { seat } => {
+                let seat_clone = seat.clone();
+                let opponent_node = self.game().and_then(|g| g.player_node(&seat.other()));
+                let reply = self
+                    .handle_wire_message(WireMessage::FoldBased(StakeNotice { seat: seat.clone() }))
+                    .await?;
+                if let Some(node) = opponent_node {
+                    let _ = self
+                        .send_wire_message(
+                            &node,
+                            WireMessage::FoldBased(StakeNotice { seat: seat_clone }),
+                        )
+                        .await;
+                }
+                if let WireReply::Snapshot(snapshot) = reply {
+                    Ok(WsServerMessage::Snapshot(snapshot))
+                } else {
+                    Ok(WsServerMessage::Ack)
+                }
+            }
+            WsClientMessage::CancelBased { seat } => {
+                // The opponent may already have accepted, so they hear about the cancel before we
+                // apply it. A refusal leaves both sides as they were; an unreachable opponent gets it
+                // from pending_sync, in order, once they're back.
+                let game = self.game().ok_or(GameError::NoActiveGame)?;
+                game.clone().cancel_based(seat.clone())?;
+                let opponent_node = game.player_node(&seat.other()).filter(|n| *n != our().node);
+                let message = WireMessage::CancelBased(StakeNotice { seat });
+                let sent = match opponent_node {
+                    Some(node) => self.send_turn_update(&node, message.clone()).await,
+                    None => Ok(()),
+                };
+                if let Some(err) = sent.as_ref().err().filter(|err| !matches!(err, GameError::PeerUnreachable(_))) {
+                    self.pending_sync.retain(|(_, _, queued)| *queued != message);
+                    return Err(err.clone());
+                }
+                let reply = self.handle_wire_message(message).await?;
+                sent?;
+                if let WireReply::Snapshot(snapshot) = reply {
+                    Ok(WsServerMessage::Snapshot(snapshot))
+                } else {
+                    Ok(WsServerMessage::Ack)
+                }
+            }
+        }
+    }
+
+    // Sends a turn update to the other seat. Anything still queued for that node goes first so the
+    // peer sees updates in order; if the node stays unreachable the update waits in pending_sync.
+    async fn send_turn_update(&mut self, node: &str, message: WireMessage) -> Result<(), GameError> {
+        self.pending_sync.push((node.to_string(), self.current_game.clone(), message));
+        self.flush_pending_for(node).await
+    }
+
+    // Each update goes out tagged with the game it was queued for, not the one now on screen.
+    async fn flush_pending_for(&mut self, node: &str) -> Result<(), GameError> {
+        while let Some(idx) = self.pending_sync.iter().position(|(n, _, _)| n == node) {
+            let (_, game_id, message) = self.pending_sync[idx].clone();
+            let result = with_retries(|| self.send_wire_message_for(node, message.clone(), game_id.clone())).await;
+            self.settle_pending(idx, result)?;
+        }
+        Ok(())
+    }
+
+    // Every node with something queued, each once however its entries interleave.
+    fn pending_nodes(&self) -> Vec<String> {
+        let mut nodes: Vec<String> = self.pending_sync.iter().map(|(n, _, _)| n.clone()).collect();
+        nodes.sort();
+        nodes.dedup();
+        nodes
+    }
+
+    // A transport failure keeps the entry queued. The peer answered otherwise, even if only to
+    // refuse, and resending won't change that, so the entry goes.
+    fn settle_pending(&mut self, idx: usize, result: Result<WireReply, GameError>) -> Result<(), GameError> {
+        if let Err(GameError::Transport(err)) = result {
+            return Err(GameError::PeerUnreachable(err));
+        }
+        self.pending_sync.remove(idx);
+        result.map(|_| ())
+    }
+
+    async fn send_wire_message(
+        &self,
+        node: &str,
+        message: WireMessage,
+    ) -> Result<WireReply, GameError> {
+        self.send_wire_message_for(node, message, self.current_game.clone()).await
+    }
+
+    async fn send_wire_message_for(
+        &self,
+        node: &str,
+        message: WireMessage,
+        game_id: Option<String>,
+    ) -> Result<WireReply, GameError> {
+        let address = Address {
+            node: node.to_string(),
+            process: process_id(),
+        };
+        let body = wire_request_body(&message, game_id)?;
+        let request = Request::to(address).expects_response(30).body(body);
+        let response: Result<WireReply, GameError> = send(request)
+            .await
+            .map_err(|e| GameError::Transport(e.to_string()))?;
+        if let Ok(WireReply::Snapshot(snapshot)) = &response {
+            check_version(snapshot.version)?;
+        }
+        response
+    }
+
+    fn validate_state_hash(&self, remote: &StateHash) -> Result<(), GameError> {
+        let game = self.game().ok_or(GameError::NoActiveGame)?;
+        validate_state_hash(game, remote)
+    }
+}
+
+// Up to WIRE_SEND_ATTEMPTS tries, stopping at the first that isn't a transport failure.
+async fn with_retries<F, Fut>(mut attempt: F) -> Result<WireReply, GameError>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<WireReply, GameError>>,
+{
+    let mut last = GameError::Transport("no attempts made".to_string());
+    for _ in 0..WIRE_SEND_ATTEMPTS {
+        match attempt().await {
+            Err(GameError::Transport(err)) => last = GameError::Transport(err),
+            result => return result,
+        }
+    }
+    Err(last)
+}
+
+fn wire_request_body(message: &WireMessage, game_id: Option<String>) -> Result<Vec<u8>, GameError> {
+    let envelope = serde_json::json!({ "HandleWireEnvelope": WireEnvelope::new(message, game_id)? });
+    serde_json::to_vec(&envelope).map_err(|e| GameError::Transport(e.to_string()))
+}
+
+async fn commit_turn_with_plan(
+    app: &mut MemeWarsState,
+    seat: Seat,
+    plan: TurnPlan,
+    salt: String,
+    turn: u32,
+) -> Result<GameSnapshot, GameError> {
+    let game_seed = app.game().ok_or(GameError::NoActiveGame)?.game_seed;
+    let hash = commitment_for(&plan, &salt, turn, game_seed);
+    app.commit_turn((seat, hash, turn)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use catalog::{find_definition, validate_catalog};
+    use game::split_players_mut;
+    use error::ManaParams;
+    use constants::MAX_HAND_SIZE;
+
+    fn make_app() -> MemeWarsState {
+        let mut app = MemeWarsState::default();
+        app.catalog = build_catalog();
+        app.next_instance = 1;
+        app
+    }
+
+    fn joined_lobby() -> Lobby {
+        Lobby {
+            id: "lobby-0".into(),
+            host: "host.os".into(),
+            mode: "casual".into(),
+            stakes: 1,
+            description: String::new(),
+            opponent: Some("opp.os".into()),
+            started: false,
+            host_deck: default_deck(),
+            opponent_deck: default_deck(),
+            feed_size: None,
+            score_to_win: None,
+            last_stand: LastStandRule::Disabled,
+            action_budget: ActionBudgetRule::Unlimited,
+            win_condition: WinCondition::ScoreOnly,
+            rules: GameRules::default(),
+            rematch_count: 0,
+            host_cosmetics: None,
+            opponent_cosmetics: None,
+            full_disclosure: false,
+            opponent_full_disclosure: false,
+            private: false,
+            password_hash: None,
+            series: None,
+        }
+    }
+    fn start_default_game(app: &mut MemeWarsState, seed: u64, options: GameOptions) -> GameState {
+        build_game(
+            &app.catalog,
+            &mut app.next_instance,
+            seed,
+            default_deck(),
+            default_deck(),
+            "opp.os".into(),
+            options,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn commitment_changes_with_salt() {
+        let plan = TurnPlan::default();
+        let a = commitment_for(&plan, "a", 0, 1);
+        let b = commitment_for(&plan, "b", 0, 1);
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn reveal_rejects_wrong_commit() {
+        let mut app = make_app();
+        let mut game =
+            build_game(&app.catalog, &mut app.next_instance, 1, vec!["n01".into()], vec!["n01".into()], "opp.os".into(), GameOptions::default())
+                .unwrap();
+        let plan = TurnPlan::default();
+        let correct_hash = commitment_for(&plan, "good", game.turn, game.game_seed);
+        game.record_commit(Seat::Host, correct_hash).unwrap();
+        let err = game.record_reveal(Seat::Host, plan.clone(), "bad".into());
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn heavy_enters_bottom_when_feed_not_empty() {
+        let mut app = make_app();
+        let mut game =
+            build_game(&app.catalog, &mut app.next_instance, 2, vec!["d10".into()], vec!["n01".into()], "opp.os".into(), GameOptions::default())
+                .unwrap();
+        let def_normal = find_definition("n01").unwrap();
+        let existing =
+            game.new_instance_from_def(def_normal, Seat::Host, Location::Feed(FeedSlot { slot: 0 }));
+        game.feed.push(existing);
+
+        let def_heavy = find_definition("d10").unwrap();
+        let heavy = game.new_instance_from_def(def_heavy, Seat::Host, Location::Kitchen);
+        let heavy_id = heavy.instance_id.clone();
+        {
+            let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
+            host.kitchen.push(heavy);
+        }
+        game.resolve_posts(&[PostAction { card_id: heavy_id }], &[])
+            .unwrap();
+
+        assert_eq!(game.feed.first().unwrap().variant_id, "n01");
+        assert_eq!(game.feed.last().unwrap().variant_id, "d10");
+    }
+
+    #[test]
+    fn gatekeeper_blocks_low_cost_posts() {
+        let mut app = make_app();
+        let mut game =
+            build_game(&app.catalog, &mut app.next_instance, 3, vec!["m04".into()], vec!["n01".into()], "opp.os".into(), GameOptions::default())
+                .unwrap();
+
+        let gate_def = find_definition("m04").unwrap();
+        let gate =
+            game.new_instance_from_def(gate_def, Seat::Host, Location::Feed(FeedSlot { slot: 0 }));
+        game.feed.push(gate);
+        game.reindex_feed();
+
+        let post_def = find_definition("n01").unwrap();
+        let post_card = game.new_instance_from_def(post_def, Seat::Opponent, Location::Kitchen);
+        let post_id = post_card.instance_id.clone();
+        {
+            let (_, opp) = split_players_mut(&mut game.players, &Seat::Opponent);
+            opp.kitchen.push(post_card);
+        }
+
+        game.resolve_posts(&[], &[PostAction { card_id: post_id }])
+            .unwrap();
+        assert_eq!(game.feed[0].variant_id, "m04");
+        assert_eq!(game.feed[1].variant_id, "n01");
+    }
+
+    #[test]
+    fn feed_yield_scales_with_stakes() {
+        let mut app = make_app();
+        let mut game =
+            build_game(&app.catalog, &mut app.next_instance, 4, vec!["n01".into()], vec!["n01".into()], "opp.os".into(), GameOptions::default())
+                .unwrap();
+
+        let card_def = find_definition("n01").unwrap();
+        let card =
+            game.new_instance_from_def(card_def, Seat::Host, Location::Feed(FeedSlot { slot: 0 }));
+        game.feed.push(card);
+        game.stakes = 2;
+        game.apply_feed_yield();
+        let host = game.players.iter().find(|p| p.seat == Seat::Host).unwrap();
+        assert_eq!(host.score, constants::BASE_FEED_YIELD * 2);
+    }
+
+    #[test]
+    fn stakes_call_accept_and_fold() {
+        let mut app = make_app();
+        let mut game =
+            build_game(&app.catalog, &mut app.next_instance, 5, vec!["n01".into()], vec!["n01".into()], "opp.os".into(), GameOptions::default())
+                .unwrap();
+
+        game.call_based(Seat::Host).unwrap();
+        assert_eq!(game.phase, Phase::StakePending);
+        assert!(game.pending_stakes.is_some());
+
+        game.accept_based(Seat::Opponent).unwrap();
+        assert_eq!(game.stakes, 2);
+        assert!(game.pending_stakes.is_none());
+        assert_eq!(game.phase, Phase::Commit);
+
+        game.call_based(Seat::Opponent).unwrap();
+        game.fold_based(Seat::Host).unwrap();
+        assert_eq!(game.winner, Some(Seat::Opponent));
+        assert_eq!(game.outcome, Some(GameOutcome::Folded(Seat::Host)));
+        assert_eq!(game.phase, Phase::GameOver);
+    }
+
+    #[test]
+    fn initiative_controls_exploit_order() {
+        let mut app = make_app();
+        let mut game = build_game(&app.catalog, &mut app.next_instance, 6, vec![], vec![], "opp.os".into(), GameOptions::default()).unwrap();
+        for player in game.players.iter_mut() {
+            player.hand.clear();
+            player.kitchen.clear();
+            player.mana = 10;
+            player.max_mana = 10;
+        }
+
+        let target_def = find_definition("n04").unwrap();
+        let mut target = game.new_instance_from_def(target_def, Seat::Host, Location::Kitchen);
+        target.cook_rate = 0;
+        let target_id = target.instance_id.clone();
+        {
+            let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
+            host.kitchen.push(target);
+        }
+
+        let protect_def = find_definition("c06").unwrap();
+        let protect = game.new_instance_from_def(protect_def, Seat::Host, Location::Hand);
+        let protect_id = protect.instance_id.clone();
+        {
+            let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
+            host.hand.push(protect);
+        }
+
+        let damage_def = find_definition("t02").unwrap();
+        let damage = game.new_instance_from_def(damage_def, Seat::Opponent, Location::Hand);
+        let damage_id = damage.instance_id.clone();
+        {
+            let (_, opp) = split_players_mut(&mut game.players, &Seat::Opponent);
+            opp.hand.push(damage);
+        }
+
+        game.initiative = Seat::Opponent;
+        let host_plan = TurnPlan {
+            plays_to_kitchen: vec![],
+            posts: vec![],
+            exploits: vec![ExploitAction {
+                card_id: protect_id,
+                target: Some(Target::Card(target_id.clone())),
+            }],
+            based: false,
+        };
+        let opp_plan = TurnPlan {
+            plays_to_kitchen: vec![],
+            posts: vec![],
+            exploits: vec![ExploitAction {
+                card_id: damage_id,
+                target: Some(Target::Card(target_id.clone())),
+            }],
+            based: false,
+        };
+        game.resolve_turn(host_plan, opp_plan).unwrap();
+
+        let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
+        let survivor = host
+            .kitchen
+            .iter()
+            .find(|c| c.instance_id == target_id)
+            .unwrap();
+        assert_eq!(survivor.current_virality, 5);
+    }
+
+    #[test]
+    fn cook_and_decay_apply() {
+        let mut app = make_app();
+        let mut game =
+            build_game(&app.catalog, &mut app.next_instance, 7, vec!["c01".into(), "d05".into()], vec![], "opp.os".into(), GameOptions::default())
+                .unwrap();
+        for player in game.players.iter_mut() {
+            player.kitchen.clear();
+            player.hand.clear();
+        }
+        let fast_cook_def = find_definition("c01").unwrap();
+        let mut fast_cook =
+            game.new_instance_from_def(fast_cook_def, Seat::Host, Location::Kitchen);
+        let fast_id = fast_cook.instance_id.clone();
+        fast_cook.current_virality = 2;
+        let volatile_def = find_definition("d05").unwrap();
+        let mut volatile = game.new_instance_from_def(volatile_def, Seat::Host, Location::Kitchen);
+        volatile.current_virality = 12;
+        {
+            let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
+            host.kitchen.push(fast_cook);
+            host.kitchen.push(volatile);
+        }
+
+        game.apply_cook_and_decay();
+
+        let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
+        let cook = host
+            .kitchen
+            .iter()
+            .find(|c| c.instance_id == fast_id)
+            .unwrap();
+        assert_eq!(cook.current_virality, 5);
+        game.cleanup_board();
+        let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
+        assert_eq!(host.kitchen.len(), 1);
+    }
+
+    #[test]
+    fn pinned_and_anchor_block_movement() {
+        let mut app = make_app();
+        let mut game =
+            build_game(&app.catalog, &mut app.next_instance, 8, vec!["m07".into(), "n01".into()], vec![], "opp.os".into(), GameOptions::default())
+                .unwrap();
+        for player in game.players.iter_mut() {
+            player.feed_locked = false;
+        }
+        let anchor_def = find_definition("m07").unwrap();
+        let anchor = game.new_instance_from_def(
+            anchor_def,
+            Seat::Host,
+            Location::Feed(FeedSlot { slot: 0 }),
+        );
+        let other_def = find_definition("n01").unwrap();
+        let other =
+            game.new_instance_from_def(other_def, Seat::Host, Location::Feed(FeedSlot { slot: 1 }));
+        game.feed = vec![anchor, other];
+        game.reindex_feed();
+
+        let pinned_id = game.feed[1].instance_id.clone();
+        let (_, opp) = split_players_mut(&mut game.players, &Seat::Opponent);
+        opp.pinned_cards.push(pinned_id);
+        game.shift_feed_up(1).unwrap();
+        assert_eq!(game.feed[0].variant_id, "m07");
+        assert_eq!(game.feed[1].variant_id, "n01");
+
+        let (_, opp) = split_players_mut(&mut game.players, &Seat::Opponent);
+        opp.pinned_cards.clear();
+        game.shift_feed_up(1).unwrap();
+        assert_eq!(game.feed[0].variant_id, "m07");
+    }
+
+    #[test]
+    fn can_play_to_kitchen_and_post_existing_in_same_turn() {
+        let mut app = make_app();
+        let mut game =
+            build_game(&app.catalog, &mut app.next_instance, 42, vec![], vec![], "opp.os".into(), GameOptions::default()).unwrap();
+
+        for player in game.players.iter_mut() {
+            player.hand.clear();
+            player.kitchen.clear();
+            player.mana = 10;
+            player.max_mana = 10;
+        }
+
+        let hand_def = find_definition("n02").unwrap();
+        let kitchen_def = find_definition("n01").unwrap();
+        let to_kitchen = game.new_instance_from_def(hand_def, Seat::Host, Location::Hand);
+        let in_kitchen = game.new_instance_from_def(kitchen_def, Seat::Host, Location::Kitchen);
+        let hand_id = to_kitchen.instance_id.clone();
+        let kitchen_id = in_kitchen.instance_id.clone();
+
+        {
+            let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
+            host.hand.push(to_kitchen);
+            host.kitchen.push(in_kitchen);
+        }
+
+        let host_plan = TurnPlan {
+            plays_to_kitchen: vec![hand_id.clone()],
+            posts: vec![PostAction {
+                card_id: kitchen_id.clone(),
+            }],
+            exploits: vec![],
+            based: false,
+        };
+        let opponent_plan = TurnPlan::default();
+
+        game.resolve_turn(host_plan, opponent_plan).unwrap();
+
+        let hand_card_in_kitchen = {
+            let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
+            host.kitchen.iter().any(|c| c.instance_id == hand_id)
+        };
+        let feed_contains_kitchen_card = game
+            .feed
+            .iter()
+            .any(|c| c.instance_id == kitchen_id && c.owner == Seat::Host);
+
+        assert!(hand_card_in_kitchen, "newly played meme should remain in kitchen");
+        assert!(feed_contains_kitchen_card, "existing kitchen meme should post to feed");
+    }
+
+    #[test]
+    fn shuffle_feed_is_deterministic_per_seed_and_turn() {
+        let mut app = make_app();
+        let mut game =
+            build_game(&app.catalog, &mut app.next_instance, 9, vec!["n01".into(), "n02".into()], vec![], "opp.os".into(), GameOptions::default())
+                .unwrap();
+        for player in game.players.iter_mut() {
+            player.feed_locked = false;
+        }
+        let first = game.new_instance_from_def(
+            find_definition("n01").unwrap(),
+            Seat::Host,
+            Location::Feed(FeedSlot { slot: 0 }),
+        );
+        let second = game.new_instance_from_def(
+            find_definition("n02").unwrap(),
+            Seat::Host,
+            Location::Feed(FeedSlot { slot: 1 }),
+        );
+        game.feed = vec![first.clone(), second.clone()];
+        game.reindex_feed();
+
+        game.apply_exploit_effect(ExploitEffect::ShuffleFeed, &Seat::Host, None)
+            .unwrap();
+        let order1: Vec<String> = game.feed.iter().map(|c| c.variant_id.clone()).collect();
+
+        let mut game2 =
+            build_game(&app.catalog, &mut app.next_instance, 9, vec!["n01".into(), "n02".into()], vec![], "opp.os".into(), GameOptions::default())
+                .unwrap();
+        game2.feed = vec![first, second];
+        game2.reindex_feed();
+        game2
+            .apply_exploit_effect(ExploitEffect::ShuffleFeed, &Seat::Host, None)
+            .unwrap();
+        let order2: Vec<String> = game2.feed.iter().map(|c| c.variant_id.clone()).collect();
+        assert_eq!(order1, order2);
+    }
+
+    #[test]
+    fn execute_ignores_shield_and_protect() {
+        let mut app = make_app();
+        let mut game =
+            build_game(&app.catalog, &mut app.next_instance, 10, vec!["c05".into()], vec![], "opp.os".into(), GameOptions::default())
+                .unwrap();
+        for player in game.players.iter_mut() {
+            player.hand.clear();
+            player.kitchen.clear();
+        }
+        let mut shielded =
+            game.new_instance_from_def(find_definition("c05").unwrap(), Seat::Host, Location::Kitchen);
+        shielded.protected_until_end = true;
+        let shielded_id = shielded.instance_id.clone();
+        {
+            let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
+            host.kitchen.push(shielded);
+        }
+        let exploit = ExploitAction {
+            card_id: "exec".into(),
+            target: Some(Target::Card(shielded_id.clone())),
+        };
+        game.apply_exploit_effect(
+            ExploitEffect::Execute,
+            &Seat::Opponent,
+            exploit.target.clone(),
+        )
+        .unwrap();
+        game.cleanup_board();
+        let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
+        assert!(host.kitchen.iter().all(|c| c.instance_id != shielded_id));
+        assert!(host.abyss.iter().any(|c| c.instance_id == shielded_id));
+    }
+
+    #[test]
+    fn lobby_options_override_feed_size_and_score() {
+        let mut app = make_app();
+        let mut game = start_default_game(
+            &mut app,
+            11,
+            GameOptions {
+                feed_size: Some(1),
+                score_to_win: Some(15),
+                ..GameOptions::default()
+            },
+        );
+        assert_eq!(game.feed_size, 1);
+        assert_eq!(game.score_to_win, 15);
+
+        game.turn = 1;
+        let def = find_definition("n01").unwrap();
+        let mut ids = vec![];
+        for _ in 0..2 {
+            let mut card = game.new_instance_from_def(def, Seat::Host, Location::Kitchen);
+            card.played_turn = 0;
+            ids.push(PostAction {
+                card_id: card.instance_id.clone(),
+            });
+            let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
+            host.kitchen.push(card);
+        }
+        game.resolve_posts(&ids, &[]).unwrap();
+        assert_eq!(game.feed.len(), 1);
+
+        let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
+        host.score = 15;
+        assert_eq!(game.check_win_condition(), Some(Seat::Host));
+
+        let defaults = start_default_game(&mut app, 12, GameOptions::default());
+        assert_eq!(defaults.feed_size, constants::FEED_SIZE);
+        assert_eq!(defaults.score_to_win, constants::SCORE_TO_WIN);
+    }
+
+    #[test]
+    fn retract_commit_only_before_opponent_commits() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 13, GameOptions::default());
+        let plan = TurnPlan::default();
+        game.record_commit(Seat::Host, commitment_for(&plan, "a", game.turn, game.game_seed)).unwrap();
+        game.retract_commit(Seat::Host).unwrap();
+        assert!(game.players[0].commit.is_none());
+        assert!(game.retract_commit(Seat::Host).is_err());
+
+        game.record_commit(Seat::Host, commitment_for(&plan, "a", game.turn, game.game_seed)).unwrap();
+        game.record_commit(Seat::Opponent, commitment_for(&plan, "b", game.turn, game.game_seed)).unwrap();
+        assert!(game.retract_commit(Seat::Host).is_err());
+        assert!(game.players[0].commit.is_some());
+    }
+
+    #[test]
+    fn reveal_rejects_oversized_plan() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 14, GameOptions::default());
+        let plan = TurnPlan {
+            posts: (0..constants::MAX_PLAN_ACTIONS + 1)
+                .map(|i| PostAction {
+                    card_id: format!("card-{}", i),
+                })
+                .collect(),
+            ..TurnPlan::default()
+        };
+        game.record_commit(Seat::Host, commitment_for(&plan, "salt", game.turn, game.game_seed)).unwrap();
+        let err = game.record_reveal(Seat::Host, plan, "salt".into()).unwrap_err();
+        assert!(matches!(err, GameError::PlanTooLarge(ref params) if params.section == "posts"));
+        assert!(game.players[0].commit.as_ref().unwrap().revealed.is_none());
+    }
+
+    #[test]
+    fn resolution_order_matches_actual_resolution() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 15, GameOptions::default());
+        game.turn = 1;
+        game.initiative = Seat::Opponent;
+        for player in game.players.iter_mut() {
+            player.hand.clear();
+            player.kitchen.clear();
+            player.mana = 5;
+        }
+        let mut post_ids = vec![];
+        for (seat, virality) in [(Seat::Host, 5), (Seat::Opponent, 5)] {
+            let mut card =
+                game.new_instance_from_def(find_definition("n01").unwrap(), seat.clone(), Location::Kitchen);
+            card.played_turn = 0;
+            card.current_virality = virality;
+            post_ids.push(card.instance_id.clone());
+            let (player, _) = split_players_mut(&mut game.players, &seat);
+            player.kitchen.push(card);
+        }
+        let mut exploit_ids = vec![];
+        for seat in [Seat::Host, Seat::Opponent] {
+            let exploit =
+                game.new_instance_from_def(find_definition("d08").unwrap(), seat.clone(), Location::Hand);
+            exploit_ids.push(exploit.instance_id.clone());
+            let (player, _) = split_players_mut(&mut game.players, &seat);
+            player.hand.push(exploit);
+        }
+        let host_plan = TurnPlan {
+            posts: vec![PostAction {
+                card_id: post_ids[0].clone(),
+            }],
+            exploits: vec![ExploitAction {
+                card_id: exploit_ids[0].clone(),
+                target: None,
+            }],
+            ..TurnPlan::default()
+        };
+        let opp_plan = TurnPlan {
+            posts: vec![PostAction {
+                card_id: post_ids[1].clone(),
+            }],
+            exploits: vec![ExploitAction {
+                card_id: exploit_ids[1].clone(),
+                target: None,
+            }],
+            ..TurnPlan::default()
+        };
+
+        let order = game.resolution_order(&host_plan, &opp_plan);
+        let ids: Vec<&str> = order.iter().map(|e| e.card_id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                exploit_ids[1].as_str(),
+                exploit_ids[0].as_str(),
+                post_ids[1].as_str(),
+                post_ids[0].as_str(),
+            ]
+        );
+        assert_eq!(order[2].reason, game::ResolutionReason::InitiativeTiebreak);
+
+        game.resolve_turn(host_plan, opp_plan).unwrap();
+        // Later posts land on top, so the feed reads in reverse resolution order.
+        let feed_ids: Vec<&str> = game.feed.iter().map(|c| c.instance_id.as_str()).collect();
+        assert_eq!(feed_ids, vec![post_ids[0].as_str(), post_ids[1].as_str()]);
+    }
+
+    #[test]
+    fn abyss_trigger_spawns_shitpost_in_hand() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 16, GameOptions::default());
+        for player in game.players.iter_mut() {
+            player.hand.clear();
+            player.kitchen.clear();
+        }
+        let tweet =
+            game.new_instance_from_def(find_definition("d11").unwrap(), Seat::Host, Location::Kitchen);
+        let tweet_id = tweet.instance_id.clone();
+        {
+            let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
+            host.kitchen.push(tweet);
+        }
+        game.apply_exploit_effect(
+            ExploitEffect::Damage(DamageParams {
+                amount: 5,
+                target: Target::EnemyKitchen,
+            }),
+            &Seat::Opponent,
+            Some(Target::Card(tweet_id.clone())),
+        )
+        .unwrap();
+        game.cleanup_board();
+
+        let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
+        assert!(host.abyss.iter().any(|c| c.instance_id == tweet_id));
+        assert_eq!(host.hand.len(), 1);
+        assert_eq!(host.hand[0].variant_id, "d06");
+    }
+
+    #[test]
+    fn last_stand_saves_strongest_kitchen_card_once() {
+        let mut app = make_app();
+        let mut game = start_default_game(
+            &mut app,
+            17,
+            GameOptions {
+                last_stand: LastStandRule::OncePerGame,
+                ..GameOptions::default()
+            },
+        );
+        for player in game.players.iter_mut() {
+            player.kitchen.clear();
+        }
+        let strong =
+            game.new_instance_from_def(find_definition("n03").unwrap(), Seat::Host, Location::Kitchen);
+        let weak =
+            game.new_instance_from_def(find_definition("n01").unwrap(), Seat::Host, Location::Kitchen);
+        let (strong_id, weak_id) = (strong.instance_id.clone(), weak.instance_id.clone());
+        {
+            let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
+            host.kitchen.push(weak);
+            host.kitchen.push(strong);
+            for card in host.kitchen.iter_mut() {
+                card.current_virality = 0;
+            }
+        }
+        game.cleanup_board();
+        {
+            let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
+            assert_eq!(host.kitchen.len(), 1);
+            assert_eq!(host.kitchen[0].instance_id, strong_id);
+            assert_eq!(host.kitchen[0].current_virality, 1);
+            assert!(host.abyss.iter().any(|c| c.instance_id == weak_id));
+            assert!(host.last_stand_used);
+        }
+        assert!(game
+            .events
+            .iter()
+            .any(|e| matches!(&e.event, game::GameEventKind::LastStand(ev) if ev.card_id == strong_id)));
+
+        {
+            let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
+            host.kitchen[0].current_virality = 0;
+        }
+        game.cleanup_board();
+        let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
+        assert!(host.kitchen.is_empty());
+        assert!(host.abyss.iter().any(|c| c.instance_id == strong_id));
+    }
+
+    #[test]
+    fn feed_turn_end_buff_accumulates() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 18, GameOptions::default());
+        game.turn = 1;
+        let mut echo =
+            game.new_instance_from_def(find_definition("m09").unwrap(), Seat::Host, Location::Kitchen);
+        echo.played_turn = 0;
+        let echo_id = echo.instance_id.clone();
+        let base = echo.current_virality;
+        {
+            let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
+            host.kitchen.push(echo);
+        }
+        let host_plan = TurnPlan {
+            posts: vec![PostAction {
+                card_id: echo_id.clone(),
+            }],
+            ..TurnPlan::default()
+        };
+        game.resolve_turn(host_plan, TurnPlan::default()).unwrap();
+        game.resolve_turn(TurnPlan::default(), TurnPlan::default()).unwrap();
+
+        let echo = game.feed.iter().find(|c| c.instance_id == echo_id).unwrap();
+        assert_eq!(echo.current_virality, base + 4);
+    }
+
+    #[test]
+    fn mana_reflects_revealed_plan_spending() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 19, GameOptions::default());
+        let card = game.new_instance_from_def(find_definition("n02").unwrap(), Seat::Host, Location::Hand);
+        let card_id = card.instance_id.clone();
+        {
+            let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
+            host.hand.push(card);
+        }
+        let (before, max) = game.mana_for(&Seat::Host).unwrap();
+        let plan = TurnPlan {
+            plays_to_kitchen: vec![card_id],
+            ..TurnPlan::default()
+        };
+        game.record_commit(Seat::Host, commitment_for(&plan, "salt", game.turn, game.game_seed)).unwrap();
+        game.record_reveal(Seat::Host, plan, "salt".into()).unwrap();
+
+        assert_eq!(game.mana_for(&Seat::Host).unwrap(), (before - 2, max));
+        assert_eq!(game.mana_for(&Seat::Opponent).unwrap().0, before);
+    }
+
+    #[test]
+    fn reseed_changes_draws_and_stays_reproducible() {
+        let kind = || rng::RandomEventKind::ShuffleFeed;
+        let mut plain = rng::FairRandomState::with_seeds(7, 7);
+        let mut reseeded = rng::FairRandomState::with_seeds(7, 7);
+        let mut replica = rng::FairRandomState::with_seeds(7, 7);
+        for state in [&mut plain, &mut reseeded, &mut replica] {
+            state.generate(1000, 0, kind());
+        }
+        reseeded.reseed(1, "host-salt", "opp-salt");
+        replica.reseed(1, "host-salt", "opp-salt");
+
+        let draws = |state: &mut rng::FairRandomState| -> Vec<u64> {
+            (0..8).map(|_| state.generate(1000, 1, kind())).collect()
+        };
+        let plain_draws = draws(&mut plain);
+        let reseeded_draws = draws(&mut reseeded);
+        assert_ne!(plain_draws, reseeded_draws);
+        assert_eq!(reseeded_draws, draws(&mut replica));
+        assert!(reseeded.verify(7));
+
+        let mut tampered = reseeded.clone();
+        tampered.reseeds[0].opponent_contribution = "other-salt".into();
+        assert!(!tampered.verify(7));
+    }
+
+    #[test]
+    fn revealed_salts_reseed_the_game_rng() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 20, GameOptions::default());
+        let plan = TurnPlan::default();
+        for (seat, salt) in [(Seat::Host, "h"), (Seat::Opponent, "o")] {
+            game.record_commit(seat.clone(), commitment_for(&plan, salt, game.turn, game.game_seed)).unwrap();
+            game.record_reveal(seat, plan.clone(), salt.into()).unwrap();
+        }
+        assert_eq!(game.turn, 1);
+        assert_eq!(game.rng.reseeds.len(), 1);
+        assert!(game.rng.verify(game.game_seed));
+    }
+
+    #[test]
+    fn playing_two_copies_counts_both() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 21, GameOptions::default());
+        let mut ids = Vec::new();
+        for _ in 0..2 {
+            let card =
+                game.new_instance_from_def(find_definition("n02").unwrap(), Seat::Host, Location::Hand);
+            ids.push(card.instance_id.clone());
+            let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
+            host.hand.push(card);
+        }
+        for id in ids.iter() {
+            game.play_to_kitchen(&Seat::Host, id).unwrap();
+        }
+        let (host, opp) = split_players_mut(&mut game.players, &Seat::Host);
+        assert_eq!(host.plays_this_game.get("n02"), Some(&2));
+        assert!(opp.plays_this_game.is_empty());
+    }
+
+    #[test]
+    fn rematch_keeps_lobby_and_gives_loser_initiative() {
+        let mut app = make_app();
+        app.lobbies.push(joined_lobby());
+        let first = app.launch_lobby_game(0, None).unwrap();
+        assert!(matches!(app.prepare_rematch("lobby-0"), Err(GameError::GameNotOver)));
+
+        if let Some(game) = app.game_mut() {
+            game.phase = Phase::GameOver;
+            game.winner = Some(Seat::Host);
+        }
+        let rematch = app.prepare_rematch("lobby-0").unwrap();
+        assert_eq!(rematch.initiative, Seat::Opponent);
+        assert_eq!(rematch.turn, 0);
+        assert_ne!(rematch.game_seed, first.game_seed);
+        assert_eq!(app.lobbies.len(), 1);
+        assert_eq!(app.lobbies[0].rematch_count, 1);
+        assert_eq!(app.game().unwrap().game_seed, rematch.game_seed);
+    }
+
+    #[test]
+    fn resolution_seed_varies_rolls_but_not_deck_shuffles() {
+        let build = |resolution_seed: Option<u64>| {
+            let mut app = make_app();
+            start_default_game(
+                &mut app,
+                22,
+                GameOptions {
+                    resolution_seed,
+                    ..GameOptions::default()
+                },
+            )
+        };
+        let deck_order = |game: &GameState| -> Vec<String> {
+            game.players
+                .iter()
+                .flat_map(|p| p.deck.iter().chain(p.hand.iter()))
+                .map(|c| c.instance_id.clone())
+                .collect()
+        };
+        let mut base = build(None);
+        let mut varied = build(Some(99));
+        assert_eq!(deck_order(&base), deck_order(&varied));
+
+        let rolls = |game: &mut GameState| -> Vec<u64> {
+            (0..8)
+                .map(|_| game.rng.generate(1000, 1, rng::RandomEventKind::ShuffleFeed))
+                .collect()
+        };
+        assert_ne!(rolls(&mut base), rolls(&mut varied));
+        assert!(varied.rng.verify(varied.game_seed));
+    }
+
+    #[test]
+    fn failed_game_sync_rolls_back_lobby_start() {
+        let mut app = make_app();
+        app.lobbies.push(joined_lobby());
+        let previous = (app.lobbies[0].clone(), app.game().cloned());
+        app.launch_lobby_game(0, None).unwrap();
+        assert!(app.lobbies[0].started);
+
+        let err = app
+            .settle_game_sync(0, previous, Err(GameError::Transport("timeout".into())))
+            .unwrap_err();
+        assert!(matches!(err, GameError::OpponentSyncFailed(ref msg) if msg.contains("timeout")));
+        assert!(!app.lobbies[0].started);
+        assert!(app.game().is_none());
+    }
+
+    #[test]
+    fn unaffordable_post_cost_rejects_whole_turn() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 23, GameOptions::default());
+        game.turn = 1;
+        let mut bait =
+            game.new_instance_from_def(find_definition("n11").unwrap(), Seat::Host, Location::Kitchen);
+        bait.played_turn = 0;
+        let doge = game.new_instance_from_def(find_definition("n01").unwrap(), Seat::Host, Location::Hand);
+        let (bait_id, doge_id) = (bait.instance_id.clone(), doge.instance_id.clone());
+        {
+            let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
+            host.kitchen.push(bait);
+            host.hand.push(doge);
+            host.mana = 2;
+        }
+        let plan = TurnPlan {
+            plays_to_kitchen: vec![doge_id.clone()],
+            posts: vec![PostAction { card_id: bait_id.clone() }],
+            ..TurnPlan::default()
+        };
+        let err = game.resolve_turn(plan, TurnPlan::default()).unwrap_err();
+        assert!(matches!(err, GameError::InsufficientMana(ManaParams { need: 3, have: 2 })));
+
+        let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
+        assert_eq!(host.mana, 2);
+        assert!(host.hand.iter().any(|c| c.instance_id == doge_id));
+        assert!(host.kitchen.iter().any(|c| c.instance_id == bait_id));
+    }
+
+    #[test]
+    fn doomscroll_draws_from_deck() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 24, GameOptions::default());
+        let effect = match &find_definition("d12").unwrap().class {
+            CardKind::Exploit(effect) => effect.clone(),
+            _ => panic!("d12 should be an exploit"),
+        };
+        let (hand_before, deck_before) = {
+            let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
+            (host.hand.len(), host.deck.len())
+        };
+        game.apply_exploit_effect(effect, &Seat::Host, None).unwrap();
+
+        let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
+        assert_eq!(host.hand.len(), hand_before + 2);
+        assert_eq!(host.deck.len(), deck_before - 2);
+    }
+
+    #[test]
+    fn feed_sabotage_sinks_opponent_posts_only() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 25, GameOptions::default());
+        let slot = || Location::Feed(FeedSlot { slot: 0 });
+        let weak = game.new_instance_from_def(find_definition("n01").unwrap(), Seat::Opponent, slot());
+        let ours = game.new_instance_from_def(find_definition("n02").unwrap(), Seat::Host, slot());
+        let strong = game.new_instance_from_def(find_definition("n05").unwrap(), Seat::Opponent, slot());
+        let ids: Vec<String> = [&weak, &ours, &strong].iter().map(|c| c.instance_id.clone()).collect();
+        game.feed = vec![weak, ours, strong];
+        game.reindex_feed();
+
+        game.apply_exploit_effect(
+            ExploitEffect::FeedSabotage,
+            &Seat::Host,
+            Some(Target::FeedOrder(vec![1, 0])),
+        )
+        .unwrap();
+
+        let order: Vec<&str> = game.feed.iter().map(|c| c.instance_id.as_str()).collect();
+        assert_eq!(order, vec![ids[2].as_str(), ids[1].as_str(), ids[0].as_str()]);
+        assert_eq!(game.feed[1].location, Location::Feed(FeedSlot { slot: 1 }));
+        assert!(game.events.iter().any(|e| matches!(
+            &e.event,
+            game::GameEventKind::FeedSabotage(ev) if ev.slots == vec![0, 2] && ev.after[0] == ids[2]
+        )));
+    }
+
+    #[test]
+    fn cosmetics_show_in_snapshot_but_not_state_hash() {
+        let mut app = make_app();
+        let started = start_default_game(&mut app, 26, GameOptions::default());
+        app.install_game(started);
+        let before = app.game().unwrap().state_hash();
+
+        app.apply_cosmetics(PlayerCosmetics {
+            deck_back: Some("holo".into()),
+            avatar: None,
+            title: Some("Chief Shitposter".into()),
+        });
+        let snapshot = app.compose_snapshot();
+        let host = &snapshot.game.as_ref().unwrap().players[0];
+        assert_eq!(
+            host.cosmetics.as_ref().and_then(|c| c.deck_back.as_deref()),
+            Some("holo")
+        );
+        assert_eq!(app.game().unwrap().state_hash(), before);
+    }
+
+    #[test]
+    fn feed_yield_is_logged_and_capped() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 27, GameOptions::default());
+        let slot = || Location::Feed(FeedSlot { slot: 0 });
+        let host_card = game.new_instance_from_def(find_definition("n01").unwrap(), Seat::Host, slot());
+        let opp_card = game.new_instance_from_def(find_definition("n01").unwrap(), Seat::Opponent, slot());
+        game.feed = vec![host_card, opp_card];
+        game.reindex_feed();
+
+        game.apply_feed_yield();
+        assert_eq!(game.score_log.len(), 2);
+        for player in game.players.iter() {
+            let logged: i32 = game
+                .score_log
+                .iter()
+                .filter(|d| d.seat == player.seat && d.reason == game::ScoreReason::FeedYield)
+                .map(|d| d.amount)
+                .sum();
+            assert_eq!(logged, player.score);
+        }
+
+        for _ in 0..constants::MAX_SCORE_LOG {
+            game.apply_feed_yield();
+        }
+        assert_eq!(game.score_log.len(), constants::MAX_SCORE_LOG);
+    }
+
+    #[test]
+    fn seed_source_makes_lobby_games_reproducible() {
+        let mut app = make_app();
+        app.seed_source = Some(500);
+        app.lobbies.push(joined_lobby());
+        let first = app.launch_lobby_game(0, None).unwrap();
+        let second = app.launch_lobby_game(0, None).unwrap();
+        assert_eq!(first.game_seed, 500);
+        assert_eq!(second.game_seed, 501);
+    }
+
+    #[test]
+    fn steal_moves_enemy_post_to_bottom_under_new_owner() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 28, GameOptions::default());
+        let slot = || Location::Feed(FeedSlot { slot: 0 });
+        let mut target =
+            game.new_instance_from_def(find_definition("t05").unwrap(), Seat::Opponent, slot());
+        target.current_virality = 9;
+        let ours = game.new_instance_from_def(find_definition("n01").unwrap(), Seat::Host, slot());
+        let target_id = target.instance_id.clone();
+        let keywords = target.keywords.clone();
+        game.feed = vec![target, ours];
+        game.reindex_feed();
+        let kitchen_card =
+            game.new_instance_from_def(find_definition("n02").unwrap(), Seat::Opponent, Location::Kitchen);
+        let freeboot = game.new_instance_from_def(find_definition("t11").unwrap(), Seat::Host, Location::Hand);
+        let action = |target: &str| ExploitAction {
+            card_id: freeboot.instance_id.clone(),
+            target: Some(Target::Card(target.to_string())),
+        };
+        let (kitchen_action, feed_action) = (action(&kitchen_card.instance_id), action(&target_id));
+        {
+            let (host, opp) = split_players_mut(&mut game.players, &Seat::Host);
+            opp.kitchen.push(kitchen_card);
+            host.hand.push(freeboot);
+        }
+        assert_eq!(
+            game.validate_exploit_target_seat(&Seat::Host, &kitchen_action),
+            Err(GameError::InvalidTarget)
+        );
+        assert!(game.validate_exploit_target_seat(&Seat::Host, &feed_action).is_ok());
+
+        game.apply_exploit_effect(ExploitEffect::Steal, &Seat::Host, Some(Target::Card(target_id.clone())))
+            .unwrap();
+        let stolen = game.feed.last().unwrap();
+        assert_eq!(stolen.instance_id, target_id);
+        assert_eq!(stolen.owner, Seat::Host);
+        assert_eq!(stolen.current_virality, 9);
+        assert_eq!(stolen.keywords, keywords);
+        assert_eq!(stolen.location, Location::Feed(FeedSlot { slot: 1 }));
+
+        game.apply_feed_yield();
+        let (host, opp) = split_players_mut(&mut game.players, &Seat::Host);
+        assert!(host.score > 0);
+        assert_eq!(opp.score, 0);
+    }
+
+    #[test]
+    fn exploit_gone_before_cast_is_refunded() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 29, GameOptions::default());
+        let scroll = game.new_instance_from_def(find_definition("d12").unwrap(), Seat::Host, Location::Hand);
+        let scroll_id = scroll.instance_id.clone();
+        {
+            let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
+            host.hand.push(scroll);
+            host.mana = 5;
+        }
+        let cast = ExploitAction {
+            card_id: scroll_id.clone(),
+            target: None,
+        };
+        // Listing the same card twice means the second cast finds it already gone.
+        let plan = TurnPlan {
+            exploits: vec![cast.clone(), cast],
+            ..TurnPlan::default()
+        };
+        let charges = game.apply_turn_for_seat(Seat::Host, plan.clone()).unwrap();
+        assert_eq!(charges, vec![1, 1]);
+
+        game.resolve_exploits(&Seat::Host, &plan, &TurnPlan::default(), (&charges, &[]))
+            .unwrap();
+        let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
+        assert_eq!(host.mana, 4);
+        assert_eq!(host.abyss.iter().filter(|c| c.instance_id == scroll_id).count(), 1);
+    }
+
+    #[test]
+    fn resolution_emits_battle_log_events() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 30, GameOptions::default());
+        game.turn = 1;
+        let bomb = game.new_instance_from_def(find_definition("t01").unwrap(), Seat::Host, Location::Hand);
+        let mut victim =
+            game.new_instance_from_def(find_definition("n01").unwrap(), Seat::Opponent, Location::Kitchen);
+        victim.current_virality = 2;
+        let mut poster =
+            game.new_instance_from_def(find_definition("n02").unwrap(), Seat::Host, Location::Kitchen);
+        poster.played_turn = 0;
+        let (bomb_id, victim_id, poster_id) = (
+            bomb.instance_id.clone(),
+            victim.instance_id.clone(),
+            poster.instance_id.clone(),
+        );
+        {
+            let (host, opp) = split_players_mut(&mut game.players, &Seat::Host);
+            host.hand.push(bomb);
+            host.kitchen.push(poster);
+            host.mana = 5;
+            opp.kitchen.push(victim);
+        }
+        let plan = TurnPlan {
+            posts: vec![PostAction { card_id: poster_id.clone() }],
+            exploits: vec![ExploitAction {
+                card_id: bomb_id.clone(),
+                target: Some(Target::Card(victim_id.clone())),
+            }],
+            ..TurnPlan::default()
+        };
+        let charges = game.apply_turn_for_seat(Seat::Host, plan.clone()).unwrap();
+        game.resolve_exploits(&Seat::Host, &plan, &TurnPlan::default(), (&charges, &[]))
+            .unwrap();
+        game.resolve_posts(&plan.posts, &[]).unwrap();
+        game.cleanup_board();
+
+        let log: Vec<&game::GameEventKind> = game.events.iter().map(|e| &e.event).collect();
+        assert!(log.iter().any(|e| matches!(e,
+            game::GameEventKind::ExploitCast(ev) if ev.card_id == bomb_id && ev.seat == Seat::Host)));
+        assert!(log.iter().any(|e| matches!(e,
+            game::GameEventKind::Damage(ev) if ev.target == victim_id && ev.amount == 3)));
+        assert!(log.iter().any(|e| matches!(e,
+            game::GameEventKind::CardDied(ev) if ev.instance_id == victim_id && ev.zone == Location::Kitchen)));
+        assert!(log.iter().any(|e| matches!(e,
+            game::GameEventKind::Posted(ev) if ev.instance_id == poster_id && ev.slot == 0)));
+        assert!(log.iter().any(|e| matches!(e, game::GameEventKind::Random(_))));
+    }
+
+    #[test]
+    fn full_disclosure_reveals_decks_only_at_game_over() {
+        let mut app = make_app();
+        let mut game = start_default_game(
+            &mut app,
+            31,
+            GameOptions {
+                full_disclosure: true,
+                ..GameOptions::default()
+            },
+        );
+        assert!(!game.players[1].deck.is_empty());
+        app.install_game(game.clone());
+        let view = app.compose_snapshot().for_client(&our().node).game.unwrap();
+        assert!(view
+            .players
+            .iter()
+            .all(|p| p.deck.iter().all(|c| c.variant_id == "hidden")));
+
+        game.phase = Phase::GameOver;
+        app.install_game(game.clone());
+        let view = app.compose_snapshot().for_client(&our().node).game.unwrap();
+        assert_eq!(view.players[1].deck, game.players[1].deck);
+
+        game.full_disclosure = false;
+        let view = game.client_view(&our().node);
+        assert_eq!(view.players[1].deck.len(), game.players[1].deck.len());
+        assert!(view.players[1].deck.iter().all(|c| c.variant_id == "hidden"));
+    }
+
+    #[test]
+    fn replay_rebuilds_game_turn_by_turn() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 26, GameOptions::default());
+        let plan = TurnPlan::default();
+        let mut hashes = vec![];
+        for turn in 0..3 {
+            for (seat, salt) in [(Seat::Host, "h"), (Seat::Opponent, "o")] {
+                let salt = format!("{salt}{turn}");
+                game.record_commit(seat.clone(), commitment_for(&plan, &salt, game.turn, game.game_seed)).unwrap();
+                game.record_reveal(seat, plan.clone(), salt).unwrap();
+            }
+            hashes.push(game.state_hash_after(turn).unwrap().to_string());
+        }
+
+        let replayed = GameState::replay_from(game.game_seed, &game.events).unwrap();
+        for (turn, hash) in hashes.iter().enumerate() {
+            assert_eq!(replayed.state_hash_after(turn as u32), Some(hash.as_str()));
+        }
+        assert_eq!(replayed.state_hash(), game.state_hash());
+
+        let mut tampered = game.events.clone();
+        if let Some(game::GameEventKind::TurnResolved(resolved)) =
+            tampered.iter_mut().rev().map(|e| &mut e.event).next()
+        {
+            resolved.state_hash = "bogus".into();
+        }
+        assert!(matches!(
+            GameState::replay_from(game.game_seed, &tampered),
+            Err(GameError::StateHashMismatch)
+        ));
+        assert!(matches!(
+            GameState::replay_from(game.game_seed, &game.events[1..]),
+            Err(GameError::ReplayMissingStart)
+        ));
+    }
+
+    #[test]
+    fn action_budget_rejects_plans_over_budget() {
+        let mut app = make_app();
+        let mut game = start_default_game(
+            &mut app,
+            27,
+            GameOptions {
+                action_budget: ActionBudgetRule::PerTurn(2),
+                ..GameOptions::default()
+            },
+        );
+        let post = |id: &str| PostAction { card_id: id.into() };
+        let plan = TurnPlan {
+            plays_to_kitchen: vec!["a".into()],
+            posts: vec![post("b"), post("c")],
+            ..TurnPlan::default()
+        };
+        let err = game.record_reveal(Seat::Host, plan, "salt".into()).unwrap_err();
+        assert!(matches!(err, GameError::PlanTooLarge(ref p) if p.section == "actions" && p.len == 3 && p.max == 2));
+
+        assert_eq!(validate_plan(&TurnPlan { posts: vec![post("b"), post("c")], ..TurnPlan::default() }, Some(2)), Ok(()));
+        assert!(validate_plan(&TurnPlan { posts: vec![post("a"), post("b"), post("c")], ..TurnPlan::default() }, None).is_ok());
+
+        game.players[0].action_points = Some(0);
+        game.players[0].reset_for_new_turn(&GameRules::default(), &game.action_budget.clone());
+        assert_eq!(game.action_points_for(&Seat::Host), Some(2));
+    }
+
+    #[test]
+    fn chat_rejects_long_text_and_keeps_recent_history() {
+        let mut app = make_app();
+        assert!(matches!(app.compose_chat("hi".into()), Err(GameError::NoActiveGame)));
+        let started = start_default_game(&mut app, 28, GameOptions::default());
+        app.install_game(started);
+        assert!(matches!(
+            app.compose_chat("x".repeat(MAX_CHAT_LEN + 1)),
+            Err(GameError::ChatTooLong)
+        ));
+
+        for i in 0..MAX_CHAT_LOG + 5 {
+            let chat = app.compose_chat(format!("gg {i}")).unwrap();
+            app.record_chat(chat);
+        }
+        assert_eq!(app.chat_log.len(), MAX_CHAT_LOG);
+        assert_eq!(app.chat_log[0].text, "gg 5");
+        assert_eq!(app.compose_snapshot().chat_log.len(), MAX_CHAT_LOG);
+    }
+
+    #[test]
+    fn unknown_ws_message_keeps_id_and_is_unsupported() {
+        let payload = r#"{"id":"req-9","type":"FutureThing","data":{"x":1}}"#;
+        let envelope = WsEnvelope::<WsClientMessage>::parse(payload).unwrap();
+        assert_eq!(envelope.id.as_deref(), Some("req-9"));
+        assert_eq!(envelope.message, WsClientMessage::Unknown);
+        assert!(WsEnvelope::<WsClientMessage>::parse(r#"{"id":"req-10","type":"SendChat","data":{}}"#).is_err());
+
+        let mut app = make_app();
+        let reply = app.process_ws_message(envelope.message);
+        let mut reply = std::pin::pin!(reply);
+        let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+        assert!(matches!(
+            std::future::Future::poll(reply.as_mut(), &mut cx),
+            std::task::Poll::Ready(Ok(WsServerMessage::UnsupportedMessage))
+        ));
+    }
+
+    #[test]
+    fn state_hash_ignores_event_log_and_bookkeeping() {
+        let mut app = make_app();
+        let game = start_default_game(&mut app, 30, GameOptions::default());
+        let mut noisy = game.clone();
+        noisy.events.reverse();
+        noisy.events.truncate(1);
+        noisy.score_log.push(game::ScoreDelta {
+            turn: 0,
+            seat: Seat::Host,
+            amount: 0,
+            reason: game::ScoreReason::FeedYield,
+        });
+        noisy.rng.history.clear();
+        assert_eq!(noisy.state_hash(), game.state_hash());
+
+        noisy.players[0].score += 1;
+        assert_ne!(noisy.state_hash(), game.state_hash());
+    }
+
+    #[test]
+    fn feed_domination_streak_wins_only_when_uninterrupted() {
+        let mut app = make_app();
+        let mut game = start_default_game(
+            &mut app,
+            31,
+            GameOptions {
+                win_condition: WinCondition::FeedDominationStreak(2),
+                ..GameOptions::default()
+            },
+        );
+        let def = find_definition("n01").unwrap();
+        let host_card = game.new_instance_from_def(def, Seat::Host, Location::Feed(FeedSlot { slot: 0 }));
+        let opp_card = game.new_instance_from_def(def, Seat::Opponent, Location::Feed(FeedSlot { slot: 1 }));
+
+        game.update_domination_streak();
+        assert_eq!(game.domination_streak, [0, 0]);
+
+        game.feed = vec![host_card.clone()];
+        game.update_domination_streak();
+        game.feed.push(opp_card);
+        game.update_domination_streak();
+        game.feed = vec![host_card];
+        game.update_domination_streak();
+        assert_eq!(game.domination_streak, [1, 0]);
+        assert_eq!(game.check_win_condition(), None);
+
+        game.update_domination_streak();
+        assert_eq!(game.check_win_condition(), Some(Seat::Host));
+    }
+
+    #[test]
+    fn exhausted_decks_end_the_game() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 32, GameOptions::default());
+        for player in game.players.iter_mut() {
+            player.deck.clear();
+            player.hand.clear();
+            player.kitchen.clear();
+        }
+        game.resolve_turn(TurnPlan::default(), TurnPlan::default()).unwrap();
+        assert_eq!(game.phase, Phase::GameOver);
+        assert_eq!(game.outcome, Some(GameOutcome::Draw));
+        assert_eq!(game.winner, None);
+
+        let mut game = start_default_game(&mut app, 33, GameOptions::default());
+        for player in game.players.iter_mut() {
+            player.deck.clear();
+            player.hand.clear();
+            player.kitchen.clear();
+        }
+        game.players[1].score = 5;
+        game.resolve_turn(TurnPlan::default(), TurnPlan::default()).unwrap();
+        assert_eq!(game.outcome, Some(GameOutcome::Win(Seat::Opponent)));
+        assert_eq!(game.winner, Some(Seat::Opponent));
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    fn stakes_override_is_clamped() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 33, GameOptions::default());
+        game.override_stakes(5);
+        assert_eq!(game.stakes, 5);
+        game.override_stakes(0);
+        assert_eq!(game.stakes, 1);
+        game.override_stakes(u8::MAX);
+        assert_eq!(game.stakes, constants::MAX_STAKES);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    fn overridden_stakes_multiply_feed_yield() {
+        let mut app = make_app();
+        let mut game =
+            build_game(&app.catalog, &mut app.next_instance, 4, vec!["n01".into()], vec!["n01".into()], "opp.os".into(), GameOptions::default())
+                .unwrap();
+        let card_def = find_definition("n01").unwrap();
+        let card =
+            game.new_instance_from_def(card_def, Seat::Host, Location::Feed(FeedSlot { slot: 0 }));
+        game.feed.push(card);
+
+        game.apply_feed_yield();
+        let single = game.players.iter().find(|p| p.seat == Seat::Host).unwrap().score;
+        game.override_stakes(5);
+        game.apply_feed_yield();
+        let host = game.players.iter().find(|p| p.seat == Seat::Host).unwrap();
+        assert!(single > 0);
+        assert_eq!(host.score, single * 6);
+    }
+
+    #[test]
+    fn area_damage_skips_stealth_but_hits_taunt() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 34, GameOptions::default());
+        let def = find_definition("n01").unwrap();
+        let mut stealthy = game.new_instance_from_def(def, Seat::Opponent, Location::Kitchen);
+        stealthy.keywords.push(Keyword::Stealth);
+        let mut taunting = game.new_instance_from_def(def, Seat::Opponent, Location::Kitchen);
+        taunting.keywords.push(Keyword::Taunt);
+        let plain = game.new_instance_from_def(def, Seat::Opponent, Location::Kitchen);
+        let before = plain.current_virality;
+        game.players[1].kitchen = vec![stealthy, taunting, plain];
+
+        game.apply_exploit_effect(ExploitEffect::AreaDamageKitchen(1), &Seat::Host, None)
+            .unwrap();
+        let kitchen = &game.players[1].kitchen;
+        assert_eq!(kitchen[0].current_virality, before);
+        assert_eq!(kitchen[1].current_virality, before - 1);
+        assert_eq!(kitchen[2].current_virality, before - 1);
+    }
+
+    #[test]
+    fn pin_follows_card_when_feed_above_is_removed() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 35, GameOptions::default());
+        let def = find_definition("n01").unwrap();
+        game.feed = (0..3)
+            .map(|slot| game.new_instance_from_def(def, Seat::Host, Location::Feed(FeedSlot { slot })))
+            .collect();
+        game.reindex_feed();
+        let pinned_id = game.feed[2].instance_id.clone();
+        let neighbour_id = game.feed[1].instance_id.clone();
+
+        game.apply_exploit_effect(ExploitEffect::PinSlot(2), &Seat::Opponent, None)
+            .unwrap();
+        game.feed.remove(0);
+        game.reindex_feed();
+
+        assert!(game.feed_slot_fixed(1));
+        assert_eq!(game.feed[1].instance_id, pinned_id);
+        assert!(!game.feed_slot_fixed(0));
+        assert_eq!(game.feed[0].instance_id, neighbour_id);
+    }
+
+    #[test]
+    fn diagnose_reports_every_broken_rule() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 36, GameOptions::default());
+        game.turn = 1;
+        let def = find_definition("n01").unwrap();
+        let memes: Vec<CardInstance> = (0..2)
+            .map(|_| game.new_instance_from_def(def, Seat::Host, Location::Hand))
+            .collect();
+        let ids: Vec<String> = memes.iter().map(|c| c.instance_id.clone()).collect();
+        game.players[0].hand = memes;
+        game.players[0].mana = 10;
+
+        let plan = TurnPlan {
+            plays_to_kitchen: ids.clone(),
+            posts: vec![PostAction { card_id: "missing".into() }],
+            ..TurnPlan::default()
+        };
+        let before = game.state_hash();
+        let diagnostics = game.diagnose(&Seat::Host, &plan);
+        assert_eq!(
+            diagnostics.iter().map(|d| d.error.clone()).collect::<Vec<_>>(),
+            vec![GameError::TooManyKitchenPlays, GameError::CardNotInKitchen("missing".into())]
+        );
+        assert_eq!(game.state_hash(), before);
+
+        let ok = TurnPlan { plays_to_kitchen: vec![ids[0].clone()], ..TurnPlan::default() };
+        assert!(game.diagnose(&Seat::Host, &ok).is_empty());
+    }
+
+    #[test]
+    fn shield_is_consumed_by_damage() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 37, GameOptions::default());
+        let def = find_definition("n01").unwrap();
+        let mut card = game.new_instance_from_def(def, Seat::Opponent, Location::Kitchen);
+        card.shield = 2;
+        card.current_virality = 10;
+        let id = card.instance_id.clone();
+        game.players[1].kitchen = vec![card];
+
+        game.apply_damage_targeted(&Seat::Host, Target::Card(id.clone()), 3).unwrap();
+        assert_eq!(game.players[1].kitchen[0].current_virality, 9);
+        assert_eq!(game.players[1].kitchen[0].shield, 0);
+
+        game.apply_damage_targeted(&Seat::Host, Target::Card(id), 3).unwrap();
+        assert_eq!(game.players[1].kitchen[0].current_virality, 6);
+    }
+
+    #[test]
+    fn game_rules_set_deck_limits_hand_and_mana() {
+        let mut app = make_app();
+        let rules = GameRules {
+            deck_size: 6,
+            meme_limit: 2,
+            exploit_limit: 4,
+            starting_hand: 3,
+            starting_mana: 4,
+            mana_cap: 5,
+            draft_draw: false,
+            discard_phase: false,
+            max_turns: None,
+            max_opening_cost: None,
+            feed_decay: 0,
+        };
+        let is_meme = |id: &String| matches!(find_definition(id).unwrap().class, CardKind::Meme(_));
+        let deck = default_deck();
+        let mut draft: Vec<String> = deck.iter().filter(|id| is_meme(id)).take(2).cloned().collect();
+        draft.extend(deck.iter().filter(|id| !is_meme(id)).take(4).cloned());
+        let options = GameOptions { rules: rules.clone(), ..GameOptions::default() };
+
+        let game = build_game(&app.catalog, &mut app.next_instance, 38, draft.clone(), draft, "opp.os".into(), options.clone())
+            .unwrap();
+        assert_eq!(game.phase, Phase::Commit);
+        assert_eq!(game.players[0].hand.len(), 3);
+        assert_eq!(game.players[0].max_mana, 4);
+        let mut player = game.players[0].clone();
+        player.reset_for_new_turn(&rules, &ActionBudgetRule::Unlimited);
+        player.reset_for_new_turn(&rules, &ActionBudgetRule::Unlimited);
+        assert_eq!(player.max_mana, 5);
+
+        let standard = start_default_game(&mut app, 38, options);
+        assert_eq!(standard.phase, Phase::GameOver);
+        assert_eq!(standard.outcome, Some(GameOutcome::Draw));
+    }
+
+    #[test]
+    fn clone_copies_own_kitchen_card() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 39, GameOptions::default());
+        game.turn = 1;
+        let def = find_definition("n01").unwrap();
+        let mut original = game.new_instance_from_def(def, Seat::Host, Location::Kitchen);
+        original.played_turn = 0;
+        original.current_virality += 5;
+        let original_id = original.instance_id.clone();
+        let enemy = game.new_instance_from_def(def, Seat::Opponent, Location::Kitchen);
+        let copypasta = game.new_instance_from_def(find_definition("t12").unwrap(), Seat::Host, Location::Hand);
+        let action = |target: &str| ExploitAction {
+            card_id: copypasta.instance_id.clone(),
+            target: Some(Target::Card(target.to_string())),
+        };
+        let enemy_action = action(&enemy.instance_id);
+        game.players[0].kitchen = vec![original];
+        game.players[0].hand.push(copypasta.clone());
+        game.players[1].kitchen.push(enemy);
+        assert_eq!(game.validate_exploit_target_seat(&Seat::Host, &enemy_action), Err(GameError::InvalidTarget));
+        assert!(game.validate_exploit_target_seat(&Seat::Host, &action(&original_id)).is_ok());
+
+        game.apply_exploit_effect(ExploitEffect::Clone, &Seat::Host, Some(Target::Card(original_id.clone())))
+            .unwrap();
+        let kitchen = &game.players[0].kitchen;
+        assert_eq!(kitchen.len(), 2);
+        assert_ne!(kitchen[1].instance_id, original_id);
+        assert_eq!(kitchen[1].variant_id, "n01");
+        assert_eq!(kitchen[1].owner, Seat::Host);
+        assert_eq!(kitchen[1].current_virality, kitchen[1].base_virality);
+        assert_eq!(kitchen[1].played_turn, 1);
+    }
+
+    #[test]
+    fn empty_plans_mark_seats_as_passed() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 40, GameOptions::default());
+        game.resolve_turn(TurnPlan::default(), TurnPlan::default()).unwrap();
+        assert_eq!(game.passed_last_turn, [true, true]);
+
+        let meme = game.players[0]
+            .hand
+            .iter()
+            .find(|c| matches!(c.class, CardKind::Meme(_)))
+            .unwrap()
+            .instance_id
+            .clone();
+        game.players[0].mana = 10;
+        let plan = TurnPlan { plays_to_kitchen: vec![meme], ..TurnPlan::default() };
+        game.resolve_turn(plan, TurnPlan::default()).unwrap();
+        assert_eq!(game.passed_last_turn, [false, true]);
+    }
+
+    #[test]
+    fn scry_reveals_deck_top_only_to_the_scrier() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 41, GameOptions::default());
+        let top: Vec<String> = game.players[0].deck.iter().rev().take(2).map(|c| c.variant_id.clone()).collect();
+        game.apply_exploit_effect(ExploitEffect::Scry(2), &Seat::Host, None).unwrap();
+        game.scry(&Seat::Opponent, 1);
+        assert_eq!(game.players[0].revealed_deck_top, top);
+
+        let host_view = game.client_view(&game.players[0].node_id);
+        assert_eq!(host_view.players[0].revealed_deck_top, top);
+        assert!(host_view.players[1].revealed_deck_top.is_empty());
+        let opponent_view = game.client_view("opp.os");
+        assert!(opponent_view.players[0].revealed_deck_top.is_empty());
+        assert_eq!(opponent_view.players[1].revealed_deck_top.len(), 1);
+
+        game.players[0].draw_card().unwrap();
+        assert!(game.players[0].revealed_deck_top.is_empty());
+    }
+
+    #[test]
+    fn sample_decks_are_valid_and_distinct() {
+        let mut app = make_app();
+        let decks = sample_decks();
+        for (name, deck) in decks.iter() {
+            let game = build_game(&app.catalog, &mut app.next_instance, 42, deck.clone(), deck.clone(), "opp.os".into(), GameOptions::default())
+                .unwrap();
+            assert_eq!(game.phase, Phase::Commit, "{name} deck failed validation");
+        }
+        assert_ne!(decks[0].1, decks[1].1);
+        assert_ne!(decks[1].1, decks[2].1);
+    }
+
+    #[test]
+    fn client_snapshot_keeps_a_window_of_events() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 43, GameOptions::default());
+        for _ in 0..5 {
+            game.resolve_turn(TurnPlan::default(), TurnPlan::default()).unwrap();
+        }
+        let filler = game.events[1].clone();
+        game.events.extend(std::iter::repeat_n(filler, SNAPSHOT_EVENT_WINDOW * 2));
+        let total = game.events.len();
+        let hash = game.state_hash();
+        app.install_game(game.clone());
+
+        let snapshot = app.compose_snapshot().for_client(&our().node);
+        let view = snapshot.game.unwrap();
+        assert_eq!(snapshot.total_events, total);
+        assert_eq!(view.events.len(), SNAPSHOT_EVENT_WINDOW);
+        assert_eq!(view.events[..], game.events[total - SNAPSHOT_EVENT_WINDOW..]);
+        assert!(view.rng.history.len() <= SNAPSHOT_EVENT_WINDOW);
+        assert_eq!(app.game().unwrap().events.len(), total);
+        assert_eq!(app.game().unwrap().state_hash(), hash);
+
+        let oldest = game.events_since(0, SNAPSHOT_EVENT_WINDOW);
+        assert_eq!(oldest, &game.events[..SNAPSHOT_EVENT_WINDOW]);
+        assert!(game.events_since(total + 10, SNAPSHOT_EVENT_WINDOW).is_empty());
+    }
+
+    #[test]
+    fn client_view_hides_the_other_hand() {
+        let mut app = make_app();
+        let game = start_default_game(&mut app, 44, GameOptions::default());
+        let view = game.redacted_for(&Seat::Host);
+        assert_eq!(view.players[0].hand, game.players[0].hand);
+        assert_eq!(view.players[1].hand.len(), game.players[1].hand.len());
+        for card in view.players[1].hand.iter().chain(view.players[0].deck.iter()) {
+            assert_eq!(card.variant_id, "hidden");
+            assert!(card.instance_id.is_empty());
+        }
+
+        app.install_game(game.clone());
+        let snapshot = app.compose_snapshot().for_client(&our().node).game.unwrap();
+        assert_eq!(snapshot.players[0].hand, game.players[0].hand);
+        assert!(snapshot.players[1].hand.iter().all(|c| c.variant_id == "hidden"));
+        let spectator = game.client_view("someone-else.os");
+        assert!(spectator.players.iter().all(|p| p.hand.iter().all(|c| c.variant_id == "hidden")));
+    }
+
+    #[test]
+    fn tampered_random_draw_fails_verification() {
+        let mut app = make_app();
+        let game = start_default_game(&mut app, 45, GameOptions::default());
+        let event = game.rng.history[0].clone();
+        assert!(game.rng.verify_event(&event).is_ok());
+        assert!(event.contributions.iter().all(|c| !c.remote_signed));
+
+        let mut tampered = event.clone();
+        tampered.contributions[0].value += 1;
+        assert!(game.rng.verify_event(&tampered).is_err());
+
+        let mut tampered = event.clone();
+        tampered.result = (tampered.result + 1) % tampered.bound.max(2);
+        assert!(game.rng.verify_event(&tampered).is_err());
+
+        let mut forged = event;
+        forged.contributions[1].value = (forged.contributions[1].value + 1) % forged.bound;
+        forged.contributions[1].commitment =
+            rng::contribution_commitment(forged.contributions[1].value, &forged.contributions[1].salt);
+        forged.result = (forged.contributions[0].value + forged.contributions[1].value) % forged.bound;
+        assert_eq!(
+            game.rng.verify_event(&forged),
+            Err("draw not found in history".to_string())
+        );
+    }
+
+    #[test]
+    fn bootleg_copies_enemy_meme_for_the_caster() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 46, GameOptions::default());
+        let def = find_definition("n01").unwrap();
+        let mut target = game.new_instance_from_def(def, Seat::Opponent, Location::Kitchen);
+        target.current_virality += 5;
+        let target_id = target.instance_id.clone();
+        let mut hidden = game.new_instance_from_def(def, Seat::Opponent, Location::Kitchen);
+        hidden.keywords.push(Keyword::Stealth);
+        let bootleg = game.new_instance_from_def(find_definition("t13").unwrap(), Seat::Host, Location::Hand);
+        let action = |target: &str| ExploitAction {
+            card_id: bootleg.instance_id.clone(),
+            target: Some(Target::Card(target.to_string())),
+        };
+        let hidden_action = action(&hidden.instance_id);
+        game.players[0].kitchen.clear();
+        game.players[0].hand.push(bootleg.clone());
+        game.players[1].kitchen = vec![target, hidden];
+        assert_eq!(game.validate_exploit_target_seat(&Seat::Host, &hidden_action), Err(GameError::TargetStealthed));
+        assert!(game.validate_exploit_target_seat(&Seat::Host, &action(&target_id)).is_ok());
+
+        game.apply_exploit_effect(ExploitEffect::CloneEnemy, &Seat::Host, Some(Target::Card(target_id.clone())))
+            .unwrap();
+        let copy = &game.players[0].kitchen[0];
+        assert_ne!(copy.instance_id, target_id);
+        assert_eq!(copy.variant_id, "n01");
+        assert_eq!(copy.owner, Seat::Host);
+        assert_eq!(copy.current_virality, copy.base_virality);
+        assert_eq!(game.players[1].kitchen.len(), 2);
+    }
+
+    #[test]
+    fn card_stats_accumulate_posts_and_yield() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 47, GameOptions::default());
+        game.feed.clear();
+        let mut card = game.new_instance_from_def(find_definition("n01").unwrap(), Seat::Host, Location::Kitchen);
+        card.played_turn = 0;
+        game.turn = 1;
+        let card_id = card.instance_id.clone();
+        game.players[0].kitchen = vec![card];
+        game.resolve_posts(&[PostAction { card_id }], &[]).unwrap();
+        let score = game.players[0].score;
+        game.apply_feed_yield();
+        game.apply_feed_yield();
+
+        let stats = &game.card_stats["n01"];
+        assert_eq!(stats.posted, 1);
+        assert_eq!(stats.score_yielded, game.players[0].score - score);
+        assert!(stats.score_yielded > 0);
+        assert_eq!(stats.deaths, 0);
+    }
+
+    #[test]
+    fn broadcast_reports_resolved_turns_and_phase_changes() {
+        let mut app = make_app();
+        let game = start_default_game(&mut app, 48, GameOptions::default());
+        app.install_game(game);
+        assert!(app.progress_messages(&app.compose_broadcast_snapshot()).is_empty());
+        assert!(app.progress_messages(&app.compose_broadcast_snapshot()).is_empty());
+
+        let game = app.game_mut().unwrap();
+        let seen = game.events.len();
+        game.resolve_turn(TurnPlan::default(), TurnPlan::default()).unwrap();
+        let turn = game.turn;
+        let new_events = game.events[seen..].to_vec();
+        let messages = app.progress_messages(&app.compose_broadcast_snapshot());
+        assert_eq!(messages[0], WsServerMessage::TurnResolved { turn, from: seen, events: new_events });
+        assert!(matches!(&messages[1..], [WsServerMessage::Diff(diff)] if diff.turn == turn));
+
+        app.game_mut().unwrap().phase = Phase::GameOver;
+        let messages = app.progress_messages(&app.compose_broadcast_snapshot());
+        assert_eq!(messages[0], WsServerMessage::PhaseChanged(Phase::GameOver));
+        assert!(matches!(&messages[1..], [WsServerMessage::Diff(diff)] if diff.phase == Some(Phase::GameOver)));
+
+        let started = start_default_game(&mut app, 49, GameOptions::default());
+        app.install_game(started);
+        assert!(app.progress_messages(&app.compose_broadcast_snapshot()).is_empty());
+    }
+
+    #[test]
+    fn private_lobby_needs_password_and_stays_unlisted() {
+        let mut app = make_app();
+        let mut lobby = joined_lobby();
+        lobby.opponent = None;
+        lobby.private = true;
+        lobby.password_hash = Some(lobby_password_hash(&lobby.id, "hunter2"));
+        app.lobbies.push(lobby);
+        let payload = |password: Option<&str>| JoinLobbyPayload {
+            lobby_id: "lobby-0".into(),
+            node_id: "opp.os".into(),
+            deck: default_deck(),
+            cosmetics: None,
+            full_disclosure: false,
+            password: password.map(str::to_string),
+        };
+
+        assert!(app.compose_remote_snapshot("opp.os").lobbies.is_empty());
+        assert!(app.compose_snapshot().lobbies.iter().all(|l| l.password_hash.is_none()));
+        assert_eq!(app.admit_to_lobby(payload(None)), Err(GameError::WrongLobbyPassword));
+        assert_eq!(app.admit_to_lobby(payload(Some("hunter3"))), Err(GameError::WrongLobbyPassword));
+        assert!(app.admit_to_lobby(payload(Some("hunter2"))).is_ok());
+        assert_eq!(app.lobbies[0].opponent.as_deref(), Some("opp.os"));
+        assert_eq!(app.compose_remote_snapshot("opp.os").lobbies.len(), 1);
+        assert!(app.compose_remote_snapshot("stranger.os").lobbies.is_empty());
+    }
+
+    #[test]
+    fn ping_echoes_nonce_without_touching_state() {
+        let mut app = make_app();
+        let started = start_default_game(&mut app, 50, GameOptions::default());
+        app.install_game(started);
+        let before = app.game().unwrap().state_hash();
+        assert_eq!(app.opponent_node().as_deref(), Some("opp.os"));
+
+        {
+            let reply = app.handle_wire_message(WireMessage::Ping { nonce: 77 });
+            let mut reply = std::pin::pin!(reply);
+            let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+            assert!(matches!(
+                std::future::Future::poll(reply.as_mut(), &mut cx),
+                std::task::Poll::Ready(Ok(WireReply::Pong { nonce: 77 }))
+            ));
+        }
+        assert_eq!(app.game().unwrap().state_hash(), before);
+    }
+
+    #[test]
+    fn rearrange_swaps_feed_slots_unless_anchored() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 51, GameOptions::default());
+        let def = find_definition("n01").unwrap();
+        game.feed = (0..3)
+            .map(|slot| game.new_instance_from_def(def, Seat::Host, Location::Feed(FeedSlot { slot })))
+            .collect();
+        let ids: Vec<String> = game.feed.iter().map(|c| c.instance_id.clone()).collect();
+        let swap = ExploitEffect::SwapSlots(SwapParams { a: 0, b: 2 });
+
+        game.apply_exploit_effect(swap.clone(), &Seat::Host, None).unwrap();
+        let order: Vec<String> = game.feed.iter().map(|c| c.instance_id.clone()).collect();
+        assert_eq!(order, vec![ids[2].clone(), ids[1].clone(), ids[0].clone()]);
+        assert_eq!(game.feed[0].location, Location::Feed(FeedSlot { slot: 0 }));
+
+        game.feed[2].keywords.push(Keyword::Anchor);
+        game.apply_exploit_effect(swap, &Seat::Host, None).unwrap();
+        assert_eq!(game.feed.iter().map(|c| c.instance_id.clone()).collect::<Vec<_>>(), order);
+
+        let rearrange = game.new_instance_from_def(find_definition("m12").unwrap(), Seat::Host, Location::Hand);
+        let action = ExploitAction { card_id: rearrange.instance_id.clone(), target: None };
+        game.players[0].hand.push(rearrange);
+        game.feed.truncate(2);
+        assert_eq!(game.validate_exploit_target_seat(&Seat::Host, &action), Err(GameError::InvalidFeedSlot));
+    }
+
+    #[test]
+    fn finished_games_are_recorded_once_and_bounded() {
+        let mut app = make_app();
+        let started = start_default_game(&mut app, 52, GameOptions::default());
+        app.install_game(started);
+        app.record_game_over();
+        assert!(app.match_history.is_empty());
+
+        app.game_mut().unwrap().finish(GameOutcome::Win(Seat::Host));
+        app.record_game_over();
+        app.record_game_over();
+        assert_eq!(app.match_history.len(), 1);
+        let record = &app.match_history[0];
+        assert_eq!(record.game_seed, 52);
+        assert_eq!(record.winner, Some(Seat::Host));
+        assert_eq!(record.players, vec![our().node, "opp.os".to_string()]);
+
+        for seed in 100..100 + MAX_MATCH_HISTORY as u64 {
+            let mut game = start_default_game(&mut app, seed, GameOptions::default());
+            game.finish(GameOutcome::Draw);
+            app.install_game(game);
+            app.record_game_over();
+        }
+        assert_eq!(app.match_history.len(), MAX_MATCH_HISTORY);
+        assert_eq!(app.match_history[0].game_seed, 100);
+    }
+
+    #[test]
+    fn commitment_is_bound_to_turn_and_game() {
+        let plan = TurnPlan::default();
+        let base = commitment_for(&plan, "salt", 3, 9);
+        assert_ne!(base, commitment_for(&plan, "salt", 4, 9));
+        assert_ne!(base, commitment_for(&plan, "salt", 3, 10));
+
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 53, GameOptions::default());
+        let stale = commitment_for(&plan, "salt", game.turn, game.game_seed);
+        game.turn += 1;
+        game.record_commit(Seat::Host, stale).unwrap();
+        assert_eq!(
+            game.record_reveal(Seat::Host, plan, "salt".into()),
+            Err(GameError::CommitHashMismatch)
+        );
+    }
+
+    #[test]
+    fn draft_draw_waits_for_both_choices() {
+        let mut app = make_app();
+        let mut game = start_default_game(
+            &mut app,
+            54,
+            GameOptions {
+                rules: GameRules {
+                    draft_draw: true,
+                    ..GameRules::default()
+                },
+                ..GameOptions::default()
+            },
+        );
+        let hand_sizes: Vec<usize> = game.players.iter().map(|p| p.hand.len()).collect();
+        game.resolve_turn(TurnPlan::default(), TurnPlan::default()).unwrap();
+        assert_eq!(game.phase, Phase::Draft);
+        assert!(game.players.iter().all(|p| p.pending_draw.len() == 2));
+        assert_eq!(game.record_commit(Seat::Host, "h".into()), Err(GameError::DraftPending));
+        assert_eq!(
+            game.resolve_turn(TurnPlan::default(), TurnPlan::default()),
+            Err(GameError::DraftPending)
+        );
+
+        let keep = game.players[0].pending_draw[1].instance_id.clone();
+        let dropped = game.players[0].pending_draw[0].instance_id.clone();
+        assert_eq!(game.choose_draw(&keep), Ok(Seat::Host));
+        assert_eq!(game.phase, Phase::Draft);
+        assert_eq!(game.choose_draw(&keep), Err(GameError::UnknownCard(keep.clone())));
+        assert!(game.players[0].hand.iter().any(|c| c.instance_id == keep));
+        assert!(game.players[0].abyss.iter().any(|c| c.instance_id == dropped));
+        assert_eq!(game.players[0].hand.len(), hand_sizes[0] + 1);
+
+        let keep = game.players[1].pending_draw[0].instance_id.clone();
+        game.choose_draw(&keep).unwrap();
+        assert_eq!(game.phase, Phase::Commit);
+        assert_eq!(game.choose_draw(&keep), Err(GameError::NotDraftPhase));
+
+        let replayed = GameState::replay_from(54, &game.events).unwrap();
+        assert_eq!(replayed.state_hash(), game.state_hash());
+    }
+
+    #[test]
+    fn rewind_returns_enemy_meme_to_hand_with_reset_stats() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 56, GameOptions::default());
+        let def = find_definition("n01").unwrap();
+        let mut target = game.new_instance_from_def(def, Seat::Opponent, Location::Kitchen);
+        target.current_virality += 5;
+        target.protected_until_end = true;
+        target.frozen_turns = 2;
+        let target_id = target.instance_id.clone();
+        let rewind = game.new_instance_from_def(find_definition("t14").unwrap(), Seat::Host, Location::Hand);
+        game.players[0].hand.push(rewind.clone());
+        game.players[1].kitchen = vec![target];
+        game.players[1].hand.clear();
+        let action = ExploitAction {
+            card_id: rewind.instance_id.clone(),
+            target: Some(Target::Card(target_id.clone())),
+        };
+        assert!(game.validate_exploit_target_seat(&Seat::Host, &action).is_ok());
+
+        game.apply_exploit_effect(ExploitEffect::Bounce, &Seat::Host, Some(Target::Card(target_id.clone())))
+            .unwrap();
+        assert!(game.players[1].kitchen.is_empty());
+        let card = &game.players[1].hand[0];
+        assert_eq!(card.instance_id, target_id);
+        assert_eq!(card.location, Location::Hand);
+        assert_eq!(card.current_virality, card.base_virality);
+        assert!(!card.protected_until_end);
+        assert_eq!(card.frozen_turns, 0);
+    }
+
+    #[test]
+    fn full_feed_control_needs_a_full_feed_for_two_turns() {
+        let mut app = make_app();
+        let mut game = start_default_game(
+            &mut app,
+            57,
+            GameOptions {
+                win_condition: WinCondition::FullFeedControl,
+                ..GameOptions::default()
+            },
+        );
+        let def = find_definition("n01").unwrap();
+        let host_feed: Vec<CardInstance> = (0..game.feed_size)
+            .map(|slot| game.new_instance_from_def(def, Seat::Host, Location::Feed(FeedSlot { slot })))
+            .collect();
+
+        game.feed = host_feed[..1].to_vec();
+        game.update_domination_streak();
+        game.update_domination_streak();
+        assert_eq!(game.domination_streak, [0, 0]);
+        assert_eq!(game.check_win_condition(), None);
+
+        game.feed = host_feed;
+        game.update_domination_streak();
+        assert_eq!(game.check_win_condition(), None);
+        game.update_domination_streak();
+        assert_eq!(game.check_win_condition(), Some(Seat::Host));
+    }
+
+    #[test]
+    fn public_snapshot_leaves_out_hidden_zones() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 58, GameOptions::default());
+        game.players[1].score = 7;
+        app.install_game(game.clone());
+        let public = app.compose_snapshot().public();
+        let view = public.game.unwrap();
+        assert_eq!(view.turn, game.turn);
+        assert_eq!(view.feed, game.feed);
+        assert!(view.scores.contains(&(Seat::Opponent, 7)));
+        let json = serde_json::to_string(&app.compose_snapshot().public()).unwrap();
+        assert!(!json.contains("\"hand\"") && !json.contains("\"deck\""));
+
+        app.lobbies.push(joined_lobby());
+        app.lobbies.push(Lobby { id: "lobby-1".into(), private: true, ..joined_lobby() });
+        let public = app.compose_snapshot().public();
+        assert_eq!(public.lobbies.len(), 1);
+        assert_eq!(public.lobbies[0].id, "lobby-0");
+        let json = serde_json::to_string(&public).unwrap();
+        assert!(!json.contains("host_deck") && !json.contains("opponent_deck"));
+
+        let remote = app.compose_remote_snapshot("opp.os");
+        assert!(remote.lobbies.iter().all(|l| l.host_deck.is_empty()));
+        assert!(remote.lobbies.iter().any(|l| !l.opponent_deck.is_empty()));
+        let stranger = app.compose_remote_snapshot("stranger.os");
+        assert!(stranger.lobbies.iter().all(|l| l.host_deck.is_empty() && l.opponent_deck.is_empty()));
+    }
+
+    #[test]
+    fn cactus_thorns_hit_back_at_the_attacker() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 59, GameOptions::default());
+        let cactus = game.new_instance_from_def(find_definition("n12").unwrap(), Seat::Host, Location::Kitchen);
+        let cactus_id = cactus.instance_id.clone();
+        let attacker = game.new_instance_from_def(find_definition("n01").unwrap(), Seat::Opponent, Location::Kitchen);
+        let attacker_virality = attacker.current_virality;
+        game.players[0].kitchen = vec![cactus];
+        game.players[1].kitchen = vec![attacker];
+
+        let damage = ExploitEffect::Damage(DamageParams {
+            amount: 3,
+            target: Target::EnemyKitchen,
+        });
+        game.apply_exploit_effect(damage, &Seat::Opponent, Some(Target::Card(cactus_id)))
+            .unwrap();
+        assert_eq!(game.players[0].kitchen[0].current_virality, game.players[0].kitchen[0].base_virality - 3);
+        assert_eq!(game.players[1].kitchen[0].current_virality, attacker_virality - 2);
+    }
+
+    #[test]
+    fn discard_phase_lets_the_player_pick_what_to_keep() {
+        let mut app = make_app();
+        let options = GameOptions {
+            rules: GameRules {
+                discard_phase: true,
+                ..GameRules::default()
+            },
+            ..GameOptions::default()
+        };
+        let mut game = start_default_game(&mut app, 61, options);
+        let def = find_definition("n01").unwrap();
+        let hand: Vec<CardInstance> = (0..MAX_HAND_SIZE)
+            .map(|_| game.new_instance_from_def(def, Seat::Host, Location::Hand))
+            .collect();
+        game.players[0].hand = hand.clone();
+        game.players[1].hand.clear();
+        let drawn = game.players[0].deck.last().unwrap().instance_id.clone();
+        game.resolve_turn(TurnPlan::default(), TurnPlan::default()).unwrap();
+        assert_eq!(game.phase, Phase::Discard);
+        assert_eq!(game.players[0].hand.len(), MAX_HAND_SIZE + 1);
+        assert_eq!(
+            game.record_commit(Seat::Host, "hash".into()),
+            Err(GameError::DiscardPending)
+        );
+
+        let mut keep: Vec<String> = hand[1..].iter().map(|c| c.instance_id.clone()).collect();
+        keep.push(drawn.clone());
+        assert_eq!(game.discard_to_hand_size(&keep[1..]), Err(GameError::InvalidDiscard));
+        assert_eq!(game.discard_to_hand_size(&keep), Ok(Seat::Host));
+        assert_eq!(game.phase, Phase::Commit);
+        assert!(game.players[0].hand.iter().any(|c| c.instance_id == drawn));
+        assert_eq!(game.players[0].abyss.last().unwrap().instance_id, hand[0].instance_id);
+    }
+
+    #[test]
+    fn quartermaster_aura_discounts_hand_cards() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 62, GameOptions::default());
+        let card = game.new_instance_from_def(find_definition("c03").unwrap(), Seat::Host, Location::Hand);
+        assert_eq!(card.cost, 3);
+        let plan = TurnPlan {
+            plays_to_kitchen: vec![card.instance_id.clone()],
+            ..TurnPlan::default()
+        };
+        game.players[0].hand = vec![card];
+        game.players[0].kitchen.clear();
+        game.players[0].mana = 2;
+        assert!(matches!(game.apply_turn_for_seat(Seat::Host, plan.clone()), Err(GameError::InsufficientMana(_))));
+
+        let quartermaster = game.new_instance_from_def(find_definition("c11").unwrap(), Seat::Host, Location::Kitchen);
+        game.players[0].kitchen.push(quartermaster);
+        assert_eq!(game.players[0].effective_discount(), 1);
+        game.apply_turn_for_seat(Seat::Host, plan).unwrap();
+        assert_eq!(game.players[0].mana, 0);
+    }
+
+    #[test]
+    fn catalog_validation_reports_every_broken_card() {
+        assert_eq!(validate_catalog(&build_catalog()), Ok(()));
+        let broken: Vec<CardDefinition> = serde_json::from_str(
+            r#"[
+                {"id": "x01", "name": "A", "cost": 1, "description": "", "class": {"Meme": {
+                    "base_virality": -1, "cook_rate": 1, "yield_rate": 1,
+                    "keywords": [{"Gatekeeper": {"max_cost": 0}}],
+                    "abilities": [{"trigger": "OnPost", "effect": {"Spawn": {"variant_id": "zz", "count": 1, "location": "Kitchen"}}}],
+                    "volatile": null, "initial_freeze": null}}},
+                {"id": "x01", "name": "B", "cost": 1, "description": "", "class": {"Exploit": "Protect"}}
+            ]"#,
+        )
+        .unwrap();
+        let errors = validate_catalog(&broken).unwrap_err();
+        assert_eq!(errors.len(), 4, "{errors:?}");
+        assert!(errors.iter().any(|e| e.contains("duplicate id")));
+        assert!(errors.iter().any(|e| e.contains("unknown card zz")));
+    }
+
+    #[test]
+    fn ratio_chains_through_the_top_enemy_memes() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 64, GameOptions::default());
+        let def = find_definition("n01").unwrap();
+        let mut kitchen = vec![];
+        for virality in [5, 12, 7, 20, 9] {
+            let mut card = game.new_instance_from_def(def, Seat::Opponent, Location::Kitchen);
+            card.current_virality = virality;
+            kitchen.push(card);
+        }
+        kitchen[3].keywords.push(Keyword::Stealth);
+        game.players[1].kitchen = kitchen;
+        let Some(CardKind::Exploit(effect)) = find_definition("t15").map(|d| d.class.clone()) else {
+            panic!("t15 should be an exploit");
+        };
+        game.apply_exploit_effect(effect, &Seat::Host, None).unwrap();
+        let virality: Vec<i32> = game.players[1].kitchen.iter().map(|c| c.current_virality).collect();
+        assert_eq!(virality, vec![5, 8, 5, 20, 6]);
+    }
+
+    #[test]
+    fn turn_limit_ends_the_game_on_score() {
+        let mut app = make_app();
+        let options = GameOptions {
+            rules: GameRules {
+                max_turns: Some(3),
+                ..GameRules::default()
+            },
+            ..GameOptions::default()
+        };
+        let mut game = start_default_game(&mut app, 65, options);
+        game.players[1].score = 4;
+        for _ in 0..2 {
+            game.resolve_turn(TurnPlan::default(), TurnPlan::default()).unwrap();
+            assert_ne!(game.phase, Phase::GameOver);
+        }
+        game.resolve_turn(TurnPlan::default(), TurnPlan::default()).unwrap();
+        assert_eq!(game.phase, Phase::GameOver);
+        assert_eq!(game.outcome, Some(GameOutcome::TurnLimit(Some(Seat::Opponent))));
+        assert_eq!(game.winner, Some(Seat::Opponent));
+    }
+
+    #[test]
+    fn osint_exposes_the_opponent_hand_to_the_caster_only() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 66, GameOptions::default());
+        let hand_ids: Vec<String> = game.players[1].hand.iter().map(|c| c.instance_id.clone()).collect();
+        let Some(CardKind::Exploit(effect)) = find_definition("d14").map(|d| d.class.clone()) else {
+            panic!("d14 should be an exploit");
+        };
+        game.apply_exploit_effect(effect, &Seat::Host, None).unwrap();
+        assert_eq!(game.players[0].exposed_hand, Some(hand_ids.clone()));
+
+        let caster = game.redacted_for(&Seat::Host);
+        assert_eq!(caster.players[1].hand, game.players[1].hand);
+        let target = game.redacted_for(&Seat::Opponent);
+        assert_eq!(target.players[0].exposed_hand, None);
+        let spectator = game.client_view("someone-else.os");
+        assert!(spectator.players[1].hand.iter().all(|c| c.variant_id == "hidden"));
+
+        game.resolve_turn(TurnPlan::default(), TurnPlan::default()).unwrap();
+        assert_eq!(game.players[0].exposed_hand, None);
+    }
+
+    #[test]
+    fn failed_resolution_leaves_the_game_untouched() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 67, GameOptions::default());
+        let meme = game.players[0]
+            .hand
+            .iter()
+            .find(|c| matches!(c.class, CardKind::Meme(_)) && c.cost <= game.players[0].mana)
+            .unwrap()
+            .instance_id
+            .clone();
+        let host = TurnPlan {
+            plays_to_kitchen: vec![meme],
+            ..TurnPlan::default()
+        };
+        let opponent = TurnPlan {
+            exploits: vec![ExploitAction {
+                card_id: "not-in-hand".into(),
+                target: None,
+            }],
+            ..TurnPlan::default()
+        };
+        let before = serde_json::to_vec(&game).unwrap();
+        assert!(game.resolve_turn(host.clone(), opponent.clone()).is_err());
+        assert_eq!(serde_json::to_vec(&game).unwrap(), before);
+
+        // Revealed one at a time, the failing reveal is undone along with the resolution.
+        for (seat, plan, salt) in [(Seat::Host, &host, "a"), (Seat::Opponent, &opponent, "b")] {
+            let hash = commitment_for(plan, salt, game.turn, game.game_seed);
+            game.record_commit(seat, hash).unwrap();
+        }
+        game.record_reveal(Seat::Host, host, "a".into()).unwrap();
+        let revealed = serde_json::to_vec(&game).unwrap();
+        assert!(game.record_reveal(Seat::Opponent, opponent, "b".into()).is_err());
+        assert_eq!(serde_json::to_vec(&game).unwrap(), revealed);
+        assert_eq!(game.phase, Phase::Reveal);
+    }
+
+    #[test]
+    fn best_of_three_series_rematches_until_clinched() {
+        let mut app = make_app();
+        app.lobbies.push(Lobby {
+            host: our().node,
+            series: Some(Series::new(3)),
+            ..joined_lobby()
+        });
+        let first = app.launch_lobby_game(0, None).unwrap();
+        let finish = |app: &mut MemeWarsState, winner: Seat| {
+            app.game_mut().unwrap().finish(GameOutcome::Win(winner));
+            app.record_game_over();
+        };
+
+        finish(&mut app, Seat::Host);
+        assert_eq!(app.lobbies[0].series.as_ref().unwrap().wins, (1, 0));
+        assert_ne!(app.game().unwrap().phase, Phase::GameOver);
+        assert_eq!(app.game().unwrap().game_seed, rng::series_seed(first.game_seed));
+        assert_eq!(app.lobbies[0].rematch_count, 1);
+
+        finish(&mut app, Seat::Opponent);
+        finish(&mut app, Seat::Host);
+        let series = app.lobbies[0].series.clone().unwrap();
+        assert_eq!(series.wins, (2, 1));
+        assert_eq!(series.clinched_by(), Some(Seat::Host));
+        assert_eq!(app.game().unwrap().phase, Phase::GameOver);
+        assert_eq!(app.match_history.len(), 3);
+        assert!(app.compose_snapshot().lobbies.is_empty());
+    }
+
+    #[test]
+    fn invest_trades_score_for_virality() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 69, GameOptions::default());
+        let card = game.new_instance_from_def(find_definition("n01").unwrap(), Seat::Host, Location::Kitchen);
+        let card_id = card.instance_id.clone();
+        let virality = card.current_virality;
+        let invest = game.new_instance_from_def(find_definition("m13").unwrap(), Seat::Host, Location::Hand);
+        game.players[0].kitchen = vec![card];
+        game.players[0].hand.push(invest.clone());
+        let action = ExploitAction {
+            card_id: invest.instance_id.clone(),
+            target: Some(Target::Card(card_id.clone())),
+        };
+        game.players[0].score = 5;
+        assert!(matches!(
+            game.validate_exploit_target_seat(&Seat::Host, &action),
+            Err(GameError::InsufficientScore(_))
+        ));
+
+        game.players[0].score = 20;
+        assert!(game.validate_exploit_target_seat(&Seat::Host, &action).is_ok());
+        let effect = ExploitEffect::Invest(InvestParams {
+            cost_score: 10,
+            virality: 8,
+        });
+        game.apply_exploit_effect(effect, &Seat::Host, Some(Target::Card(card_id)))
+            .unwrap();
+        assert_eq!(game.players[0].score, 10);
+        assert_eq!(game.players[0].kitchen[0].current_virality, virality + 8);
+    }
+
+    #[test]
+    fn analyze_default_deck() {
+        let app = make_app();
+        let analysis = analyze_deck(&app.catalog, &default_deck()).unwrap();
+        assert_eq!(analysis.meme_count, 4);
+        assert_eq!(analysis.exploit_count, 8);
+        assert_eq!(analysis.total_cost, 26);
+        assert!((analysis.avg_cost - 26.0 / 12.0).abs() < f32::EPSILON);
+        assert_eq!(analysis.curve, vec![(1, 2), (2, 7), (3, 2), (4, 1)]);
+        assert!(analysis.keyword_histogram.is_empty());
+
+        let shielded = analyze_deck(&app.catalog, &["c05".to_string(), "c05".to_string()]).unwrap();
+        assert_eq!(shielded.keyword_histogram.get("Shielded"), Some(&2));
+
+        let err = analyze_deck(&app.catalog, &["n01".to_string(), "zz99".to_string()]).unwrap_err();
+        assert_eq!(err, GameError::UnknownCard("zz99".into()));
+    }
+
+    #[test]
+    fn lobby_placeholder_starts_in_place() {
+        let mut app = make_app();
+        app.seed_source = Some(31);
+        let lobby = joined_lobby();
+        let mut placeholder = GameState::lobby(String::new(), &lobby.game_options());
+        placeholder.id = lobby.game_id();
+        app.install_game(placeholder);
+        app.lobbies.push(lobby);
+        let placeholder = app.game().unwrap();
+        assert_eq!(placeholder.phase, Phase::Lobby);
+        assert!(placeholder.feed.is_empty());
+        assert!(placeholder.players.iter().all(|p| p.hand.is_empty() && p.deck.is_empty()));
+        assert!(app.opponent_node().is_none());
+        assert!(app.compose_remote_snapshot("opp.os").game.is_none());
+
+        let game = app.launch_lobby_game(0, None).unwrap();
+        assert_eq!(game.phase, Phase::Commit);
+        assert_eq!(game.players[1].node_id, "opp.os");
+        let mut fresh_app = make_app();
+        let fresh = start_default_game(&mut fresh_app, 31, joined_lobby().game_options());
+        assert_eq!(game.state_hash(), fresh.state_hash());
+        assert_eq!(game.events, fresh.events);
+
+        let mut started = game.clone();
+        let err = started
+            .start(&app.catalog, &mut app.next_instance, 32, default_deck(), default_deck(), GameOptions::default())
+            .unwrap_err();
+        assert_eq!(err, GameError::NotLobbyPhase);
+    }
+
+    #[test]
+    fn discard_removes_random_cards_from_opponent_hand() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 33, GameOptions::default());
+        let hand_before = game.players[1].hand.len();
+        let abyss_before = game.players[1].abyss.len();
+        let events_before = game.events.len();
+        game.apply_exploit_effect(ExploitEffect::Discard(2), &Seat::Host, None)
+            .unwrap();
+        assert_eq!(game.players[1].hand.len(), hand_before - 2);
+        assert_eq!(game.players[1].abyss.len(), abyss_before + 2);
+        assert!(game.players[1].abyss.iter().all(|c| c.location == Location::Abyss));
+        let rolls: Vec<_> = game.events[events_before..]
+            .iter()
+            .filter_map(|e| match &e.event {
+                game::GameEventKind::Random(random) => Some(random),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(rolls.len(), 2);
+        assert!(rolls
+            .iter()
+            .all(|r| r.kind == rng::RandomEventKind::Discard(Seat::Opponent)));
+        assert_eq!(rolls[0].bound, hand_before as u64);
+        assert!(game.rng.verify(game.game_seed));
+
+        // Only what's in hand can go.
+        game.players[1].hand.truncate(1);
+        game.apply_exploit_effect(ExploitEffect::Discard(2), &Seat::Host, None)
+            .unwrap();
+        assert!(game.players[1].hand.is_empty());
+        assert_eq!(find_definition("d15").unwrap().class, CardKind::Exploit(ExploitEffect::Discard(2)));
+    }
+
+    #[test]
+    fn duplicate_commit_keeps_reveal_and_conflicting_commit_errors() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 34, GameOptions::default());
+        let plan = TurnPlan::default();
+        let hash = commitment_for(&plan, "salt", game.turn, game.game_seed);
+        game.record_commit(Seat::Host, hash.clone()).unwrap();
+        game.record_reveal(Seat::Host, plan.clone(), "salt".into()).unwrap();
+
+        game.record_commit(Seat::Host, hash.clone()).unwrap();
+        let commit = game.players[0].commit.clone().unwrap();
+        assert_eq!(commit.hash, hash);
+        assert_eq!(commit.salt.as_deref(), Some("salt"));
+        assert_eq!(commit.revealed, Some(plan.clone()));
+
+        let other = commitment_for(&plan, "other", game.turn, game.game_seed);
+        assert_eq!(game.record_commit(Seat::Host, other), Err(GameError::CommitConflict));
+        assert_eq!(game.players[0].commit, Some(commit));
+    }
+
+    #[test]
+    fn opening_cost_cap_cycles_expensive_hands() {
+        let deck: Vec<String> = [
+            "n05", "m01", "d10", "n01", // Memes
+            "d01", "t09", "c07", "t03", "t11", "t13", "t15", "m06",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let options = GameOptions {
+            rules: GameRules {
+                max_opening_cost: Some(5),
+                ..GameRules::default()
+            },
+            ..GameOptions::default()
+        };
+        let mut cycled = 0;
+        for seed in 0..8 {
+            let mut app = make_app();
+            let game = build_game(&app.catalog, &mut app.next_instance, seed, deck.clone(), deck.clone(), "opp.os".into(), options.clone())
+                .unwrap();
+            let card = |id: &String| {
+                game.players
+                    .iter()
+                    .flat_map(|p| p.deck.iter().chain(p.hand.iter()))
+                    .find(|c| &c.instance_id == id)
+                    .unwrap()
+            };
+            for player in &game.players {
+                let opening: u32 = player.hand.iter().map(|c| c.cost as u32).sum();
+                assert!(opening <= 5, "seed {seed} opened at cost {opening}");
+                assert!(player.hand.iter().any(|c| matches!(c.class, CardKind::Meme(_))));
+            }
+            for event in &game.events {
+                if let game::GameEventKind::StartingHand(start) = &event.event {
+                    // Every rejected hand broke one of the two rules.
+                    for cycle in &start.cycles {
+                        let cards: Vec<_> = cycle.card_ids.iter().map(card).collect();
+                        let cost: u32 = cards.iter().map(|c| c.cost as u32).sum();
+                        let has_meme = cards.iter().any(|c| matches!(c.class, CardKind::Meme(_)));
+                        assert!(cost > 5 || !has_meme);
+                    }
+                    cycled += start.cycles.len();
+                }
+            }
+        }
+        assert!(cycled > 0);
+    }
+
+    #[test]
+    fn outcome_tracks_fold_and_draw() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 35, GameOptions::default());
+        assert_eq!(game.outcome, None);
+        game.call_based(Seat::Host).unwrap();
+        game.fold_based(Seat::Opponent).unwrap();
+        assert_eq!(game.phase, Phase::GameOver);
+        assert_eq!(game.outcome, Some(GameOutcome::Folded(Seat::Opponent)));
+        assert_eq!(game.winner, Some(Seat::Host));
+
+        // Neither deck fits the format, so nobody can win.
+        let game = build_game(&app.catalog, &mut app.next_instance, 36, vec!["n01".into()], vec!["n01".into()], "opp.os".into(), GameOptions::default())
+            .unwrap();
+        assert_eq!(game.outcome, Some(GameOutcome::Draw));
+        assert_eq!(game.winner, None);
+    }
+
+    #[test]
+    fn wire_batch_applies_all_or_nothing() {
+        let mut app = make_app();
+        let started = start_default_game(&mut app, 37, GameOptions::default());
+        app.install_game(started);
+        let plan = TurnPlan::default();
+        let (turn, seed) = {
+            let game = app.game().unwrap();
+            (game.turn, game.game_seed)
+        };
+        let commit = WireMessage::Commit(WireCommit {
+            seat: Seat::Host,
+            hash: commitment_for(&plan, "salt", turn, seed),
+            turn,
+        });
+        let reveal = |salt: &str| {
+            WireMessage::Reveal(WireReveal {
+                seat: Seat::Host,
+                plan: plan.clone(),
+                salt: salt.into(),
+                turn,
+            })
+        };
+
+        // The reveal fails, so the commit before it is undone too.
+        let err = app
+            .apply_wire_batch(vec![commit.clone(), reveal("wrong")])
+            .unwrap_err();
+        assert_eq!(err, GameError::CommitHashMismatch);
+        assert!(app.game().unwrap().players[0].commit.is_none());
+
+        let nested = vec![WireMessage::Batch(vec![commit.clone()])];
+        assert_eq!(app.apply_wire_batch(nested), Err(GameError::NestedBatch));
+
+        let reply = app.apply_wire_batch(vec![commit, reveal("salt")]).unwrap();
+        assert!(matches!(reply, WireReply::Snapshot(_)));
+        let recorded = app.game().unwrap().players[0].commit.clone().unwrap();
+        assert_eq!(recorded.revealed, Some(plan));
+    }
+
+    #[test]
+    fn reorder_rearranges_whole_feed_unless_pinned() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 38, GameOptions::default());
+        let slot = || Location::Feed(FeedSlot { slot: 0 });
+        let a = game.new_instance_from_def(find_definition("n01").unwrap(), Seat::Opponent, slot());
+        let b = game.new_instance_from_def(find_definition("n02").unwrap(), Seat::Host, slot());
+        let c = game.new_instance_from_def(find_definition("n03").unwrap(), Seat::Opponent, slot());
+        let ids: Vec<String> = [&a, &b, &c].iter().map(|c| c.instance_id.clone()).collect();
+        game.feed = vec![a, b, c];
+        game.reindex_feed();
+        let tweak = game.new_instance_from_def(find_definition("t16").unwrap(), Seat::Host, Location::Hand);
+        let action = |order: Vec<usize>| ExploitAction {
+            card_id: tweak.instance_id.clone(),
+            target: Some(Target::FeedOrder(order)),
+        };
+        game.players[0].hand.push(tweak.clone());
+
+        assert_eq!(game.validate_exploit_target_seat(&Seat::Host, &action(vec![0, 0, 1])), Err(GameError::InvalidTarget));
+        assert_eq!(game.validate_exploit_target_seat(&Seat::Host, &action(vec![1, 0])), Err(GameError::InvalidTarget));
+        assert!(game.validate_exploit_target_seat(&Seat::Host, &action(vec![2, 0, 1])).is_ok());
+
+        game.players[1].pinned_cards.push(ids[0].clone());
+        assert_eq!(game.validate_exploit_target_seat(&Seat::Host, &action(vec![2, 0, 1])), Err(GameError::FeedSlotFixed));
+        // Moving everything but the pinned post is fine.
+        assert!(game.validate_exploit_target_seat(&Seat::Host, &action(vec![0, 2, 1])).is_ok());
+        game.players[1].pinned_cards.clear();
+
+        game.apply_exploit_effect(ExploitEffect::Reorder, &Seat::Host, action(vec![2, 0, 1]).target)
+            .unwrap();
+        let feed_ids = |game: &GameState| -> Vec<String> { game.feed.iter().map(|c| c.instance_id.clone()).collect() };
+        let order = feed_ids(&game);
+        assert_eq!(order, vec![ids[2].clone(), ids[0].clone(), ids[1].clone()]);
+        assert_eq!(game.feed[2].location, Location::Feed(FeedSlot { slot: 2 }));
+
+        // An order that no longer fits the feed leaves it alone.
+        game.apply_exploit_effect(ExploitEffect::Reorder, &Seat::Host, action(vec![1, 0]).target)
+            .unwrap();
+        assert_eq!(feed_ids(&game), order);
+    }
+
+    #[test]
+    fn overload_costs_next_turn_mana_then_recovers() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 39, GameOptions::default());
+        for _ in 0..2 {
+            let take = game.new_instance_from_def(find_definition("n13").unwrap(), Seat::Host, Location::Hand);
+            game.players[0].hand.push(take.clone());
+            game.play_to_kitchen(&Seat::Host, &take.instance_id).unwrap();
         }
+        assert_eq!(game.players[0].overload_next, 4);
+
+        let rules = game.rules.clone();
+        let budget = game.action_budget.clone();
+        let host = &mut game.players[0];
+        host.max_mana = 6;
+        host.reset_for_new_turn(&rules, &budget);
+        assert_eq!(host.max_mana, 7);
+        assert_eq!(host.mana, 3);
+        assert_eq!(host.overload_next, 0);
+
+        host.reset_for_new_turn(&rules, &budget);
+        assert_eq!(host.mana, host.max_mana);
     }
 
-    async fn send_wire_message(
-        &self,
-        node: &str,
-        message: WireMessage,
-    ) -> Result<WireReply, String> {
-        let address = Address {
-            node: node.to_string(),
-            process: process_id(),
+    #[test]
+    fn heal_restores_virality_up_to_base() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 40, GameOptions::default());
+        let mut meme = game.new_instance_from_def(find_definition("n03").unwrap(), Seat::Host, Location::Kitchen);
+        let id = meme.instance_id.clone();
+        meme.current_virality = meme.base_virality - 6;
+        game.players[0].kitchen.push(meme);
+        let heal = || Some(Target::Card(id.clone()));
+        let virality = |game: &GameState| game.players[0].kitchen.last().unwrap().current_virality;
+
+        game.apply_exploit_effect(ExploitEffect::Heal(4), &Seat::Host, heal()).unwrap();
+        assert_eq!(virality(&game), 8 - 2);
+        game.apply_exploit_effect(ExploitEffect::Heal(4), &Seat::Host, heal()).unwrap();
+        assert_eq!(virality(&game), 8);
+
+        // Already boosted past base: a heal leaves it alone.
+        game.players[0].kitchen.last_mut().unwrap().current_virality = 11;
+        game.apply_exploit_effect(ExploitEffect::Heal(4), &Seat::Host, heal()).unwrap();
+        assert_eq!(virality(&game), 11);
+        assert_eq!(find_definition("c12").unwrap().class, CardKind::Exploit(ExploitEffect::Heal(4)));
+    }
+
+    #[test]
+    fn projected_yield_scores_each_feed_slot() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 41, GameOptions::default());
+        let mut first = game.new_instance_from_def(find_definition("n03").unwrap(), Seat::Host, Location::Feed(FeedSlot { slot: 0 }));
+        let mut second = game.new_instance_from_def(find_definition("n03").unwrap(), Seat::Opponent, Location::Feed(FeedSlot { slot: 1 }));
+        first.yield_rate = 1;
+        second.yield_rate = 2;
+        let (first_id, second_id) = (first.instance_id.clone(), second.instance_id.clone());
+        game.feed = vec![first, second];
+
+        let projected = game.projected_yield();
+        assert_eq!(projected, vec![(first_id.clone(), 10), (second_id.clone(), 30)]);
+        assert_eq!(game.feed.len(), 2);
+        assert_eq!(game.players[0].score, 0);
+        game.stakes = 2;
+        assert_eq!(game.projected_yield(), vec![(first_id, 20), (second_id, 60)]);
+    }
+
+    #[test]
+    fn seed_handshake_agrees_on_the_same_seed() {
+        let mut host = make_app();
+        host.lobbies.push(joined_lobby());
+        host.seed_source = Some(900);
+        let mut opponent = make_app();
+        opponent.discovered_lobbies.push(Lobby { opponent: Some(our().node), ..joined_lobby() });
+        opponent.seed_source = Some(7_000);
+
+        let (commit, reveal) = host.open_seed_handshake("lobby-0", "opp.os");
+        let their_value = opponent.accept_seed_commit("host.os", commit).unwrap();
+        let agreed = opponent.accept_seed_reveal("host.os", reveal.clone()).unwrap();
+        assert_eq!(host.finish_seed_handshake(their_value).unwrap(), agreed);
+        assert_eq!(agreed, rng::shared_seed(900, 7_000));
+
+        // The host deals from the agreed seed and the opponent accepts it.
+        let game = host.launch_lobby_game(0, None).unwrap();
+        assert_eq!(game.game_seed, agreed);
+        assert!(host.seed_handshake.is_none());
+        opponent.check_agreed_seed("host.os", &game).unwrap();
+
+        // A reveal that doesn't match the commitment is refused.
+        let (commit, mut reveal) = host.open_seed_handshake("lobby-0", "opp.os");
+        opponent.accept_seed_commit("host.os", commit).unwrap();
+        reveal.value += 1;
+        assert_eq!(
+            opponent.accept_seed_reveal("host.os", reveal),
+            Err(GameError::SeedCommitmentMismatch)
+        );
+    }
+
+    #[test]
+    fn agreed_seed_rejects_a_game_dealt_from_another_seed() {
+        let mut opponent = make_app();
+        opponent.discovered_lobbies.push(Lobby { opponent: Some(our().node), ..joined_lobby() });
+        let mut host = make_app();
+        let (commit, reveal) = host.open_seed_handshake("lobby-0", "opp.os");
+        // Only the lobby's other seat may open a handshake.
+        assert_eq!(
+            opponent.accept_seed_commit("stranger.os", commit.clone()),
+            Err(GameError::NotSeated("stranger.os".into()))
+        );
+        opponent.accept_seed_commit("host.os", commit).unwrap();
+        opponent.accept_seed_reveal("host.os", reveal).unwrap();
+
+        let game = start_default_game(&mut host, 3, GameOptions::default());
+        assert_eq!(opponent.check_agreed_seed("host.os", &game), Err(GameError::SeedMismatch));
+        // Without a handshake there is no seed a new game could be checked against.
+        assert_eq!(opponent.check_agreed_seed("host.os", &game), Err(GameError::NoAgreedSeed));
+
+        // The next game of a series follows from the one we hold; any other seed is refused.
+        let mut finished = start_default_game(&mut host, 4, GameOptions::default());
+        finished.id = "host.os/lobby-0".into();
+        opponent.install_game(finished.clone());
+        let mut next = start_default_game(&mut host, rng::series_seed(4), GameOptions::default());
+        next.id = finished.id.clone();
+        opponent.check_agreed_seed("host.os", &next).unwrap();
+        next.game_seed = 5;
+        assert_eq!(opponent.check_agreed_seed("host.os", &next), Err(GameError::NoAgreedSeed));
+    }
+
+    #[test]
+    fn purge_strips_every_keyword_and_the_shield() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 42, GameOptions::default());
+        let mut target = game.new_instance_from_def(find_definition("n03").unwrap(), Seat::Opponent, Location::Kitchen);
+        let id = target.instance_id.clone();
+        target.keywords = vec![
+            Keyword::Shielded(ShieldedKeyword { amount: 3 }),
+            Keyword::Taunt,
+            Keyword::Anchor,
+            Keyword::Thorns(ThornsKeyword { amount: 2 }),
+        ];
+        target.abilities = vec![Ability {
+            trigger: AbilityTrigger::OnPost,
+            effect: AbilityEffect::BuffSelf(1),
+        }];
+        target.shield = 3;
+        game.players[1].kitchen.push(target);
+
+        let deplatform = game.new_instance_from_def(find_definition("m14").unwrap(), Seat::Host, Location::Hand);
+        let action = ExploitAction {
+            card_id: deplatform.instance_id.clone(),
+            target: Some(Target::Card(id.clone())),
         };
-        let envelope = serde_json::json!({ "HandleWireMessage": message });
-        let body = serde_json::to_vec(&envelope).map_err(|e| e.to_string())?;
-        let request = Request::to(address).expects_response(30).body(body);
-        let response: Result<WireReply, String> = send(request).await.map_err(|e| e.to_string())?;
-        response
+        game.players[0].hand.push(deplatform);
+        game.validate_exploit_target_seat(&Seat::Host, &action).unwrap();
+        game.apply_exploit_effect(ExploitEffect::Purge, &Seat::Host, action.target).unwrap();
+        let purged = game.players[1].kitchen.iter().find(|c| c.instance_id == id).unwrap();
+        assert!(purged.keywords.is_empty());
+        assert!(purged.abilities.is_empty());
+        assert_eq!(purged.shield, 0);
     }
 
-    fn validate_state_hash(&self, remote: &StateHash) -> Result<(), String> {
-        let game = self.game.as_ref().ok_or("no active game")?;
-        validate_state_hash(game, remote)
+    #[test]
+    fn plan_history_keeps_both_plans_for_each_resolved_turn() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 43, GameOptions::default());
+        let based = TurnPlan { based: true, ..TurnPlan::default() };
+        game.resolve_turn(TurnPlan::default(), based.clone()).unwrap();
+        game.resolve_turn(based.clone(), TurnPlan::default()).unwrap();
+
+        let order: Vec<(u32, Seat)> = game
+            .plan_history
+            .iter()
+            .map(|(turn, seat, _)| (*turn, seat.clone()))
+            .collect();
+        assert_eq!(
+            order,
+            vec![(0, Seat::Host), (0, Seat::Opponent), (1, Seat::Host), (1, Seat::Opponent)]
+        );
+        assert_eq!(game.plan_history[1].2, based);
+        assert_eq!(game.plan_history[2].2, based);
+        assert_eq!(game.plan_history[3].2, TurnPlan::default());
     }
-}
 
-async fn commit_turn_with_plan(
-    app: &mut MemeWarsState,
-    seat: Seat,
-    plan: TurnPlan,
-    salt: String,
-    turn: u32,
-) -> Result<GameSnapshot, String> {
-    let hash = commitment_for(&plan, &salt);
-    app.commit_turn((seat, hash, turn)).await
-}
+    #[test]
+    fn cancelling_a_based_call_restores_stakes_and_phase() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 44, GameOptions::default());
+        let (stakes, phase) = (game.stakes, game.phase.clone());
+        assert_eq!(game.cancel_based(Seat::Host), Err(GameError::NoPendingStakes));
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use catalog::find_definition;
-    use game::split_players_mut;
+        game.call_based(Seat::Host).unwrap();
+        assert_eq!(game.cancel_based(Seat::Opponent), Err(GameError::NotStakeCaller));
+        game.cancel_based(Seat::Host).unwrap();
+        assert_eq!(game.stakes, stakes);
+        assert_eq!(game.phase, phase);
+        assert!(game.pending_stakes.is_none());
+    }
 
-    fn make_app() -> MemeWarsState {
-        let mut app = MemeWarsState::default();
-        app.catalog = build_catalog();
-        app.next_instance = 1;
-        app
+    #[test]
+    fn find_cards_applies_every_filter() {
+        let mut app = make_app();
+        let all = find_cards(&app.catalog, &CardFilter::default());
+        assert_eq!(all.len(), app.catalog.len());
+
+        let filter = CardFilter {
+            kind: Some(catalog::CardCategory::Meme),
+            max_cost: Some(3),
+            keyword: Some("Taunt".into()),
+        };
+        let found = find_cards(&app.catalog, &filter);
+        assert!(!found.is_empty());
+        for def in found.iter() {
+            assert!(def.cost <= 3);
+            let CardKind::Meme(meme) = &def.class else { panic!("{} is not a meme", def.id) };
+            assert!(meme.keywords.contains(&Keyword::Taunt));
+        }
+        let exploits = CardFilter { kind: Some(catalog::CardCategory::Exploit), ..CardFilter::default() };
+        assert!(find_cards(&app.catalog, &exploits).iter().all(|d| matches!(d.class, CardKind::Exploit(_))));
+
+        assert!(app.compose_snapshot_with(false).catalog.is_empty());
+        assert_eq!(app.compose_snapshot().catalog.len(), app.catalog.len());
+
+        // Broadcasts carry the catalog until a client has it.
+        assert_eq!(app.compose_broadcast_snapshot().catalog.len(), app.catalog.len());
+        assert_eq!(ready(app.get_catalog()).unwrap().len(), app.catalog.len());
+        assert!(app.compose_broadcast_snapshot().catalog.is_empty());
     }
 
     #[test]
-    fn commitment_changes_with_salt() {
-        let plan = TurnPlan::default();
-        let a = commitment_for(&plan, "a");
-        let b = commitment_for(&plan, "b");
-        assert_ne!(a, b);
+    fn combo_memes_posted_together_buff_each_other() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 45, GameOptions::default());
+        game.turn = 1;
+        let post = |game: &mut GameState, ids: &[&str]| -> Vec<i32> {
+            game.feed.clear();
+            let mut posts = vec![];
+            for id in ids {
+                let mut card = game.new_instance_from_def(find_definition(id).unwrap(), Seat::Host, Location::Kitchen);
+                card.played_turn = 0;
+                posts.push(PostAction { card_id: card.instance_id.clone() });
+                game.players[0].kitchen.push(card);
+            }
+            game.resolve_posts(&posts, &[]).unwrap();
+            ids.iter()
+                .map(|id| game.feed.iter().find(|c| c.variant_id == *id).unwrap().current_virality)
+                .collect()
+        };
+
+        assert_eq!(post(&mut game, &["n14", "c13"]), vec![4 + 2, 2 + 3]);
+        assert_eq!(post(&mut game, &["n14"]), vec![4]);
     }
 
     #[test]
-    fn reveal_rejects_wrong_commit() {
+    fn wire_versions_are_checked_before_decoding() {
+        let app = make_app();
+        let mut snapshot = app.compose_snapshot();
+        assert_eq!(snapshot.version, PROTOCOL_VERSION);
+        assert_eq!(check_version(snapshot.version), Ok(()));
+        snapshot.version = PROTOCOL_VERSION + 7;
+        let mismatch = GameError::VersionMismatch(error::VersionParams {
+            ours: PROTOCOL_VERSION,
+            theirs: PROTOCOL_VERSION + 7,
+        });
+        assert_eq!(check_version(snapshot.version), Err(mismatch.clone()));
+
+        // A future build's message we can't parse still gets the version error, not a decode one.
+        let future = WireEnvelope {
+            version: PROTOCOL_VERSION + 7,
+            game_id: None,
+            message: r#"{"Teleport":{"to":3}}"#.into(),
+        };
+        assert_eq!(future.open(), Err(mismatch));
+        let garbled = WireEnvelope {
+            version: PROTOCOL_VERSION,
+            game_id: None,
+            message: r#"{"Teleport":{"to":3}}"#.into(),
+        };
+        assert!(matches!(garbled.open(), Err(GameError::MalformedWireMessage(_))));
+
+        let ping = WireEnvelope::new(&WireMessage::Ping { nonce: 5 }, None).unwrap();
+        assert_eq!(ping.open(), Ok(WireMessage::Ping { nonce: 5 }));
+        // Snapshots from builds before versioning read as version 0.
+        let mut legacy = serde_json::to_value(app.compose_snapshot()).unwrap();
+        legacy.as_object_mut().unwrap().remove("version");
+        let legacy: GameSnapshot = serde_json::from_value(legacy).unwrap();
+        assert!(check_version(legacy.version).is_err());
+        // So do envelopes; the peer gets the version error rather than a decode failure.
+        let unversioned: WireEnvelope = serde_json::from_str(r#"{"message":"\"RequestSnapshot\""}"#).unwrap();
+        assert_eq!(
+            unversioned.open(),
+            Err(GameError::VersionMismatch(error::VersionParams { ours: PROTOCOL_VERSION, theirs: 0 }))
+        );
+    }
+
+    #[test]
+    fn sacrifice_trades_own_meme_for_damage_to_enemy_top() {
         let mut app = make_app();
-        let mut game =
-            build_game(&app.catalog, &mut app.next_instance, 1, vec!["n01".into()], vec!["n01".into()], "opp.os".into())
-                .unwrap();
-        let plan = TurnPlan::default();
-        let correct_hash = commitment_for(&plan, "good");
-        game.record_commit(Seat::Host, correct_hash).unwrap();
-        let err = game.record_reveal(Seat::Host, plan.clone(), "bad".into());
-        assert!(err.is_err());
+        let mut game = start_default_game(&mut app, 46, GameOptions::default());
+        for player in game.players.iter_mut() {
+            player.kitchen.clear();
+        }
+        let mut fodder = game.new_instance_from_def(find_definition("n01").unwrap(), Seat::Host, Location::Kitchen);
+        fodder.current_virality = 7;
+        let fodder_id = fodder.instance_id.clone();
+        game.players[0].kitchen.push(fodder);
+        let mut enemy = game.new_instance_from_def(find_definition("n03").unwrap(), Seat::Opponent, Location::Kitchen);
+        enemy.current_virality = 12;
+        let enemy_id = enemy.instance_id.clone();
+        game.players[1].kitchen.push(enemy);
+
+        let martyr = game.new_instance_from_def(find_definition("t17").unwrap(), Seat::Host, Location::Hand);
+        let at = |id: &str| ExploitAction {
+            card_id: martyr.instance_id.clone(),
+            target: Some(Target::Card(id.to_string())),
+        };
+        game.players[0].hand.push(martyr.clone());
+        assert_eq!(game.validate_exploit_target_seat(&Seat::Host, &at(&enemy_id)), Err(GameError::InvalidTarget));
+        game.validate_exploit_target_seat(&Seat::Host, &at(&fodder_id)).unwrap();
+
+        let effect = ExploitEffect::Sacrifice(SacrificeParams { damage: 1 });
+        game.apply_exploit_effect(effect, &Seat::Host, at(&fodder_id).target).unwrap();
+        assert!(game.players[0].kitchen.is_empty());
+        assert!(game.players[0].abyss.iter().any(|c| c.instance_id == fodder_id));
+        assert_eq!(game.players[1].kitchen[0].current_virality, 12 - 7);
     }
 
     #[test]
-    fn heavy_enters_bottom_when_feed_not_empty() {
+    fn rejoining_a_lobby_is_idempotent_for_the_same_node() {
         let mut app = make_app();
-        let mut game =
-            build_game(&app.catalog, &mut app.next_instance, 2, vec!["d10".into()], vec!["n01".into()], "opp.os".into())
-                .unwrap();
-        let def_normal = find_definition("n01").unwrap();
-        let existing =
-            game.new_instance_from_def(def_normal, Seat::Host, Location::Feed(FeedSlot { slot: 0 }));
-        game.feed.push(existing);
+        let mut lobby = joined_lobby();
+        lobby.opponent = None;
+        app.lobbies.push(lobby);
+        let payload = |node: &str, deck: Vec<String>| JoinLobbyPayload {
+            lobby_id: "lobby-0".into(),
+            node_id: node.into(),
+            deck,
+            cosmetics: None,
+            full_disclosure: false,
+            password: None,
+        };
 
-        let def_heavy = find_definition("d10").unwrap();
-        let heavy = game.new_instance_from_def(def_heavy, Seat::Host, Location::Kitchen);
-        let heavy_id = heavy.instance_id.clone();
+        app.admit_to_lobby(payload("opp.os", default_deck())).unwrap();
+        app.admit_to_lobby(payload("opp.os", aggro_deck())).unwrap();
+        assert_eq!(app.lobbies[0].opponent.as_deref(), Some("opp.os"));
+        assert_eq!(app.lobbies[0].opponent_deck, aggro_deck());
+        assert_eq!(app.admit_to_lobby(payload("rival.os", default_deck())), Err(GameError::LobbyFull));
+        assert_eq!(app.lobbies[0].opponent_deck, aggro_deck());
+
+        // The local join follows the same rule for our own node.
+        let join = |app: &mut MemeWarsState| {
+            let reply = app.join_lobby(("lobby-0".into(), default_deck(), false));
+            let mut reply = std::pin::pin!(reply);
+            let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+            match std::future::Future::poll(reply.as_mut(), &mut cx) {
+                std::task::Poll::Ready(result) => result.map(|_| ()),
+                std::task::Poll::Pending => panic!("join_lobby should not wait"),
+            }
+        };
+        assert_eq!(join(&mut app), Err(GameError::LobbyFull));
+        app.lobbies[0].opponent = None;
+        assert_eq!(join(&mut app), Ok(()));
+        assert_eq!(join(&mut app), Ok(()));
+        assert_eq!(app.lobbies[0].opponent, Some(our().node));
+    }
+
+    #[test]
+    fn feed_decay_wears_down_stale_feed_cards() {
+        let mut app = make_app();
+        let options = GameOptions {
+            rules: GameRules {
+                feed_decay: 2,
+                ..GameRules::default()
+            },
+            ..GameOptions::default()
+        };
+        let mut game = start_default_game(&mut app, 47, options);
+        let mut stale = game.new_instance_from_def(find_definition("n01").unwrap(), Seat::Host, Location::Feed(FeedSlot { slot: 0 }));
+        stale.current_virality = 3;
+        stale.abilities.clear();
+        stale.keywords = vec![Keyword::Anchor];
+        let id = stale.instance_id.clone();
+        game.feed = vec![stale];
+
+        game.resolve_turn(TurnPlan::default(), TurnPlan::default()).unwrap();
+        assert_eq!(game.feed[0].current_virality, 1);
+        game.resolve_turn(TurnPlan::default(), TurnPlan::default()).unwrap();
+        assert!(game.feed.is_empty());
+        assert!(game.events.iter().any(|e| matches!(
+            &e.event,
+            game::GameEventKind::CardDied(died) if died.instance_id == id
+        )));
+    }
+
+    #[test]
+    fn mill_moves_the_top_of_the_enemy_deck_to_their_abyss() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 48, GameOptions::default());
+        let deck_before = game.players[1].deck.len();
+        let top: Vec<String> = game.players[1].deck.iter().rev().take(3).map(|c| c.instance_id.clone()).collect();
+        assert_eq!(find_definition("d16").unwrap().class, CardKind::Exploit(ExploitEffect::Mill(3)));
+
+        game.apply_exploit_effect(ExploitEffect::Mill(3), &Seat::Host, None).unwrap();
+        let opponent = &game.players[1];
+        assert_eq!(opponent.deck.len(), deck_before - 3);
+        for id in top.iter() {
+            assert!(opponent.abyss.iter().any(|c| c.instance_id == *id && c.location == Location::Abyss));
+        }
+        assert!(game.events.iter().any(|e| matches!(
+            &e.event,
+            game::GameEventKind::Milled(milled) if milled.seat == Seat::Opponent && milled.card_ids == top
+        )));
+    }
+
+    #[test]
+    fn game_diff_lists_what_changed_between_adjacent_states() {
+        let mut app = make_app();
+        let before = start_default_game(&mut app, 42, GameOptions::default());
+        let mut after = before.clone();
+        let posted = after.players[0].hand.remove(0);
+        let posted_id = posted.instance_id.clone();
+        let mut card = after.new_instance_from_def(find_definition("n03").unwrap(), Seat::Host, Location::Feed(FeedSlot { slot: 0 }));
+        let card_id = card.instance_id.clone();
+        card.yield_rate = 2;
+        after.feed.push(card.clone());
+        after.players[1].score += 15;
+        after.phase = Phase::Reveal;
+        after.turn += 1;
+
+        let diff = after.diff(&before);
+        let fields = [
+            ("next_instance".to_string(), serde_json::to_value(after.next_instance).unwrap()),
+            ("players".to_string(), serde_json::to_value(&after.players).unwrap()),
+        ];
+        assert_eq!(
+            diff,
+            crate::snapshot::GameDiff {
+                turn: before.turn + 1,
+                phase: Some(Phase::Reveal),
+                feed: vec![crate::snapshot::FeedSlotChange { slot: 0, card: Some(card) }],
+                scores: vec![(Seat::Opponent, 15)],
+                added: vec![card_id],
+                removed: vec![posted_id],
+                fields: fields.into_iter().collect(),
+                projected_yield: Some(after.projected_yield()),
+            }
+        );
+        assert!(after.diff(&after).is_empty());
+        let emptied = before.diff(&after);
+        assert_eq!(emptied.feed, vec![crate::snapshot::FeedSlotChange { slot: 0, card: None }]);
+        assert_eq!(emptied.scores, vec![(Seat::Opponent, -15)]);
+    }
+
+    #[test]
+    fn games_run_side_by_side_and_peers_reach_the_one_they_name() {
+        let mut app = make_app();
+        app.seed_source = Some(60);
+        app.lobbies.push(joined_lobby());
+        let hosted = app.launch_lobby_game(0, None).unwrap();
+        assert_eq!(hosted.id, "host.os/lobby-0");
+        let started = start_default_game(&mut app, 61, GameOptions::default());
+        app.install_game(started);
+        let local_id = app.current_game.clone().unwrap();
+        assert_ne!(local_id, hosted.id);
+        assert_eq!(app.compose_snapshot().game_ids, vec![hosted.id.clone(), local_id.clone()]);
+        assert!(app.compose_remote_snapshot("opp.os").game_ids.is_empty());
+
+        // A tagged message reaches its game without taking the screen from the one we're playing.
+        let envelope = WireEnvelope::new(&WireMessage::RequestStateHash, Some(hosted.id.clone())).unwrap();
         {
-            let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
-            host.kitchen.push(heavy);
+            let reply = app.handle_wire_envelope(envelope);
+            let mut reply = std::pin::pin!(reply);
+            let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+            assert_eq!(
+                std::future::Future::poll(reply.as_mut(), &mut cx),
+                std::task::Poll::Ready(Ok(WireReply::StateHash(hosted.state_hash())))
+            );
         }
-        game.resolve_posts(&[PostAction { card_id: heavy_id }], &[])
-            .unwrap();
+        assert_eq!(app.current_game.as_deref(), Some(local_id.as_str()));
+        assert!(!app.background);
 
-        assert_eq!(game.feed.first().unwrap().variant_id, "n01");
-        assert_eq!(game.feed.last().unwrap().variant_id, "d10");
+        app.focus_game(hosted.id.clone()).unwrap();
+        assert_eq!(app.game().unwrap().id, hosted.id);
+        assert_eq!(app.focus_game("elsewhere".into()), Err(GameError::GameNotFound("elsewhere".into())));
+
+        // Finishing the hosted game and starting another drops it; the unfinished one stays.
+        app.game_mut().unwrap().finish(GameOutcome::Win(Seat::Host));
+        let next = start_default_game(&mut app, 62, GameOptions::default());
+        app.install_game(next);
+        assert!(!app.games.contains_key(&hosted.id));
+        assert!(app.games.contains_key(&local_id));
+        assert_eq!(app.games.len(), 2);
     }
 
     #[test]
-    fn gatekeeper_blocks_low_cost_posts() {
+    fn score_tax_takes_enemy_score_down_to_zero_within_the_game_cap() {
         let mut app = make_app();
-        let mut game =
-            build_game(&app.catalog, &mut app.next_instance, 3, vec!["m04".into()], vec!["n01".into()], "opp.os".into())
-                .unwrap();
+        let mut game = start_default_game(&mut app, 49, GameOptions::default());
+        assert_eq!(find_definition("t18").unwrap().class, CardKind::Exploit(ExploitEffect::ScoreTax(8)));
+        game.players[1].score = 12;
 
-        let gate_def = find_definition("m04").unwrap();
-        let gate =
-            game.new_instance_from_def(gate_def, Seat::Host, Location::Feed(FeedSlot { slot: 0 }));
-        game.feed.push(gate);
-        game.reindex_feed();
+        game.apply_exploit_effect(ExploitEffect::ScoreTax(8), &Seat::Host, None).unwrap();
+        assert_eq!(game.players[1].score, 4);
+        game.apply_exploit_effect(ExploitEffect::ScoreTax(8), &Seat::Host, None).unwrap();
+        assert_eq!(game.players[1].score, 0);
+        assert_eq!(game.players[0].score_tax_used, 12);
+        assert_eq!(game.score_log.last().map(|d| (d.amount, d.reason.clone())), Some((-4, game::ScoreReason::ScoreTax)));
 
-        let post_def = find_definition("n01").unwrap();
-        let post_card = game.new_instance_from_def(post_def, Seat::Opponent, Location::Kitchen);
-        let post_id = post_card.instance_id.clone();
-        {
-            let (_, opp) = split_players_mut(&mut game.players, &Seat::Opponent);
-            opp.kitchen.push(post_card);
+        // Only MAX_SCORE_TAX - 12 = 8 is left for the rest of the game.
+        game.players[1].score = 30;
+        game.apply_exploit_effect(ExploitEffect::ScoreTax(8), &Seat::Host, None).unwrap();
+        game.apply_exploit_effect(ExploitEffect::ScoreTax(8), &Seat::Host, None).unwrap();
+        assert_eq!(game.players[1].score, 22);
+        assert_eq!(game.players[0].score_tax_used, constants::MAX_SCORE_TAX);
+    }
+
+    #[test]
+    fn equal_virality_posts_order_by_initiative_then_instance_id() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 50, GameOptions::default());
+        game.initiative = Seat::Opponent;
+        let mut card = |seat: Seat| {
+            let mut card = game.new_instance_from_def(find_definition("n03").unwrap(), seat, Location::Kitchen);
+            card.current_virality = 5;
+            card
+        };
+        let (first, second, third) = (card(Seat::Host), card(Seat::Host), card(Seat::Opponent));
+        let mut entries = vec![
+            (Seat::Host, second.clone()),
+            (Seat::Host, first.clone()),
+            (Seat::Opponent, third.clone()),
+        ];
+        // Reversed input orders the same way.
+        let mut reversed: Vec<(Seat, CardInstance)> = entries.iter().rev().cloned().collect();
+        game.order_posts(&mut entries);
+        game.order_posts(&mut reversed);
+        let ids = |entries: &[(Seat, CardInstance)]| entries.iter().map(|(_, c)| c.instance_id.clone()).collect::<Vec<_>>();
+        let (lower, higher) = if first.instance_id < second.instance_id {
+            (first.instance_id, second.instance_id)
+        } else {
+            (second.instance_id, first.instance_id)
+        };
+        assert_eq!(ids(&entries), vec![third.instance_id, lower, higher]);
+        assert_eq!(ids(&entries), ids(&reversed));
+    }
+
+    #[test]
+    fn disconnect_resolution_claims_a_recorded_win_or_abandons_quietly() {
+        let mut app = make_app();
+        let started = start_default_game(&mut app, 53, GameOptions::default());
+        app.install_game(started);
+        assert_eq!(app.settle_disconnect(DisconnectResolution::ClaimWin), Err(GameError::OpponentReachable));
+
+        app.pending_sync.push(("opp.os".into(), app.current_game.clone(), WireMessage::Ping { nonce: 1 }));
+        app.settle_disconnect(DisconnectResolution::ClaimWin).unwrap();
+        app.broadcast_snapshot();
+        let game = app.game().unwrap();
+        assert_eq!(game.outcome, Some(GameOutcome::Timeout(Seat::Opponent)));
+        assert_eq!(game.winner, Some(Seat::Host));
+        assert!(app.pending_sync.is_empty());
+        let record = app.match_history.last().unwrap();
+        assert_eq!(record.outcome, Some(GameOutcome::Timeout(Seat::Opponent)));
+        assert_eq!(record.winner, Some(Seat::Host));
+
+        let recorded = app.match_history.len();
+        let started = start_default_game(&mut app, 54, GameOptions::default());
+        app.install_game(started);
+        app.pending_sync.push(("opp.os".into(), app.current_game.clone(), WireMessage::Ping { nonce: 2 }));
+        app.settle_disconnect(DisconnectResolution::Abandon).unwrap();
+        app.broadcast_snapshot();
+        assert_eq!(app.game().unwrap().phase, Phase::GameOver);
+        assert_eq!(app.game().unwrap().winner, None);
+        assert_eq!(app.match_history.len(), recorded);
+    }
+
+    #[test]
+    fn draft_pack_holds_distinct_real_cards_and_replays_from_the_seed() {
+        assert_eq!(find_definition("t09").unwrap().rarity, Rarity::Rare);
+        assert_eq!(find_definition("n01").unwrap().rarity, Rarity::Common);
+
+        let mut rng = rng::FairRandomState::with_seeds(70, 71);
+        let pack = catalog::generate_pack(&mut rng, 5);
+        assert_eq!(pack.len(), 5);
+        assert!(pack.iter().all(|id| find_definition(id).is_some()));
+        assert_eq!(pack.iter().collect::<std::collections::HashSet<_>>().len(), 5);
+        assert!(rng.history.iter().all(|e| e.kind == rng::RandomEventKind::DraftPack));
+        let mut replay = rng::FairRandomState::with_seeds(70, 71);
+        assert_eq!(catalog::generate_pack(&mut replay, 5), pack);
+
+        // Asking for more than the catalog holds deals every card once.
+        let everything = catalog::generate_pack(&mut rng, usize::MAX);
+        assert_eq!(everything.len(), build_catalog().len());
+    }
+
+    #[test]
+    fn demote_swaps_an_enemy_post_for_shitposts_in_their_kitchen() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 55, GameOptions::default());
+        let demote = game.new_instance_from_def(find_definition("t19").unwrap(), Seat::Host, Location::Hand);
+        game.players[0].hand.push(demote.clone());
+        let mut target = game.new_instance_from_def(find_definition("n03").unwrap(), Seat::Opponent, Location::Feed(FeedSlot { slot: 0 }));
+        target.cost = 5;
+        let target_id = target.instance_id.clone();
+        let ours = game.new_instance_from_def(find_definition("n03").unwrap(), Seat::Host, Location::Feed(FeedSlot { slot: 1 }));
+        let ours_id = ours.instance_id.clone();
+        game.feed = vec![target, ours];
+        let kitchen_before = game.players[1].kitchen.len();
+
+        let cast = |card: &str| ExploitAction { card_id: demote.instance_id.clone(), target: Some(Target::Card(card.into())) };
+        assert_eq!(game.validate_exploit_target_seat(&Seat::Host, &cast(&ours_id)), Err(GameError::InvalidTarget));
+        assert!(game.validate_exploit_target_seat(&Seat::Host, &cast(&target_id)).is_ok());
+
+        game.apply_exploit_effect(ExploitEffect::Demote, &Seat::Host, Some(Target::Card(target_id.clone()))).unwrap();
+        assert!(game.feed.iter().all(|c| c.instance_id != target_id));
+        assert_eq!(game.feed[0].location, Location::Feed(FeedSlot { slot: 0 }));
+        assert!(game.players[1].abyss.iter().any(|c| c.instance_id == target_id));
+        let kitchen = &game.players[1].kitchen;
+        assert_eq!(kitchen.len(), kitchen_before + 2);
+        assert!(kitchen[kitchen_before..].iter().all(|c| c.variant_id == "d06" && c.owner == Seat::Opponent));
+    }
+
+    #[test]
+    fn preview_turn_leaves_the_real_game_alone() {
+        let mut app = make_app();
+        let mut started = start_default_game(&mut app, 51, GameOptions::default());
+        let def = find_definition("n01").unwrap();
+        let meme = started.new_instance_from_def(def, Seat::Host, Location::Hand);
+        let meme_id = meme.instance_id.clone();
+        started.players[0].hand.push(meme);
+        started.players[0].mana = 10;
+        app.install_game(started);
+        let before = app.game().unwrap().clone();
+
+        let plan = TurnPlan { plays_to_kitchen: vec![meme_id.clone()], ..TurnPlan::default() };
+        let snapshot = {
+            let reply = app.preview_turn((Seat::Host, plan));
+            let mut reply = std::pin::pin!(reply);
+            let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+            match std::future::Future::poll(reply.as_mut(), &mut cx) {
+                std::task::Poll::Ready(result) => result.unwrap(),
+                std::task::Poll::Pending => panic!("preview_turn should not wait"),
+            }
+        };
+        let preview = snapshot.game.unwrap();
+        assert_eq!(preview.turn, before.turn + 1);
+        assert!(preview.players[1].hand.iter().all(|c| c.variant_id == "hidden"));
+        assert!(preview.players[0].kitchen.iter().any(|c| c.instance_id == meme_id));
+        assert!(preview.rng.reseeds.last().is_some_and(|r| r.host_contribution == constants::PREVIEW_RNG_SALT));
+
+        assert_eq!(app.game(), Some(&before));
+        assert_eq!(app.game().unwrap().state_hash(), before.state_hash());
+
+        let mut drafting = before.clone();
+        drafting.phase = Phase::Draft;
+        assert_eq!(drafting.preview_turn(&Seat::Host, TurnPlan::default()), Err(GameError::DraftPending));
+    }
+
+    #[test]
+    fn client_view_never_names_the_other_hand() {
+        let mut app = make_app();
+        let game = start_default_game(&mut app, 52, GameOptions::default());
+        let hidden: Vec<String> = game.players[1].hand.iter().map(|c| c.instance_id.clone()).collect();
+        assert!(!hidden.is_empty());
+        for view in [game.client_view(&game.players[0].node_id), game.client_view("someone-else.os")] {
+            let json = serde_json::to_string(&view).unwrap();
+            for id in hidden.iter() {
+                assert!(!json.contains(&format!("\"{}\"", id)), "{} leaked", id);
+            }
+            assert_eq!(view.game_seed, 0);
+            assert_eq!((view.rng.shuffle_seed, view.rng.opponent_seed), (0, 0));
         }
 
-        game.resolve_posts(&[], &[PostAction { card_id: post_id }])
+        // The seated player still sees their own cards in the log.
+        let own = game.client_view("opp.os");
+        let json = serde_json::to_string(&own).unwrap();
+        assert!(hidden.iter().all(|id| json.contains(&format!("\"{}\"", id))));
+    }
+
+    #[test]
+    fn subscribers_only_get_the_seatless_view() {
+        let mut app = make_app();
+        let started = start_default_game(&mut app, 53, GameOptions::default());
+        app.install_game(started);
+        let reply = app
+            .dispatch_wire_message(WireMessage::Subscribe(SubscribePayload { node_id: our().node }))
             .unwrap();
-        assert_eq!(game.feed[0].variant_id, "m04");
-        assert_eq!(game.feed[1].variant_id, "n01");
+        let WireReply::Snapshot(snapshot) = reply else {
+            panic!("expected a snapshot");
+        };
+        let view = snapshot.game.unwrap();
+        assert!(view.players.iter().all(|p| p.hand.iter().all(|c| c.variant_id == "hidden")));
+        assert_eq!(view.game_seed, 0);
+        assert_eq!(app.spectators, vec![our().node]);
     }
 
     #[test]
-    fn feed_yield_scales_with_stakes() {
+    fn peers_cannot_act_for_the_other_seat() {
         let mut app = make_app();
-        let mut game =
-            build_game(&app.catalog, &mut app.next_instance, 4, vec!["n01".into()], vec!["n01".into()], "opp.os".into())
-                .unwrap();
+        let started = start_default_game(&mut app, 54, GameOptions::default());
+        let turn = started.turn;
+        app.install_game(started);
+        let before = app.game().unwrap().clone();
+        // The stub source is our node, which holds the host seat.
+        let not_seated = Err(GameError::NotSeated(our().node));
+        let commit = WireMessage::Commit(WireCommit { seat: Seat::Opponent, hash: "junk".into(), turn });
+        assert_eq!(app.dispatch_wire_message(commit), not_seated);
+        let fold = WireMessage::FoldBased(StakeNotice { seat: Seat::Opponent });
+        assert_eq!(app.dispatch_wire_message(fold), not_seated);
+        assert_eq!(app.game(), Some(&before));
 
-        let card_def = find_definition("n01").unwrap();
-        let card =
-            game.new_instance_from_def(card_def, Seat::Host, Location::Feed(FeedSlot { slot: 0 }));
-        game.feed.push(card);
-        game.stakes = 2;
-        game.apply_feed_yield();
-        let host = game.players.iter().find(|p| p.seat == Seat::Host).unwrap();
-        assert_eq!(host.score, constants::BASE_FEED_YIELD * 2);
+        let commit = WireMessage::Commit(WireCommit { seat: Seat::Host, hash: "ours".into(), turn });
+        assert!(app.dispatch_wire_message(commit).is_ok());
+    }
+
+    #[test]
+    fn game_sync_only_comes_from_across_the_table() {
+        let mut app = make_app();
+        // The stub source is our own node, which holds the host seat here, not the other one.
+        let ours = start_default_game(&mut app, 55, GameOptions::default());
+        assert_eq!(
+            app.dispatch_wire_message(WireMessage::SyncGame(ours)),
+            Err(GameError::NotSeated(our().node))
+        );
+        assert!(app.game().is_none());
+
+        // A game we aren't seated in is only taken from the host we're watching.
+        let mut watched = start_default_game(&mut app, 56, GameOptions::default());
+        watched.players[0].node_id = "host.os".into();
+        watched.id = "host.os/game-0".into();
+        let watched = watched.client_view("");
+        assert_eq!(
+            app.dispatch_wire_message(WireMessage::SyncGame(watched.clone())),
+            Err(GameError::NotSeated(our().node))
+        );
+        app.watching = Some(our().node);
+        app.dispatch_wire_message(WireMessage::SyncGame(watched.clone())).unwrap();
+        assert_eq!(app.game(), Some(&watched));
+    }
+
+    #[test]
+    fn snapshot_requests_from_outside_the_game_are_redacted() {
+        let mut app = make_app();
+        let mut started = start_default_game(&mut app, 57, GameOptions::default());
+        // The stub source is our node; seat someone else in its place.
+        started.players[0].node_id = "host.os".into();
+        app.install_game(started);
+        let WireReply::Snapshot(snapshot) = app.dispatch_wire_message(WireMessage::RequestSnapshot).unwrap() else {
+            panic!("expected a snapshot");
+        };
+        let view = snapshot.game.unwrap();
+        assert!(view.players.iter().all(|p| p.hand.iter().all(|c| c.variant_id == "hidden")));
+        assert!(view.players.iter().all(|p| p.deck.iter().all(|c| c.variant_id == "hidden")));
+        assert_eq!(view.game_seed, 0);
+    }
+
+    #[test]
+    fn paged_events_are_redacted_and_capped() {
+        let mut app = make_app();
+        let game = start_default_game(&mut app, 58, GameOptions::default());
+        let hidden: Vec<String> = game.players[1].hand.iter().map(|c| c.instance_id.clone()).collect();
+        let total = game.events.len();
+        app.install_game(game);
+
+        let page = app.client_events_since(0, usize::MAX).unwrap();
+        assert_eq!(page.len(), total.min(SNAPSHOT_EVENT_WINDOW));
+        let json = serde_json::to_string(&page).unwrap();
+        for id in hidden.iter() {
+            assert!(!json.contains(&format!("\"{}\"", id)), "{} leaked", id);
+        }
+        assert_eq!(app.client_events_since(1, 2).unwrap(), page[1..3]);
+    }
+
+    // Polls a future once; the stubbed transport never leaves one pending.
+    fn ready<T>(future: impl std::future::Future<Output = T>) -> T {
+        let mut future = std::pin::pin!(future);
+        let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+        match future.as_mut().poll(&mut cx) {
+            std::task::Poll::Ready(output) => output,
+            std::task::Poll::Pending => panic!("future not ready"),
+        }
     }
 
     #[test]
-    fn stakes_call_accept_and_fold() {
+    fn queued_updates_keep_their_game_and_unknown_games_are_refused() {
         let mut app = make_app();
-        let mut game =
-            build_game(&app.catalog, &mut app.next_instance, 5, vec!["n01".into()], vec!["n01".into()], "opp.os".into())
-                .unwrap();
+        let first = start_default_game(&mut app, 59, GameOptions::default());
+        app.install_game(first);
+        let first_id = app.current_game.clone();
+        assert!(matches!(
+            ready(app.send_turn_update("opp.os", WireMessage::Ping { nonce: 3 })),
+            Err(GameError::PeerUnreachable(_))
+        ));
+        let second = start_default_game(&mut app, 60, GameOptions::default());
+        app.install_game(second);
+        assert_ne!(app.current_game, first_id);
+        assert_eq!(app.pending_sync[0].1, first_id);
 
-        game.call_based(Seat::Host).unwrap();
-        assert_eq!(game.phase, Phase::StakePending);
-        assert!(game.pending_stakes.is_some());
+        let unknown = Some("elsewhere".to_string());
+        let commit = WireMessage::Commit(WireCommit { seat: Seat::Opponent, hash: "h".into(), turn: 1 });
+        assert_eq!(
+            ready(app.handle_wire_envelope(WireEnvelope::new(&commit, unknown.clone()).unwrap())),
+            Err(GameError::GameNotFound("elsewhere".into()))
+        );
+        let ping = WireEnvelope::new(&WireMessage::Ping { nonce: 4 }, unknown).unwrap();
+        assert_eq!(ready(app.handle_wire_envelope(ping)), Ok(WireReply::Pong { nonce: 4 }));
+    }
 
-        game.accept_based(Seat::Opponent).unwrap();
-        assert_eq!(game.stakes, 2);
-        assert!(game.pending_stakes.is_none());
-        assert_eq!(game.phase, Phase::Commit);
+    #[test]
+    fn sends_retry_only_transport_failures() {
+        let mut tries = 0;
+        let unreachable = ready(with_retries(|| {
+            tries += 1;
+            async { Err(GameError::Transport("down".into())) }
+        }));
+        assert_eq!(unreachable, Err(GameError::Transport("down".into())));
+        assert_eq!(tries, WIRE_SEND_ATTEMPTS);
 
-        game.call_based(Seat::Opponent).unwrap();
-        game.fold_based(Seat::Host).unwrap();
-        assert_eq!(game.winner, Some(Seat::Opponent));
-        assert_eq!(game.phase, Phase::GameOver);
+        let mut tries = 0;
+        let refused = ready(with_retries(|| {
+            tries += 1;
+            async { Err(GameError::NotCommitPhase) }
+        }));
+        assert_eq!(refused, Err(GameError::NotCommitPhase));
+        assert_eq!(tries, 1);
     }
 
     #[test]
-    fn initiative_controls_exploit_order() {
+    fn pending_updates_flush_per_node_in_order() {
         let mut app = make_app();
-        let mut game = build_game(&app.catalog, &mut app.next_instance, 6, vec![], vec![], "opp.os".into()).unwrap();
-        for player in game.players.iter_mut() {
-            player.hand.clear();
-            player.kitchen.clear();
-            player.mana = 10;
-            player.max_mana = 10;
+        for (node, nonce) in [("b.os", 1), ("a.os", 2), ("b.os", 3), ("a.os", 4)] {
+            app.pending_sync.push((node.into(), None, WireMessage::Ping { nonce }));
         }
+        assert_eq!(app.pending_nodes(), vec!["a.os".to_string(), "b.os".to_string()]);
 
-        let target_def = find_definition("n04").unwrap();
-        let mut target = game.new_instance_from_def(target_def, Seat::Host, Location::Kitchen);
-        target.cook_rate = 0;
-        let target_id = target.instance_id.clone();
-        {
-            let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
-            host.kitchen.push(target);
-        }
+        // The stubbed transport is down: a.os keeps both updates, in order, and b.os is untouched.
+        assert!(matches!(ready(app.flush_pending_for("a.os")), Err(GameError::PeerUnreachable(_))));
+        let nonces: Vec<u64> = app
+            .pending_sync
+            .iter()
+            .map(|(_, _, m)| match m {
+                WireMessage::Ping { nonce } => *nonce,
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(nonces, vec![1, 2, 3, 4]);
 
-        let protect_def = find_definition("c06").unwrap();
-        let protect = game.new_instance_from_def(protect_def, Seat::Host, Location::Hand);
-        let protect_id = protect.instance_id.clone();
-        {
-            let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
-            host.hand.push(protect);
-        }
+        // A refusal drops the entry it answered; a transport failure keeps it.
+        assert_eq!(app.settle_pending(1, Err(GameError::NotCommitPhase)), Err(GameError::NotCommitPhase));
+        assert_eq!(app.pending_sync.len(), 3);
+        assert!(app.settle_pending(0, Err(GameError::Transport("down".into()))).is_err());
+        assert_eq!(app.pending_sync.len(), 3);
+        app.settle_pending(0, Ok(WireReply::Ack)).unwrap();
+        assert_eq!(app.pending_sync.len(), 2);
+    }
 
-        let damage_def = find_definition("t02").unwrap();
-        let damage = game.new_instance_from_def(damage_def, Seat::Opponent, Location::Hand);
-        let damage_id = damage.instance_id.clone();
-        {
-            let (_, opp) = split_players_mut(&mut game.players, &Seat::Opponent);
-            opp.hand.push(damage);
-        }
+    #[test]
+    fn broadcasts_send_deltas_alone_when_they_cover_the_change() {
+        let mut app = make_app();
+        let started = start_default_game(&mut app, 61, GameOptions::default());
+        app.install_game(started);
+        let snapshot = app.compose_broadcast_snapshot();
+        assert!(app.progress_messages(&snapshot).is_empty());
+        let before = app.broadcast_view.clone().unwrap();
 
-        game.initiative = Seat::Opponent;
-        let host_plan = TurnPlan {
-            plays_to_kitchen: vec![],
-            posts: vec![],
-            exploits: vec![ExploitAction {
-                card_id: protect_id,
-                target: Some(Target::Card(target_id.clone())),
-            }],
+        app.game_mut().unwrap().players[1].score += 5;
+        let snapshot = app.compose_broadcast_snapshot();
+        let messages = app.progress_messages(&snapshot);
+        let [WsServerMessage::Diff(diff)] = &messages[..] else {
+            panic!("expected a lone diff, got {:?}", messages);
         };
-        let opp_plan = TurnPlan {
-            plays_to_kitchen: vec![],
-            posts: vec![],
-            exploits: vec![ExploitAction {
-                card_id: damage_id,
-                target: Some(Target::Card(target_id.clone())),
-            }],
+        // Patching the previous view with the diff's fields gives the new one.
+        let serde_json::Value::Object(mut patched) = serde_json::to_value(&before).unwrap() else {
+            unreachable!()
         };
-        game.resolve_turn(host_plan, opp_plan).unwrap();
+        patched.extend(diff.fields.clone());
+        let after = app.broadcast_view.clone().unwrap();
+        assert_eq!(serde_json::Value::Object(patched), serde_json::to_value(&after).unwrap());
 
-        let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
-        let survivor = host
-            .kitchen
-            .iter()
-            .find(|c| c.instance_id == target_id)
-            .unwrap();
-        assert_eq!(survivor.current_virality, 5);
+        // A change outside the game takes a full snapshot.
+        app.game_mut().unwrap().players[1].score += 5;
+        app.chat_log.push(ChatMessage { from: "opp.os".into(), text: "gg".into(), turn: 1 });
+        let snapshot = app.compose_broadcast_snapshot();
+        assert!(app.progress_messages(&snapshot).is_empty());
     }
 
     #[test]
-    fn cook_and_decay_apply() {
+    fn we_only_choose_our_own_draw() {
         let mut app = make_app();
-        let mut game =
-            build_game(&app.catalog, &mut app.next_instance, 7, vec!["c01".into(), "d05".into()], vec![], "opp.os".into())
-                .unwrap();
-        for player in game.players.iter_mut() {
-            player.kitchen.clear();
-            player.hand.clear();
-        }
-        let fast_cook_def = find_definition("c01").unwrap();
-        let mut fast_cook =
-            game.new_instance_from_def(fast_cook_def, Seat::Host, Location::Kitchen);
-        let fast_id = fast_cook.instance_id.clone();
-        fast_cook.current_virality = 2;
-        let volatile_def = find_definition("d05").unwrap();
-        let mut volatile = game.new_instance_from_def(volatile_def, Seat::Host, Location::Kitchen);
-        volatile.current_virality = 12;
-        {
-            let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
-            host.kitchen.push(fast_cook);
-            host.kitchen.push(volatile);
-        }
+        let options = GameOptions {
+            rules: GameRules { draft_draw: true, ..GameRules::default() },
+            ..GameOptions::default()
+        };
+        let mut game = start_default_game(&mut app, 62, options);
+        game.resolve_turn(TurnPlan::default(), TurnPlan::default()).unwrap();
+        let theirs = game.players[1].pending_draw[0].instance_id.clone();
+        let ours = game.players[0].pending_draw[0].instance_id.clone();
+        app.install_game(game);
 
-        game.apply_cook_and_decay();
+        assert_eq!(ready(app.choose_draw(theirs)), Err(GameError::NotSeated(our().node)));
+        assert_eq!(app.game().unwrap().players[1].pending_draw.len(), 2);
+        // The opponent is unreachable through the stub, but our choice still stands.
+        assert!(ready(app.choose_draw(ours.clone())).is_err());
+        assert!(app.game().unwrap().players[0].hand.iter().any(|c| c.instance_id == ours));
+    }
 
-        let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
-        let cook = host
-            .kitchen
-            .iter()
-            .find(|c| c.instance_id == fast_id)
-            .unwrap();
-        assert_eq!(cook.current_virality, 5);
-        game.cleanup_board();
-        let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
-        assert_eq!(host.kitchen.len(), 1);
+    #[test]
+    fn we_only_trim_our_own_hand() {
+        let mut app = make_app();
+        let options = GameOptions {
+            rules: GameRules { discard_phase: true, ..GameRules::default() },
+            ..GameOptions::default()
+        };
+        let mut game = start_default_game(&mut app, 63, options);
+        let def = find_definition("n01").unwrap();
+        let hand: Vec<CardInstance> = (0..MAX_HAND_SIZE)
+            .map(|_| game.new_instance_from_def(def, Seat::Opponent, Location::Hand))
+            .collect();
+        game.players[1].hand = hand.clone();
+        game.players[0].hand.clear();
+        game.resolve_turn(TurnPlan::default(), TurnPlan::default()).unwrap();
+        assert_eq!(game.phase, Phase::Discard);
+        app.install_game(game);
+
+        let keep: Vec<String> = hand.iter().map(|c| c.instance_id.clone()).collect();
+        assert_eq!(ready(app.discard_to_hand_size(keep)), Err(GameError::NotSeated(our().node)));
+        assert_eq!(app.game().unwrap().players[1].hand.len(), MAX_HAND_SIZE + 1);
+        assert_eq!(app.game().unwrap().phase, Phase::Discard);
     }
 
     #[test]
-    fn pinned_and_anchor_block_movement() {
+    fn only_our_own_plans_are_diagnosed() {
         let mut app = make_app();
-        let mut game =
-            build_game(&app.catalog, &mut app.next_instance, 8, vec!["m07".into(), "n01".into()], vec![], "opp.os".into())
-                .unwrap();
-        for player in game.players.iter_mut() {
-            player.feed_locked = false;
-        }
-        let anchor_def = find_definition("m07").unwrap();
-        let anchor = game.new_instance_from_def(
-            anchor_def,
-            Seat::Host,
-            Location::Feed(FeedSlot { slot: 0 }),
+        let started = start_default_game(&mut app, 64, GameOptions::default());
+        let probe = TurnPlan {
+            plays_to_kitchen: vec![started.players[1].hand[0].instance_id.clone()],
+            ..TurnPlan::default()
+        };
+        app.install_game(started);
+        assert_eq!(
+            ready(app.diagnose((Seat::Opponent, probe.clone()))),
+            Err(GameError::NotSeated(our().node))
         );
-        let other_def = find_definition("n01").unwrap();
-        let other =
-            game.new_instance_from_def(other_def, Seat::Host, Location::Feed(FeedSlot { slot: 1 }));
-        game.feed = vec![anchor, other];
-        game.reindex_feed();
+        assert!(ready(app.diagnose((Seat::Host, probe))).is_ok());
+    }
 
-        let (_, opp) = split_players_mut(&mut game.players, &Seat::Opponent);
-        opp.pinned_slots.push(1);
-        game.shift_feed_up(1).unwrap();
-        assert_eq!(game.feed[0].variant_id, "m07");
-        assert_eq!(game.feed[1].variant_id, "n01");
+    #[test]
+    fn cancelled_calls_reach_the_opponent_before_they_apply() {
+        let mut app = make_app();
+        let started = start_default_game(&mut app, 65, GameOptions::default());
+        app.install_game(started);
+        let cancel = WsClientMessage::CancelBased { seat: Seat::Host };
+        assert_eq!(ready(app.process_ws_message(cancel.clone())), Err(GameError::NoPendingStakes));
+        assert!(app.pending_sync.is_empty());
 
-        let (_, opp) = split_players_mut(&mut game.players, &Seat::Opponent);
-        opp.pinned_slots.clear();
-        game.shift_feed_up(1).unwrap();
-        assert_eq!(game.feed[0].variant_id, "m07");
+        app.game_mut().unwrap().call_based(Seat::Host).unwrap();
+        // The stubbed transport is down: the cancel is queued for the opponent and applied here.
+        assert!(matches!(ready(app.process_ws_message(cancel)), Err(GameError::PeerUnreachable(_))));
+        assert!(app.game().unwrap().pending_stakes.is_none());
+        assert_eq!(
+            app.pending_sync.last().map(|(_, _, m)| m),
+            Some(&WireMessage::CancelBased(StakeNotice { seat: Seat::Host }))
+        );
     }
 
     #[test]
-    fn can_play_to_kitchen_and_post_existing_in_same_turn() {
+    fn resolution_order_counts_combos_and_every_post() {
         let mut app = make_app();
-        let mut game =
-            build_game(&app.catalog, &mut app.next_instance, 42, vec![], vec![], "opp.os".into()).unwrap();
-
+        let mut game = start_default_game(&mut app, 66, GameOptions::default());
+        game.turn = 1;
+        game.initiative = Seat::Host;
         for player in game.players.iter_mut() {
             player.hand.clear();
             player.kitchen.clear();
             player.mana = 10;
-            player.max_mana = 10;
         }
+        let kitchen = |game: &mut GameState, seat: Seat, id: &str, virality: Option<i32>| {
+            let mut card = game.new_instance_from_def(find_definition(id).unwrap(), seat.clone(), Location::Kitchen);
+            card.played_turn = 0;
+            if let Some(virality) = virality {
+                card.current_virality = virality;
+            }
+            let card_id = card.instance_id.clone();
+            split_players_mut(&mut game.players, &seat).0.kitchen.push(card);
+            PostAction { card_id }
+        };
+        // Alone, n14 (4) and c13 (2) would trail the opponent's 5. Posted together, the combo lifts
+        // them to 6 and 5, and host initiative breaks the tie at 5.
+        let combo = kitchen(&mut game, Seat::Host, "n14", None);
+        let partner = kitchen(&mut game, Seat::Host, "c13", None);
+        let rival = kitchen(&mut game, Seat::Opponent, "n01", Some(5));
+        let host_plan = TurnPlan { posts: vec![combo.clone(), partner.clone()], ..TurnPlan::default() };
+        let opp_plan = TurnPlan { posts: vec![rival.clone()], ..TurnPlan::default() };
 
-        let hand_def = find_definition("n02").unwrap();
-        let kitchen_def = find_definition("n01").unwrap();
-        let to_kitchen = game.new_instance_from_def(hand_def, Seat::Host, Location::Hand);
-        let in_kitchen = game.new_instance_from_def(kitchen_def, Seat::Host, Location::Kitchen);
-        let hand_id = to_kitchen.instance_id.clone();
-        let kitchen_id = in_kitchen.instance_id.clone();
-
-        {
-            let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
-            host.hand.push(to_kitchen);
-            host.kitchen.push(in_kitchen);
-        }
+        let order = game.resolution_order(&host_plan, &opp_plan);
+        let ids: Vec<&str> = order.iter().map(|e| e.card_id.as_str()).collect();
+        assert_eq!(ids, vec![combo.card_id.as_str(), partner.card_id.as_str(), rival.card_id.as_str()]);
+        assert_eq!(order[0].reason, game::ResolutionReason::Virality);
+        assert_eq!(order[1].reason, game::ResolutionReason::InitiativeTiebreak);
 
-        let host_plan = TurnPlan {
-            plays_to_kitchen: vec![hand_id.clone()],
-            posts: vec![PostAction {
-                card_id: kitchen_id.clone(),
-            }],
-            exploits: vec![],
-        };
-        let opponent_plan = TurnPlan::default();
+        game.resolve_posts(&host_plan.posts, &opp_plan.posts).unwrap();
+        // Later posts land on top, so the feed reads in reverse resolution order.
+        let feed_ids: Vec<&str> = game.feed.iter().rev().map(|c| c.instance_id.as_str()).collect();
+        assert_eq!(feed_ids, ids);
+    }
 
-        game.resolve_turn(host_plan, opponent_plan).unwrap();
+    #[test]
+    fn failed_batches_roll_back_lobbies_and_chat_without_broadcasting() {
+        let mut app = make_app();
+        let started = start_default_game(&mut app, 39, GameOptions::default());
+        app.install_game(started);
+        app.lobbies.push(Lobby { opponent: None, ..joined_lobby() });
+        app.broadcast_frame = None;
+        let join = WireMessage::JoinLobby(JoinLobbyPayload {
+            lobby_id: "lobby-0".into(),
+            node_id: "opp.os".into(),
+            deck: default_deck(),
+            cosmetics: None,
+            full_disclosure: false,
+            password: None,
+        });
+        let chat = WireMessage::Chat(ChatMessage { from: String::new(), text: "gl".into(), turn: 1 });
+        let stale = WireMessage::CallBased(StakeNotice { seat: Seat::Opponent });
 
-        let hand_card_in_kitchen = {
-            let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
-            host.kitchen.iter().any(|c| c.instance_id == hand_id)
-        };
-        let feed_contains_kitchen_card = game
-            .feed
-            .iter()
-            .any(|c| c.instance_id == kitchen_id && c.owner == Seat::Host);
+        let err = app.apply_wire_batch(vec![join.clone(), chat.clone(), stale]).unwrap_err();
+        assert!(matches!(err, GameError::NotSeated(_)), "{err:?}");
+        assert_eq!(app.lobbies[0].opponent, None);
+        assert!(app.chat_log.is_empty());
+        assert!(app.broadcast_frame.is_none());
+        assert!(app.deferred_broadcast.is_none());
 
-        assert!(hand_card_in_kitchen, "newly played meme should remain in kitchen");
-        assert!(feed_contains_kitchen_card, "existing kitchen meme should post to feed");
+        app.apply_wire_batch(vec![join, chat]).unwrap();
+        assert_eq!(app.lobbies[0].opponent.as_deref(), Some("opp.os"));
+        assert_eq!(app.chat_log.len(), 1);
+        assert!(app.broadcast_frame.is_some());
     }
 
     #[test]
-    fn shuffle_feed_is_deterministic_per_seed_and_turn() {
+    fn mana_counts_the_tax_still_owed() {
         let mut app = make_app();
-        let mut game =
-            build_game(&app.catalog, &mut app.next_instance, 9, vec!["n01".into(), "n02".into()], vec![], "opp.os".into())
-                .unwrap();
-        for player in game.players.iter_mut() {
-            player.feed_locked = false;
+        let mut game = start_default_game(&mut app, 40, GameOptions::default());
+        let (before, max) = game.mana_for(&Seat::Host).unwrap();
+        {
+            let (host, opponent) = split_players_mut(&mut game.players, &Seat::Host);
+            host.mana_tax_next = 1;
+            opponent.mana_tax_next = -1;
         }
-        let first = game.new_instance_from_def(
-            find_definition("n01").unwrap(),
-            Seat::Host,
-            Location::Feed(FeedSlot { slot: 0 }),
-        );
-        let second = game.new_instance_from_def(
-            find_definition("n02").unwrap(),
-            Seat::Host,
-            Location::Feed(FeedSlot { slot: 1 }),
-        );
-        game.feed = vec![first.clone(), second.clone()];
-        game.reindex_feed();
 
-        game.apply_exploit_effect(ExploitEffect::ShuffleFeed, &Seat::Host, None)
-            .unwrap();
-        let order1: Vec<String> = game.feed.iter().map(|c| c.variant_id.clone()).collect();
+        assert_eq!(game.mana_for(&Seat::Host).unwrap(), (before - 1, max));
+        assert_eq!(game.mana_for(&Seat::Opponent).unwrap().0, before + 1);
+    }
 
-        let mut game2 =
-            build_game(&app.catalog, &mut app.next_instance, 9, vec!["n01".into(), "n02".into()], vec![], "opp.os".into())
-                .unwrap();
-        game2.feed = vec![first, second];
-        game2.reindex_feed();
-        game2
-            .apply_exploit_effect(ExploitEffect::ShuffleFeed, &Seat::Host, None)
+    #[test]
+    fn exploit_whose_target_left_play_fizzles() {
+        let mut app = make_app();
+        let mut game = start_default_game(&mut app, 41, GameOptions::default());
+        let rewind = game.new_instance_from_def(find_definition("t14").unwrap(), Seat::Host, Location::Hand);
+        let dox = game.new_instance_from_def(find_definition("t02").unwrap(), Seat::Host, Location::Hand);
+        let meme = game.new_instance_from_def(find_definition("n01").unwrap(), Seat::Opponent, Location::Kitchen);
+        let (dox_id, meme_id) = (dox.instance_id.clone(), meme.instance_id.clone());
+        {
+            let (host, opponent) = split_players_mut(&mut game.players, &Seat::Host);
+            host.hand.extend([rewind.clone(), dox]);
+            host.mana = 5;
+            opponent.kitchen.push(meme);
+        }
+        // Both target the meme, but Rewind sends it back to hand before Dox resolves.
+        let plan = TurnPlan {
+            exploits: vec![
+                ExploitAction { card_id: rewind.instance_id.clone(), target: Some(Target::Card(meme_id.clone())) },
+                ExploitAction { card_id: dox_id.clone(), target: Some(Target::Card(meme_id.clone())) },
+            ],
+            ..TurnPlan::default()
+        };
+        let charges = game.apply_turn_for_seat(Seat::Host, plan.clone()).unwrap();
+
+        game.resolve_exploits(&Seat::Host, &plan, &TurnPlan::default(), (&charges, &[]))
             .unwrap();
-        let order2: Vec<String> = game2.feed.iter().map(|c| c.variant_id.clone()).collect();
-        assert_eq!(order1, order2);
+        let damaged = game
+            .events
+            .iter()
+            .any(|e| matches!(&e.event, game::GameEventKind::Damage(d) if d.target == meme_id));
+        assert!(!damaged);
+        let (host, opponent) = split_players_mut(&mut game.players, &Seat::Host);
+        let bounced = opponent.hand.iter().find(|c| c.instance_id == meme_id).unwrap();
+        assert_eq!(bounced.current_virality, bounced.base_virality);
+        // The fizzled exploit is still spent.
+        assert!(host.abyss.iter().any(|c| c.instance_id == dox_id));
+        assert_eq!(host.mana, 0);
     }
 
     #[test]
-    fn execute_ignores_shield_and_protect() {
+    fn post_cost_is_only_paid_for_posts_that_land() {
         let mut app = make_app();
-        let mut game =
-            build_game(&app.catalog, &mut app.next_instance, 10, vec!["c05".into()], vec![], "opp.os".into())
-                .unwrap();
-        for player in game.players.iter_mut() {
-            player.hand.clear();
-            player.kitchen.clear();
-        }
-        let mut shielded =
-            game.new_instance_from_def(find_definition("c05").unwrap(), Seat::Host, Location::Kitchen);
-        shielded.protected_until_end = true;
-        let shielded_id = shielded.instance_id.clone();
+        let mut game = start_default_game(&mut app, 42, GameOptions::default());
+        game.turn = 1;
+        let bait = game.new_instance_from_def(find_definition("n11").unwrap(), Seat::Host, Location::Kitchen);
+        let bait_id = bait.instance_id.clone();
         {
             let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
-            host.kitchen.push(shielded);
+            host.kitchen.push(bait);
+            host.mana = 2;
         }
-        let exploit = ExploitAction {
-            card_id: "exec".into(),
-            target: Some(Target::Card(shielded_id.clone())),
+        let plan = TurnPlan {
+            posts: vec![PostAction { card_id: bait_id.clone() }],
+            ..TurnPlan::default()
         };
-        game.apply_exploit_effect(
-            ExploitEffect::Execute,
-            &Seat::Opponent,
-            exploit.target.clone(),
-        )
-        .unwrap();
-        game.cleanup_board();
-        let (host, _) = split_players_mut(&mut game.players, &Seat::Host);
-        assert!(host.kitchen.iter().all(|c| c.instance_id != shielded_id));
-        assert!(host.abyss.iter().any(|c| c.instance_id == shielded_id));
+        let host_mana = |game: &mut GameState| split_players_mut(&mut game.players, &Seat::Host).0.mana;
+        let post = |game: &mut GameState| {
+            game.apply_turn_for_seat(Seat::Host, plan.clone()).unwrap();
+            game.resolve_posts(&plan.posts, &[]).unwrap();
+        };
+
+        // Played this turn, so it can't post yet.
+        split_players_mut(&mut game.players, &Seat::Host).0.kitchen[0].played_turn = 1;
+        post(&mut game);
+        assert_eq!(host_mana(&mut game), 2);
+
+        // Ready to post, but the feed is locked.
+        split_players_mut(&mut game.players, &Seat::Host).0.kitchen[0].played_turn = 0;
+        split_players_mut(&mut game.players, &Seat::Host).1.feed_locked = true;
+        post(&mut game);
+        assert_eq!(host_mana(&mut game), 2);
+
+        split_players_mut(&mut game.players, &Seat::Host).1.feed_locked = false;
+        post(&mut game);
+        assert_eq!(host_mana(&mut game), 0);
+        assert!(game.feed.iter().any(|c| c.instance_id == bait_id));
+    }
+
+    #[test]
+    fn replays_keep_the_logged_seats() {
+        let app = make_app();
+        let mut next_instance = 0;
+        let mut game = GameState::hosted_lobby("host.os".into(), "opp.os".into(), &GameOptions::default());
+        game.start(&app.catalog, &mut next_instance, 43, default_deck(), default_deck(), GameOptions::default())
+            .unwrap();
+
+        let replayed = GameState::replay_from(43, &game.events).unwrap();
+        let nodes: Vec<&str> = replayed.players.iter().map(|p| p.node_id.as_str()).collect();
+        assert_eq!(nodes, ["host.os", "opp.os"]);
+        assert_eq!(replayed.state_hash(), game.state_hash());
     }
 }
@@ -1,5 +1,7 @@
+use crate::constants::PROTOCOL_VERSION;
+use crate::error::{GameError, VersionParams};
 use crate::snapshot::GameSnapshot;
-use crate::types::{Seat, TurnPlan};
+use crate::types::{ChatMessage, DisconnectResolution, Seat, TurnPlan};
 use serde::{Deserialize, Serialize};
 
 // Wire-level message shapes for P2P sync and the websocket bridge. These stay simple to keep
@@ -20,6 +22,24 @@ pub struct WireReveal {
     pub turn: u32,
 }
 
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct WireRetract {
+    pub seat: Seat,
+    pub turn: u32,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct WireDrawChoice {
+    pub keep: String,
+    pub turn: u32,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct WireDiscard {
+    pub keep: Vec<String>,
+    pub turn: u32,
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub struct StakeNotice {
     pub seat: Seat,
@@ -30,11 +50,42 @@ pub struct JoinLobbyPayload {
     pub lobby_id: String,
     pub node_id: String,
     pub deck: Vec<String>,
+    #[serde(default)]
+    pub cosmetics: Option<crate::types::PlayerCosmetics>,
+    #[serde(default)]
+    pub full_disclosure: bool,
+    #[serde(default)]
+    pub password: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct SubscribePayload {
+    pub node_id: String,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct RematchPayload {
+    pub lobby_id: String,
+}
+
+// Opens a seed handshake for the lobby's next game; the reveal must match `commitment`.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct WireSeedCommit {
+    pub lobby_id: String,
+    pub commitment: String,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct WireSeedReveal {
+    pub lobby_id: String,
+    pub value: u64,
+    pub salt: String,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub enum WireMessage {
     Commit(WireCommit),
+    RetractCommit(WireRetract),
     Reveal(WireReveal),
     RequestStateHash,
     StateHash(crate::types::StateHash),
@@ -42,22 +93,105 @@ pub enum WireMessage {
     CallBased(StakeNotice),
     AcceptBased(StakeNotice),
     FoldBased(StakeNotice),
+    CancelBased(StakeNotice),
     JoinLobby(JoinLobbyPayload),
     RequestSnapshot,
     SyncGame(crate::game::GameState),
+    Subscribe(SubscribePayload),
+    Rematch(RematchPayload),
+    Chat(ChatMessage),
+    VerifyRandom(crate::rng::RandomEvent),
+    ChooseDraw(WireDrawChoice),
+    DiscardToHandSize(WireDiscard),
+    // Answered with our own SeedValue; the reveal then fixes the seed on both sides.
+    SeedCommit(WireSeedCommit),
+    SeedReveal(WireSeedReveal),
+    // Liveness check; answered with a Pong echoing the nonce and nothing else.
+    Ping {
+        nonce: u64,
+    },
+    // Several messages applied in order as one request. Batches don't nest.
+    Batch(Vec<WireMessage>),
+}
+
+impl WireMessage {
+    // About a game both sides already hold, so an envelope naming a game we don't have is an error.
+    // Lobby traffic, pings and SyncGame (which brings its game along) aren't.
+    pub fn is_game_scoped(&self) -> bool {
+        match self {
+            WireMessage::JoinLobby(_)
+            | WireMessage::RequestSnapshot
+            | WireMessage::SyncGame(_)
+            | WireMessage::Subscribe(_)
+            | WireMessage::SeedCommit(_)
+            | WireMessage::SeedReveal(_)
+            | WireMessage::Ping { .. } => false,
+            WireMessage::Batch(messages) => messages.iter().any(WireMessage::is_game_scoped),
+            _ => true,
+        }
+    }
+}
+
+// Versioned framing for peer requests. The message stays as JSON text until the version checks
+// out, so a peer on another build gets VersionMismatch instead of a decode failure.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct WireEnvelope {
+    // An envelope without one reads as version 0 and is refused with VersionMismatch.
+    #[serde(default)]
+    pub version: u32,
+    // The sender's game this message is about; None goes to whichever game the receiver has open.
+    #[serde(default)]
+    pub game_id: Option<String>,
+    pub message: String,
+}
+
+impl WireEnvelope {
+    pub fn new(message: &WireMessage, game_id: Option<String>) -> Result<Self, GameError> {
+        let message = serde_json::to_string(message).map_err(|e| GameError::Transport(e.to_string()))?;
+        Ok(Self {
+            version: PROTOCOL_VERSION,
+            game_id,
+            message,
+        })
+    }
+
+    pub fn open(self) -> Result<WireMessage, GameError> {
+        check_version(self.version)?;
+        serde_json::from_str(&self.message).map_err(|e| GameError::MalformedWireMessage(e.to_string()))
+    }
+}
+
+pub fn check_version(theirs: u32) -> Result<(), GameError> {
+    if theirs == PROTOCOL_VERSION {
+        Ok(())
+    } else {
+        Err(GameError::VersionMismatch(VersionParams {
+            ours: PROTOCOL_VERSION,
+            theirs,
+        }))
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub enum WireReply {
     Ack,
-    Snapshot(GameSnapshot),
+    // Boxed so the small replies don't pay for a whole snapshot; serializes the same.
+    Snapshot(Box<GameSnapshot>),
     StateHash(crate::types::StateHash),
+    Pong {
+        nonce: u64,
+    },
+    SeedValue {
+        value: u64,
+    },
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 #[serde(tag = "type", content = "data")]
 pub enum WsClientMessage {
     GetSnapshot,
+    // For clients that already hold the catalog; the reply's catalog is empty.
+    GetSnapshotWithoutCatalog,
     NewGame {
         opponent: Option<String>,
     },
@@ -65,6 +199,8 @@ pub enum WsClientMessage {
     JoinLobby {
         lobby_id: String,
         deck: Vec<String>,
+        #[serde(default)]
+        full_disclosure: bool,
     },
     StartLobbyGame {
         lobby_id: String,
@@ -76,16 +212,29 @@ pub enum WsClientMessage {
         host_node: String,
         lobby_id: String,
         deck: Vec<String>,
+        #[serde(default)]
+        full_disclosure: bool,
+        #[serde(default)]
+        password: Option<String>,
     },
     SyncRemoteGame {
         host_node: String,
     },
+    SpectateRemote {
+        host_node: String,
+    },
+    Rematch {
+        lobby_id: String,
+    },
     CommitTurn {
         seat: Seat,
         plan: TurnPlan,
         salt: String,
         turn: u32,
     },
+    RetractCommit {
+        seat: Seat,
+    },
     RevealTurn {
         seat: Seat,
         plan: TurnPlan,
@@ -106,24 +255,243 @@ pub enum WsClientMessage {
     FoldBased {
         seat: Seat,
     },
+    CancelBased {
+        seat: Seat,
+    },
+    SendChat {
+        text: String,
+    },
+    PingOpponent,
+    ResolveDisconnect {
+        resolution: DisconnectResolution,
+    },
+    ChooseDraw {
+        keep: String,
+    },
+    DiscardToHandSize {
+        keep: Vec<String>,
+    },
+    // Any message type this build doesn't know, so newer clients still get a reply tied to their id.
+    #[serde(other)]
+    Unknown,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 #[serde(tag = "type", content = "data")]
 pub enum WsServerMessage {
-    Snapshot(GameSnapshot),
+    Snapshot(Box<GameSnapshot>),
     Error(String),
+    GameError(WsGameError),
+    Chat(ChatMessage),
+    UnsupportedMessage,
     Ack,
+    // Pushed ahead of the snapshot once a turn resolves. `from` is the index of the first event
+    // in the game's full log, so a client can spot a gap and wait for the snapshot instead.
+    TurnResolved {
+        turn: u32,
+        from: usize,
+        events: Vec<crate::game::GameEvent>,
+    },
+    PhaseChanged(crate::types::Phase),
+    // Round trip to the seated opponent in milliseconds.
+    Latency(u64),
+    // What changed since the last broadcast. The snapshot that follows stays the resync point.
+    Diff(crate::snapshot::GameDiff),
+}
+
+// Handler failures carry the structured error for clients plus the rendered message as a fallback.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct WsGameError {
+    pub error: GameError,
+    pub message: String,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub struct WsEnvelope<T> {
     pub id: Option<String>,
+    // From clients: the game to switch to before handling the message.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub game_id: Option<String>,
     #[serde(flatten)]
     pub message: T,
 }
 
+impl WsEnvelope<WsClientMessage> {
+    // `serde(other)` only catches unknown types sent without data, so retry with the payload
+    // stripped when the type is the part we don't recognise. Malformed known messages still fail.
+    pub fn parse(payload: &str) -> Result<Self, serde_json::Error> {
+        let err = match serde_json::from_str(payload) {
+            Ok(envelope) => return Ok(envelope),
+            Err(err) => err,
+        };
+        let Ok(serde_json::Value::Object(mut fields)) = serde_json::from_str(payload) else {
+            return Err(err);
+        };
+        fields.remove("data");
+        match serde_json::from_value::<Self>(serde_json::Value::Object(fields)) {
+            Ok(envelope) if envelope.message == WsClientMessage::Unknown => Ok(envelope),
+            _ => Err(err),
+        }
+    }
+}
+
 pub enum WsTarget {
     Channel(u32),
     Broadcast,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameState;
+    use crate::types::{ExploitAction, GameOptions, Phase, PostAction, Target};
+
+    // Golden JSON for the shapes peers and the UI depend on. If one of these fails, the change
+    // breaks older clients: keep the old shape, or bump PROTOCOL_VERSION and update the UI with it.
+    fn assert_json<T>(value: &T, expected: &str)
+    where
+        T: Serialize + serde::de::DeserializeOwned + PartialEq + std::fmt::Debug,
+    {
+        assert_eq!(serde_json::to_string(value).unwrap(), expected);
+        assert_eq!(&serde_json::from_str::<T>(expected).unwrap(), value);
+    }
+
+    fn golden_plan() -> TurnPlan {
+        TurnPlan {
+            plays_to_kitchen: vec!["n01-1".into()],
+            posts: vec![PostAction { card_id: "n02-2".into() }],
+            exploits: vec![ExploitAction {
+                card_id: "t08-3".into(),
+                target: Some(Target::Card("n03-4".into())),
+            }],
+            based: false,
+        }
+    }
+
+    #[test]
+    fn wire_messages_keep_their_json_shape() {
+        let plan_json = r#"{"plays_to_kitchen":["n01-1"],"posts":[{"card_id":"n02-2"}],"exploits":[{"card_id":"t08-3","target":{"Card":"n03-4"}}],"based":false}"#;
+        assert_json(&golden_plan(), plan_json);
+        assert_json(
+            &WireMessage::Commit(WireCommit { seat: Seat::Host, hash: "abc".into(), turn: 3 }),
+            r#"{"Commit":{"seat":"Host","hash":"abc","turn":3}}"#,
+        );
+        assert_json(
+            &WireMessage::Reveal(WireReveal { seat: Seat::Opponent, plan: golden_plan(), salt: "s".into(), turn: 3 }),
+            &format!(r#"{{"Reveal":{{"seat":"Opponent","plan":{},"salt":"s","turn":3}}}}"#, plan_json),
+        );
+        assert_json(&WireMessage::RequestStateHash, r#""RequestStateHash""#);
+        assert_json(&WireMessage::Ping { nonce: 9 }, r#"{"Ping":{"nonce":9}}"#);
+        assert_json(
+            &WireMessage::Batch(vec![WireMessage::RequestSnapshot, WireMessage::Ping { nonce: 1 }]),
+            r#"{"Batch":["RequestSnapshot",{"Ping":{"nonce":1}}]}"#,
+        );
+        assert_json(&WireReply::Pong { nonce: 9 }, r#"{"Pong":{"nonce":9}}"#);
+        assert_json(
+            &WireEnvelope { version: 2, game_id: Some("host.os/lobby-0".into()), message: r#""RequestSnapshot""#.into() },
+            r#"{"version":2,"game_id":"host.os/lobby-0","message":"\"RequestSnapshot\""}"#,
+        );
+    }
+
+    #[test]
+    fn ws_envelopes_keep_their_json_shape() {
+        // Tagged content flattened next to the envelope's own fields; game_id is left out when unset.
+        let commit = WsEnvelope {
+            id: Some("req-1".into()),
+            game_id: None,
+            message: WsClientMessage::CommitTurn { seat: Seat::Host, plan: TurnPlan::default(), salt: "s".into(), turn: 2 },
+        };
+        assert_json(
+            &commit,
+            r#"{"id":"req-1","type":"CommitTurn","data":{"seat":"Host","plan":{"plays_to_kitchen":[],"posts":[],"exploits":[],"based":false},"salt":"s","turn":2}}"#,
+        );
+        let reveal = WsEnvelope {
+            id: None,
+            game_id: Some("our.os/game-0".into()),
+            message: WsClientMessage::RevealTurn { seat: Seat::Opponent, plan: golden_plan(), salt: "t".into(), turn: 2 },
+        };
+        let reveal_json = serde_json::to_string(&reveal).unwrap();
+        assert!(reveal_json.starts_with(r#"{"id":null,"game_id":"our.os/game-0","type":"RevealTurn","data":{"seat":"Opponent","plan":"#));
+        assert_eq!(WsEnvelope::<WsClientMessage>::parse(&reveal_json).unwrap(), reveal);
+        // Unit variants carry no data at all.
+        assert_json(
+            &WsEnvelope { id: Some("req-2".into()), game_id: None, message: WsClientMessage::GetSnapshot },
+            r#"{"id":"req-2","type":"GetSnapshot"}"#,
+        );
+
+        let snapshot = GameSnapshot {
+            catalog: vec![],
+            game: None,
+            lobbies: vec![],
+            chat_log: vec![ChatMessage { from: "opp.os".into(), text: "gg".into(), turn: 4 }],
+            total_events: 0,
+            projected_yield: vec![("n01-1".into(), 10)],
+            version: 2,
+            game_ids: vec!["our.os/game-0".into()],
+        };
+        assert_json(
+            &WsEnvelope { id: None, game_id: None, message: WsServerMessage::Snapshot(snapshot.into()) },
+            r#"{"id":null,"type":"Snapshot","data":{"catalog":[],"game":null,"lobbies":[],"chat_log":[{"from":"opp.os","text":"gg","turn":4}],"total_events":0,"projected_yield":[["n01-1",10]],"version":2,"game_ids":["our.os/game-0"]}}"#,
+        );
+        assert_json(
+            &WsEnvelope { id: None, game_id: None, message: WsServerMessage::PhaseChanged(Phase::Reveal) },
+            r#"{"id":null,"type":"PhaseChanged","data":"Reveal"}"#,
+        );
+        assert_json(
+            &WsEnvelope { id: None, game_id: None, message: WsServerMessage::Latency(12) },
+            r#"{"id":null,"type":"Latency","data":12}"#,
+        );
+    }
+
+    #[test]
+    fn peer_snapshots_keep_the_game_shape() {
+        let mut game = GameState::lobby("opp.os".into(), &GameOptions::default());
+        game.id = "our.os/lobby-0".into();
+        let snapshot = GameSnapshot {
+            catalog: vec![],
+            game: Some(game),
+            lobbies: vec![],
+            chat_log: vec![],
+            total_events: 0,
+            projected_yield: vec![],
+            version: 2,
+            game_ids: vec![],
+        };
+        let player = |seat: &str, node: &str| {
+            format!(
+                concat!(
+                    r#"{{"seat":"{}","node_id":"{}","deck":[],"hand":[],"kitchen":[],"abyss":[],"mana":2,"max_mana":2,"#,
+                    r#""score":0,"cost_discount":0,"mana_tax_next":0,"overload_next":0,"score_tax_used":0,"commit":null,"#,
+                    r#""feed_locked":false,"pinned_cards":[],"revealed_deck_top":[],"exposed_hand":null,"pending_draw":[],"#,
+                    r#""last_stand_used":false,"action_points":null,"plays_this_game":{{}},"cosmetics":null}}"#,
+                ),
+                seat, node
+            )
+        };
+        let game_json = format!(
+            concat!(
+                r#"{{"id":"our.os/lobby-0","feed":[],"players":[{},{}],"turn":0,"initiative":"Host","phase":"Lobby","#,
+                r#""stakes":1,"pending_stakes":null,"winner":null,"outcome":null,"feed_size":3,"score_to_win":30,"#,
+                r#""last_stand":"Disabled","action_budget":"Unlimited","win_condition":"ScoreOnly","#,
+                r#""rules":{{"deck_size":12,"meme_limit":4,"exploit_limit":8,"starting_hand":2,"starting_mana":2,"#,
+                r#""mana_cap":10,"draft_draw":false,"discard_phase":false,"max_turns":null,"max_opening_cost":null,"#,
+                r#""feed_decay":0}},"domination_streak":[0,0],"passed_last_turn":[false,false],"full_disclosure":false,"#,
+                r#""game_seed":0,"next_instance":0,"rng":{{"shuffle_seed":0,"resolution_seed":0,"#,
+                r#""host_seed":1132174760382659405,"opponent_seed":1007170684561817943,"host_draws":0,"#,
+                r#""opponent_draws":0,"resolution_host_seed":13105326709074384767,"#,
+                r#""resolution_opponent_seed":2223417968778814662,"resolution_host_draws":0,"#,
+                r#""resolution_opponent_draws":0,"history":[],"reseeds":[]}},"events":[],"score_log":[],"#,
+                r#""plan_history":[],"card_stats":{{}},"last_damaged_by":{{}}}}"#,
+            ),
+            player("Host", "our.os"),
+            player("Opponent", "opp.os"),
+        );
+        assert_json(
+            &WireReply::Snapshot(snapshot.into()),
+            &format!(
+                r#"{{"Snapshot":{{"catalog":[],"game":{},"lobbies":[],"chat_log":[],"total_events":0,"projected_yield":[],"version":2,"game_ids":[]}}}}"#,
+                game_json
+            ),
+        );
+    }
+}
@@ -5,13 +5,18 @@ use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
 // Fair randomness uses a commit+reveal PCG stream per player. History is stored so peers can
-// verify draws and shuffles after the fact.
+// verify draws and shuffles after the fact. Deck shuffles and in-game rolls draw from separate
+// streams so either can be pinned while the other varies.
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub enum RandomEventKind {
     ShuffleDeck(Seat),
     ShuffleFeed,
     RandomizeVirality(String),
+    // Index into this seat's hand for a forced discard.
+    Discard(Seat),
+    // A weighted pick for a draft pack.
+    DraftPack,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
@@ -20,7 +25,11 @@ pub struct RandomContribution {
     pub value: u64,
     pub salt: String,
     pub commitment: String,
+    // Only a hash of the seat and our node id, not a real signature. Every contribution today is
+    // simulated locally from the shared seeds, so this stays false until peers sign their own.
     pub signature: String,
+    #[serde(default)]
+    pub remote_signed: bool,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
@@ -44,13 +53,49 @@ pub struct StartingHandEvent {
     pub chosen: Vec<String>,
 }
 
+// A re-randomization beacon: both revealed plan salts are mixed into the streams so later draws
+// can't be predicted from the starting seed alone. `history_index` marks where it applied.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct ReseedEvent {
+    pub turn: u32,
+    pub history_index: usize,
+    pub host_contribution: String,
+    pub opponent_contribution: String,
+}
+
+// Our side of a lobby seed handshake with `peer`. The initiator commits to its value before seeing
+// the responder's, and the responder's is fixed before the reveal, so neither picks the seed.
+// `commitment` is the peer's and only set when we're responding.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct SeedHandshake {
+    pub lobby_id: String,
+    pub peer: String,
+    pub value: u64,
+    pub commitment: Option<String>,
+    pub agreed: Option<u64>,
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub struct FairRandomState {
+    #[serde(default)]
+    pub shuffle_seed: u64,
+    #[serde(default)]
+    pub resolution_seed: u64,
     pub host_seed: u64,
     pub opponent_seed: u64,
     pub host_draws: u64,
     pub opponent_draws: u64,
+    #[serde(default)]
+    pub resolution_host_seed: u64,
+    #[serde(default)]
+    pub resolution_opponent_seed: u64,
+    #[serde(default)]
+    pub resolution_host_draws: u64,
+    #[serde(default)]
+    pub resolution_opponent_draws: u64,
     pub history: Vec<RandomEvent>,
+    #[serde(default)]
+    pub reseeds: Vec<ReseedEvent>,
 }
 
 impl RandomContribution {
@@ -63,51 +108,138 @@ impl RandomContribution {
             salt,
             commitment,
             signature,
+            remote_signed: false,
         }
     }
 }
 
 impl FairRandomState {
-    pub fn new(seed: u64) -> Self {
+    pub fn with_seeds(shuffle_seed: u64, resolution_seed: u64) -> Self {
         Self {
-            host_seed: derive_seed(seed, "host"),
-            opponent_seed: derive_seed(seed, "opponent"),
+            shuffle_seed,
+            resolution_seed,
+            host_seed: derive_seed(shuffle_seed, "host"),
+            opponent_seed: derive_seed(shuffle_seed, "opponent"),
             host_draws: 0,
             opponent_draws: 0,
+            resolution_host_seed: derive_seed(resolution_seed, "resolution-host"),
+            resolution_opponent_seed: derive_seed(resolution_seed, "resolution-opponent"),
+            resolution_host_draws: 0,
+            resolution_opponent_draws: 0,
             history: Vec::new(),
+            reseeds: Vec::new(),
         }
     }
 
+    // Zeroes every seed for a client view. Draw counts and history stay, so past draws still show.
+    pub fn hide_seeds(&mut self) {
+        self.shuffle_seed = 0;
+        self.resolution_seed = 0;
+        self.host_seed = 0;
+        self.opponent_seed = 0;
+        self.resolution_host_seed = 0;
+        self.resolution_opponent_seed = 0;
+    }
+
+    pub fn reseed(&mut self, turn: u32, host_contribution: &str, opponent_contribution: &str) {
+        let beacon = beacon_seed(turn, host_contribution, opponent_contribution);
+        self.host_seed = derive_seed(self.host_seed ^ beacon, "host");
+        self.opponent_seed = derive_seed(self.opponent_seed ^ beacon, "opponent");
+        self.host_draws = 0;
+        self.opponent_draws = 0;
+        self.resolution_host_seed =
+            derive_seed(self.resolution_host_seed ^ beacon, "resolution-host");
+        self.resolution_opponent_seed =
+            derive_seed(self.resolution_opponent_seed ^ beacon, "resolution-opponent");
+        self.resolution_host_draws = 0;
+        self.resolution_opponent_draws = 0;
+        self.reseeds.push(ReseedEvent {
+            turn,
+            history_index: self.history.len(),
+            host_contribution: host_contribution.to_string(),
+            opponent_contribution: opponent_contribution.to_string(),
+        });
+    }
+
+    // Replays every recorded draw from the starting shuffle seed (plus the recorded resolution
+    // seed), applying reseeds where they happened.
+    pub fn verify(&self, seed: u64) -> bool {
+        let mut replay = FairRandomState::with_seeds(seed, self.resolution_seed);
+        let mut reseeds = self.reseeds.iter().peekable();
+        for event in self.history.iter() {
+            while let Some(reseed) = reseeds.next_if(|r| r.history_index == replay.history.len()) {
+                replay.reseed(reseed.turn, &reseed.host_contribution, &reseed.opponent_contribution);
+            }
+            if replay.generate(event.bound, event.turn, event.kind.clone()) != event.result {
+                return false;
+            }
+        }
+        for reseed in reseeds {
+            replay.reseed(reseed.turn, &reseed.host_contribution, &reseed.opponent_contribution);
+        }
+        replay.host_seed == self.host_seed
+            && replay.opponent_seed == self.opponent_seed
+            && replay.resolution_host_seed == self.resolution_host_seed
+            && replay.resolution_opponent_seed == self.resolution_opponent_seed
+    }
+
+    // Checks a single draw: every contribution must match its commitment, the result must follow
+    // from the contributions, and the draw must be one we recorded.
+    pub fn verify_event(&self, event: &RandomEvent) -> Result<(), String> {
+        if event.contributions.is_empty() {
+            return Err("draw has no contributions".to_string());
+        }
+        for contribution in event.contributions.iter() {
+            if contribution_commitment(contribution.value, &contribution.salt) != contribution.commitment {
+                return Err(format!("{:?} contribution does not match its commitment", contribution.seat));
+            }
+        }
+        if event.bound > 0 {
+            let sum: u128 = event.contributions.iter().map(|c| c.value as u128).sum();
+            if (sum % event.bound as u128) as u64 != event.result {
+                return Err("result does not follow from the contributions".to_string());
+            }
+        }
+        if !self.history.contains(event) {
+            return Err("draw not found in history".to_string());
+        }
+        Ok(())
+    }
+
     pub fn generate(&mut self, bound: u64, turn: u32, kind: RandomEventKind) -> u64 {
         if bound == 0 {
             return 0;
         }
+        let (host_seed, host_draws, opponent_seed, opponent_draws) = match kind {
+            RandomEventKind::ShuffleDeck(_) => (
+                self.host_seed,
+                &mut self.host_draws,
+                self.opponent_seed,
+                &mut self.opponent_draws,
+            ),
+            _ => (
+                self.resolution_host_seed,
+                &mut self.resolution_host_draws,
+                self.resolution_opponent_seed,
+                &mut self.resolution_opponent_draws,
+            ),
+        };
         let host_value = {
-            let mut rng = pcg_from_seed(self.host_seed);
-            for _ in 0..self.host_draws {
-                let _ = rng.next_u64();
-            }
-            let value = rng.gen_range(0..bound);
-            self.host_draws += 1;
+            let value = draw_from(host_seed, host_draws, bound);
             RandomContribution::new(
                 Seat::Host,
                 value,
-                format!("turn-{}-host-draw-{}-{:?}", turn, self.host_draws, &kind),
+                format!("turn-{}-host-draw-{}-{:?}", turn, host_draws, &kind),
             )
         };
         let opponent_value = {
-            let mut rng = pcg_from_seed(self.opponent_seed);
-            for _ in 0..self.opponent_draws {
-                let _ = rng.next_u64();
-            }
-            let value = rng.gen_range(0..bound);
-            self.opponent_draws += 1;
+            let value = draw_from(opponent_seed, opponent_draws, bound);
             RandomContribution::new(
                 Seat::Opponent,
                 value,
                 format!(
                     "turn-{}-opponent-draw-{}-{:?}",
-                    turn, self.opponent_draws, &kind
+                    turn, opponent_draws, &kind
                 ),
             )
         };
@@ -134,6 +266,17 @@ impl FairRandomState {
     }
 }
 
+// Skips the draws already consumed from a stream, then takes the next value below `bound`.
+fn draw_from(seed: u64, draws: &mut u64, bound: u64) -> u64 {
+    let mut rng = pcg_from_seed(seed);
+    for _ in 0..*draws {
+        let _ = rng.next_u64();
+    }
+    let value = rng.gen_range(0..bound);
+    *draws += 1;
+    value
+}
+
 pub fn contribution_commitment(value: u64, salt: &str) -> String {
     let mut hasher = Sha256::new();
     hasher.update(value.to_le_bytes());
@@ -159,6 +302,35 @@ pub fn derive_seed(base: u64, label: &str) -> u64 {
     u64::from_le_bytes(seed_bytes)
 }
 
+// The next game in a series is dealt from the agreed seed of the game before it, so the host can't
+// pick it between games and the opponent can check it without another handshake.
+pub fn series_seed(previous: u64) -> u64 {
+    derive_seed(previous, "series")
+}
+
+// Both peers hash the halves in initiator, responder order so they land on the same seed.
+pub fn shared_seed(initiator_value: u64, responder_value: u64) -> u64 {
+    let mut hasher = Sha256::new();
+    hasher.update(initiator_value.to_le_bytes());
+    hasher.update(responder_value.to_le_bytes());
+    hasher.update(b"lobby-seed");
+    let hash = hasher.finalize();
+    let mut seed_bytes = [0u8; 8];
+    seed_bytes.copy_from_slice(&hash[..8]);
+    u64::from_le_bytes(seed_bytes)
+}
+
+fn beacon_seed(turn: u32, host_contribution: &str, opponent_contribution: &str) -> u64 {
+    let mut hasher = Sha256::new();
+    hasher.update(turn.to_le_bytes());
+    hasher.update(host_contribution.as_bytes());
+    hasher.update(opponent_contribution.as_bytes());
+    let hash = hasher.finalize();
+    let mut seed_bytes = [0u8; 8];
+    seed_bytes.copy_from_slice(&hash[..8]);
+    u64::from_le_bytes(seed_bytes)
+}
+
 pub fn pcg_from_seed(seed: u64) -> Pcg64Mcg {
     // Expand the u64 into 16 bytes to seed the PCG generator deterministically.
     let mut hasher = Sha256::new();
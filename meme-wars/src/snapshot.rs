@@ -1,6 +1,8 @@
+use crate::constants::SNAPSHOT_EVENT_WINDOW;
 use crate::game::GameState;
-use crate::types::{CardDefinition, Lobby};
+use crate::types::{CardDefinition, CardInstance, ChatMessage, Lobby, Phase, Seat, Series};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 // Lightweight container for UI sync. Carries catalog, live game, and lobby list.
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
@@ -8,4 +10,140 @@ pub struct GameSnapshot {
     pub catalog: Vec<CardDefinition>,
     pub game: Option<GameState>,
     pub lobbies: Vec<Lobby>,
+    #[serde(default)]
+    pub chat_log: Vec<ChatMessage>,
+    // Length of the game's full event log, so clients can tell how much the window left out.
+    #[serde(default)]
+    pub total_events: usize,
+    // Score each feed card would yield if the turn ended now, by instance id.
+    #[serde(default)]
+    pub projected_yield: Vec<(String, i32)>,
+    // PROTOCOL_VERSION of the build that composed it; 0 means a build from before versioning.
+    #[serde(default)]
+    pub version: u32,
+    // Every game this node holds, sorted; `game` is the one on screen.
+    #[serde(default)]
+    pub game_ids: Vec<String>,
+}
+
+impl GameSnapshot {
+    // Peers sync from the full snapshot; the UI only ever sees the view redacted for its node.
+    pub fn for_client(mut self, viewer: &str) -> Self {
+        self.game = self.game.map(|game| {
+            let mut view = game.client_view(viewer);
+            view.trim_history(SNAPSHOT_EVENT_WINDOW);
+            view
+        });
+        self
+    }
+
+    // Only what anyone at the table could see: no hands, decks or commitments.
+    pub fn public(self) -> PublicSnapshot {
+        PublicSnapshot {
+            game: self.game.map(|game| {
+                let view = game.client_view("");
+                PublicGame {
+                    turn: view.turn,
+                    phase: view.phase,
+                    stakes: view.stakes,
+                    scores: view.players.iter().map(|p| (p.seat.clone(), p.score)).collect(),
+                    feed: view.feed,
+                    winner: view.winner,
+                }
+            }),
+            lobbies: self
+                .lobbies
+                .into_iter()
+                .filter(|l| !l.private)
+                .map(PublicLobby::from)
+                .collect(),
+        }
+    }
+}
+
+// Read-only view for scoreboards and observers.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct PublicSnapshot {
+    pub game: Option<PublicGame>,
+    pub lobbies: Vec<PublicLobby>,
+}
+
+// A lobby as the lobby list shows it: who is playing what, without their decks.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct PublicLobby {
+    pub id: String,
+    pub host: String,
+    pub mode: String,
+    pub stakes: u8,
+    pub description: String,
+    pub opponent: Option<String>,
+    pub started: bool,
+    pub series: Option<Series>,
+}
+
+impl From<Lobby> for PublicLobby {
+    fn from(lobby: Lobby) -> Self {
+        PublicLobby {
+            id: lobby.id,
+            host: lobby.host,
+            mode: lobby.mode,
+            stakes: lobby.stakes,
+            description: lobby.description,
+            opponent: lobby.opponent,
+            started: lobby.started,
+            series: lobby.series,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct PublicGame {
+    pub turn: u32,
+    pub phase: Phase,
+    pub stakes: u8,
+    pub scores: Vec<(Seat, i32)>,
+    pub feed: Vec<CardInstance>,
+    pub winner: Option<Seat>,
+}
+
+// Changes between two broadcasts of a game, for clients that can't afford a snapshot every time.
+// Feed slots carry the whole card; cards entering a hand, kitchen or abyss only appear by id, so
+// a client that needs them waits for the next snapshot.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct GameDiff {
+    pub turn: u32,
+    // Only set when the phase changed.
+    pub phase: Option<Phase>,
+    pub feed: Vec<FeedSlotChange>,
+    // Score change per seat, for seats whose score moved.
+    pub scores: Vec<(Seat, i32)>,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    // Every other top-level field of the game that changed, by name, with its new value. Events
+    // aren't among them; they come with TurnResolved.
+    #[serde(default)]
+    pub fields: BTreeMap<String, serde_json::Value>,
+    // The feed's projected yield, when it moved.
+    #[serde(default)]
+    pub projected_yield: Option<Vec<(String, i32)>>,
+}
+
+impl GameDiff {
+    // Turn number aside, nothing changed.
+    pub fn is_empty(&self) -> bool {
+        self.phase.is_none()
+            && self.feed.is_empty()
+            && self.scores.is_empty()
+            && self.added.is_empty()
+            && self.removed.is_empty()
+            && self.fields.is_empty()
+            && self.projected_yield.is_none()
+    }
+}
+
+// `card` is None when the slot emptied, e.g. the feed shrank.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct FeedSlotChange {
+    pub slot: usize,
+    pub card: Option<CardInstance>,
 }
@@ -1,3 +1,4 @@
+use crate::constants::{EXPLOIT_LIMIT, MANA_CAP, MAX_DECK_SIZE, MEME_LIMIT, STARTING_HAND, STARTING_MANA};
 use serde::{Deserialize, Serialize};
 
 // Shared data types that describe cards, abilities, and turn plans. These are kept lean and
@@ -26,9 +27,30 @@ pub struct CardDefinition {
     pub description: String,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub image: Option<String>,
+    #[serde(default)]
+    pub rarity: Rarity,
     pub class: CardKind,
 }
 
+#[derive(Clone, Default, Serialize, Deserialize, Debug, PartialEq)]
+pub enum Rarity {
+    #[default]
+    Common,
+    Uncommon,
+    Rare,
+}
+
+impl Rarity {
+    // Relative odds of a card turning up in a draft pack.
+    pub fn weight(&self) -> u64 {
+        match self {
+            Rarity::Common => 6,
+            Rarity::Uncommon => 3,
+            Rarity::Rare => 1,
+        }
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub enum CardKind {
     Meme(MemeBlueprint),
@@ -44,6 +66,8 @@ pub struct MemeBlueprint {
     pub abilities: Vec<Ability>,
     pub volatile: Option<i32>,
     pub initial_freeze: Option<u32>,
+    #[serde(default)]
+    pub post_cost: u8,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
@@ -57,6 +81,9 @@ pub enum Keyword {
     Heavy,
     Gatekeeper(GatekeeperKeyword),
     HealKitchen,
+    Thorns(ThornsKeyword),
+    Overload(OverloadKeyword),
+    Combo(ComboKeyword),
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
@@ -69,6 +96,24 @@ pub struct GatekeeperKeyword {
     pub max_cost: u8,
 }
 
+// Exploit damage to this meme hits back at the attacker's top kitchen card.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct ThornsKeyword {
+    pub amount: i32,
+}
+
+// Playing this meme to the kitchen costs its owner this much mana on their next turn.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct OverloadKeyword {
+    pub amount: u8,
+}
+
+// Gains this much virality for each other Combo meme its owner posts in the same turn.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct ComboKeyword {
+    pub amount: i32,
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub enum AbilityTrigger {
     OnPlayKitchen,
@@ -91,6 +136,9 @@ pub enum AbilityEffect {
     PingOpponentTop(i32),
     SelfDestructNext,
     RandomizeVirality(RandomRange),
+    Scry(u8),
+    // With AuraKitchen: the owner's hand cards cost this much less while the meme is in the kitchen.
+    CostAura(u8),
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
@@ -139,6 +187,32 @@ pub enum ExploitEffect {
     WipeBottom(usize),
     SpawnShitposts(usize),
     Silence,
+    Draw(u8),
+    FeedSabotage,
+    Steal,
+    Clone,
+    Scry(u8),
+    CloneEnemy,
+    SwapSlots(SwapParams),
+    Bounce,
+    ChainDamage(ChainParams),
+    Expose,
+    Invest(InvestParams),
+    // The opponent discards this many cards, picked at random since the caster can't see them.
+    Discard(u8),
+    // Rearranges the whole feed into the order given by a Target::FeedOrder.
+    Reorder,
+    // Restores lost virality on an own card, never past its base. Boost is the one that overshoots.
+    Heal(i32),
+    // Silence that doesn't spare shields: every keyword and ability goes, and the shield drops to 0.
+    Purge,
+    Sacrifice(SacrificeParams),
+    // The top this many cards of the opponent's deck go straight to their abyss.
+    Mill(u8),
+    // Takes score from the opponent, floored at 0 and capped per game by MAX_SCORE_TAX.
+    ScoreTax(i32),
+    // Sends an enemy feed card to their abyss and fills their kitchen with a shitpost per 2 of its cost.
+    Demote,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
@@ -162,12 +236,43 @@ pub struct ManaBurnParams {
     pub amount: u8,
 }
 
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct SwapParams {
+    pub a: usize,
+    pub b: usize,
+}
+
+// Spends `cost_score` of the caster's score to give one of their memes `virality`.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct InvestParams {
+    pub cost_score: i32,
+    pub virality: i32,
+}
+
+// Sends one of the caster's kitchen memes to the abyss and hits the enemy's top kitchen meme for
+// `damage` times the sacrificed meme's current virality.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct SacrificeParams {
+    pub damage: i32,
+}
+
+// Hits up to `targets` enemy kitchen memes, each for `falloff` less than the one before.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct ChainParams {
+    pub amount: i32,
+    pub targets: usize,
+    pub falloff: i32,
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub enum Target {
     AnyKitchen,
     EnemyKitchen,
     FeedSlot(usize),
     Card(String),
+    // New order for feed cards, as indices into their current top-to-bottom order. FeedSabotage
+    // orders only the opponent's cards; Reorder orders the whole feed.
+    FeedOrder(Vec<usize>),
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
@@ -252,9 +357,38 @@ pub enum Phase {
     Reveal,
     Resolving,
     StakePending,
+    // Draft-draw games only: waiting for both seats to pick which drawn card to keep.
+    Draft,
+    // Discard-phase games only: a seat drew past MAX_HAND_SIZE and must pick what to keep.
+    Discard,
     GameOver,
 }
 
+// Why a game ended, so a drawn game can be told apart from one with no winner recorded yet.
+// `GameState.outcome` stays `None` while the game is still going.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum GameOutcome {
+    Win(Seat),
+    Draw,
+    // max_turns ran out: the higher score wins, `None` on a tie.
+    TurnLimit(Option<Seat>),
+    // This seat folded to a BASED call, handing the game to the other.
+    Folded(Seat),
+    // This seat stopped answering and the other claimed the game.
+    Timeout(Seat),
+    // The opponent stopped answering and the game was dropped: no winner, no match record.
+    Abandoned,
+}
+
+// What the player left behind does with a game whose opponent stopped answering.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum DisconnectResolution {
+    // A win that counts, recorded as GameOutcome::Timeout.
+    ClaimWin,
+    // Ends the game without touching the match history.
+    Abandon,
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub struct Lobby {
     pub id: String,
@@ -266,6 +400,93 @@ pub struct Lobby {
     pub started: bool,
     pub host_deck: Vec<String>,
     pub opponent_deck: Vec<String>,
+    #[serde(default)]
+    pub feed_size: Option<usize>,
+    #[serde(default)]
+    pub score_to_win: Option<i32>,
+    #[serde(default)]
+    pub last_stand: LastStandRule,
+    #[serde(default)]
+    pub action_budget: ActionBudgetRule,
+    #[serde(default)]
+    pub win_condition: WinCondition,
+    #[serde(default)]
+    pub rules: GameRules,
+    #[serde(default)]
+    pub rematch_count: u32,
+    #[serde(default)]
+    pub host_cosmetics: Option<PlayerCosmetics>,
+    #[serde(default)]
+    pub opponent_cosmetics: Option<PlayerCosmetics>,
+    // Both sides must opt in before decks are disclosed at game over.
+    #[serde(default)]
+    pub full_disclosure: bool,
+    #[serde(default)]
+    pub opponent_full_disclosure: bool,
+    // Private lobbies are left out of remote lobby lists and need the password to join.
+    #[serde(default)]
+    pub private: bool,
+    // Only kept on the hosting node; snapshots always go out with it cleared.
+    #[serde(default)]
+    pub password_hash: Option<String>,
+    #[serde(default)]
+    pub series: Option<Series>,
+}
+
+// A best-of-N match played as back-to-back games in one lobby. Wins are (host, opponent).
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct Series {
+    pub best_of: u8,
+    pub wins: (u32, u32),
+}
+
+impl Series {
+    pub fn new(best_of: u8) -> Self {
+        Self { best_of, wins: (0, 0) }
+    }
+
+    // Draws count for nobody.
+    pub fn record(&mut self, winner: Option<&Seat>) {
+        match winner {
+            Some(Seat::Host) => self.wins.0 += 1,
+            Some(Seat::Opponent) => self.wins.1 += 1,
+            None => {}
+        }
+    }
+
+    // The seat that has won a majority of the games, if either has.
+    pub fn clinched_by(&self) -> Option<Seat> {
+        let needed = self.best_of as u32 / 2 + 1;
+        if self.wins.0 >= needed {
+            Some(Seat::Host)
+        } else if self.wins.1 >= needed {
+            Some(Seat::Opponent)
+        } else {
+            None
+        }
+    }
+}
+
+impl Lobby {
+    // Host and lobby id together, since each host numbers its lobbies from the same start.
+    pub fn game_id(&self) -> String {
+        format!("{}/{}", self.host, self.id)
+    }
+
+    pub fn game_options(&self) -> GameOptions {
+        GameOptions {
+            feed_size: self.feed_size,
+            score_to_win: self.score_to_win,
+            last_stand: self.last_stand.clone(),
+            action_budget: self.action_budget.clone(),
+            win_condition: self.win_condition.clone(),
+            rules: self.rules.clone(),
+            resolution_seed: None,
+            host_cosmetics: self.host_cosmetics.clone(),
+            opponent_cosmetics: self.opponent_cosmetics.clone(),
+            full_disclosure: self.full_disclosure && self.opponent_full_disclosure,
+        }
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
@@ -274,6 +495,162 @@ pub struct LobbyConfig {
     pub stakes: u8,
     pub description: String,
     pub deck: Vec<String>,
+    #[serde(default)]
+    pub feed_size: Option<usize>,
+    #[serde(default)]
+    pub score_to_win: Option<i32>,
+    #[serde(default)]
+    pub last_stand: LastStandRule,
+    #[serde(default)]
+    pub action_budget: ActionBudgetRule,
+    #[serde(default)]
+    pub win_condition: WinCondition,
+    #[serde(default)]
+    pub rules: GameRules,
+    #[serde(default)]
+    pub cosmetics: Option<PlayerCosmetics>,
+    #[serde(default)]
+    pub full_disclosure: bool,
+    #[serde(default)]
+    pub password: Option<String>,
+    // Play a best-of-N series in this lobby; 0 or 1 is a single game.
+    #[serde(default)]
+    pub best_of: Option<u8>,
+}
+
+// Per-game overrides for the pacing constants. `None` falls back to the defaults in constants.rs.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Default)]
+pub struct GameOptions {
+    pub feed_size: Option<usize>,
+    pub score_to_win: Option<i32>,
+    #[serde(default)]
+    pub last_stand: LastStandRule,
+    #[serde(default)]
+    pub action_budget: ActionBudgetRule,
+    #[serde(default)]
+    pub win_condition: WinCondition,
+    #[serde(default)]
+    pub rules: GameRules,
+    // Seeds in-game rolls separately from deck shuffles; defaults to the game seed.
+    #[serde(default)]
+    pub resolution_seed: Option<u64>,
+    #[serde(default)]
+    pub host_cosmetics: Option<PlayerCosmetics>,
+    #[serde(default)]
+    pub opponent_cosmetics: Option<PlayerCosmetics>,
+    #[serde(default)]
+    pub full_disclosure: bool,
+}
+
+// When enabled, each player's strongest kitchen meme survives one lethal cleanup at 1 Virality.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Default)]
+pub enum LastStandRule {
+    #[default]
+    Disabled,
+    OncePerGame,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct ChatMessage {
+    pub from: String,
+    pub text: String,
+    pub turn: u32,
+}
+
+// Summary of a finished game. `players` and `final_scores` are in seat order (host, opponent).
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct MatchRecord {
+    pub game_seed: u64,
+    pub players: Vec<String>,
+    pub winner: Option<Seat>,
+    pub final_scores: Vec<i32>,
+    pub turns: u32,
+    // Unix seconds.
+    pub ended_at: u64,
+    #[serde(default)]
+    pub outcome: Option<GameOutcome>,
+}
+
+// Deck and mana format for a game. The default is the standard format from constants.rs; other
+// formats (e.g. draft) swap in their own limits.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct GameRules {
+    pub deck_size: usize,
+    pub meme_limit: usize,
+    pub exploit_limit: usize,
+    pub starting_hand: usize,
+    pub starting_mana: u8,
+    pub mana_cap: u8,
+    // Draw two at end of turn and keep one instead of drawing a single card.
+    #[serde(default)]
+    pub draft_draw: bool,
+    // Let the end-of-turn draw go over MAX_HAND_SIZE and have the player choose what to discard,
+    // instead of the drawn card going straight to the abyss.
+    #[serde(default)]
+    pub discard_phase: bool,
+    // The game ends after this many turns even if nobody has reached score_to_win.
+    #[serde(default)]
+    pub max_turns: Option<u32>,
+    // Cycle the opening hand, like the meme guarantee does, until its total cost is at most this.
+    #[serde(default)]
+    pub max_opening_cost: Option<u32>,
+    // Virality every feed card loses at the end of each turn, pinned and anchored ones included.
+    #[serde(default)]
+    pub feed_decay: i32,
+}
+
+impl Default for GameRules {
+    fn default() -> Self {
+        Self {
+            deck_size: MAX_DECK_SIZE,
+            meme_limit: MEME_LIMIT,
+            exploit_limit: EXPLOIT_LIMIT,
+            starting_hand: STARTING_HAND,
+            starting_mana: STARTING_MANA,
+            mana_cap: MANA_CAP,
+            draft_draw: false,
+            discard_phase: false,
+            max_turns: None,
+            max_opening_cost: None,
+            feed_decay: 0,
+        }
+    }
+}
+
+// Reaching score_to_win always wins; FeedDominationStreak adds a second way to win by owning every
+// occupied feed slot at the end of that many consecutive turns. FullFeedControl needs every slot
+// filled and owned, held for FULL_FEED_CONTROL_TURNS turns.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Default)]
+pub enum WinCondition {
+    #[default]
+    ScoreOnly,
+    FeedDominationStreak(u8),
+    FullFeedControl,
+}
+
+// Experimental: caps how many plays, posts and exploits a player may take per turn, on top of mana.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Default)]
+pub enum ActionBudgetRule {
+    #[default]
+    Unlimited,
+    PerTurn(u8),
+}
+
+impl ActionBudgetRule {
+    pub fn per_turn(&self) -> Option<u8> {
+        match self {
+            ActionBudgetRule::Unlimited => None,
+            ActionBudgetRule::PerTurn(points) => Some(*points),
+        }
+    }
+}
+
+// Purely cosmetic player presentation. Never part of the state hash.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Default)]
+pub struct PlayerCosmetics {
+    pub deck_back: Option<String>,
+    pub avatar: Option<String>,
+    pub title: Option<String>,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
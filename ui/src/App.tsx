@@ -146,6 +146,14 @@ const locationToUi = (location: any): 'hand' | 'kitchen' | 'feed' => {
   return 'hand';
 };
 
+// The FeedOrder a Reorder drop submits: the feed flipped top to bottom, or with the most
+// recently played cards on top.
+const reorderPermutation = (feed: CardInstance[], newestFirst: boolean): number[] => {
+  const order = feed.map((_, i) => i);
+  if (!newestFirst) return order.reverse();
+  return order.sort((a, b) => feed[b].played_turn - feed[a].played_turn || a - b);
+};
+
 const mapInstanceToLiveCard = (instance: CardInstance, catalog?: Map<string, BackendCardDefinition>): LiveCard => {
   const kind: 'Meme' | 'Exploit' = 'Meme' in (instance.class as any) ? 'Meme' : 'Exploit';
   const def = catalog?.get(instance.variant_id);
@@ -387,11 +395,15 @@ function App() {
     fetchRemoteLobbies,
     syncRemoteGame,
     startLobbyGame,
+    rematch,
     leaveGame,
+    selectGame,
+    resolveDisconnect,
     commitTurn,
     revealTurn,
     acceptBased,
     foldBased,
+    cancelBased,
   } = useMcgStore();
 
   const [activeScreen, setActiveScreen] = useState<Screen>('lobby');
@@ -404,8 +416,20 @@ function App() {
   const [modalCard, setModalCard] = useState<{ card: UICardDefinition | LiveCard; rect: DOMRect } | null>(null);
   const [showSettingsModal, setShowSettingsModal] = useState(false);
   const [showHostModal, setShowHostModal] = useState(false);
-  const [pendingLobbyAction, setPendingLobbyAction] = useState<{ lobby: Lobby; action: 'start' | 'join' } | null>(null);
-  const [hostForm, setHostForm] = useState({ mode: 'Standard', stakes: 1, description: 'Public lobby' });
+  const [reorderNewestFirst, setReorderNewestFirst] = useState(false);
+  const [pendingLobbyAction, setPendingLobbyAction] = useState<{
+    lobby: Lobby;
+    action: 'start' | 'join';
+    password?: string | null;
+  } | null>(null);
+  const [inviteForm, setInviteForm] = useState({ code: '', password: '' });
+  const [hostForm, setHostForm] = useState({
+    mode: 'Standard',
+    stakes: 1,
+    description: 'Public lobby',
+    password: '',
+    best_of: 1,
+  });
   const [draggingId, setDraggingId] = useState<string | null>(null);
   const [lastSyncedHost, setLastSyncedHost] = useState<string | null>(null);
   const [draftPlan, setDraftPlan] = useState<TurnPlan>({
@@ -512,6 +536,10 @@ function App() {
     () => (game ? game.feed.map((card) => mapInstanceToLiveCard(card, catalogById)) : []),
     [game, catalogById],
   );
+  const projectedYield = useMemo(
+    () => new Map(snapshot?.projected_yield ?? []),
+    [snapshot?.projected_yield],
+  );
   const playerKitchen = useMemo(() => {
     const cards = myPlayer ? myPlayer.kitchen.map((card) => mapInstanceToLiveCard(card, catalogById)) : [];
     console.log('playerKitchen cards:', cards.map(c => ({ name: c.name, owner: c.owner })));
@@ -591,7 +619,17 @@ function App() {
     !!game?.pending_stakes;
 
   const costWithDiscount = (card: LiveCard) => {
-    const discount = myPlayer?.cost_discount ?? 0;
+    // Mirrors PlayerState::effective_discount: one-off discounts plus kitchen cost auras.
+    const auras = (myPlayer?.kitchen ?? [])
+      .flatMap((kitchenCard) => kitchenCard.abilities)
+      .reduce((sum, ability) => {
+        const effect = ability.effect;
+        if (ability.trigger !== 'AuraKitchen' || typeof effect !== 'object' || !('CostAura' in effect)) {
+          return sum;
+        }
+        return sum + effect.CostAura;
+      }, 0);
+    const discount = (myPlayer?.cost_discount ?? 0) + auras;
     return Math.max(0, card.cost - discount);
   };
 
@@ -615,6 +653,7 @@ function App() {
   const availableMana = Math.max(0, (myPlayer?.mana ?? 0) - plannedManaSpent);
   const opponentCalledBased =
     !!game && !!game.pending_stakes && game.pending_stakes !== myNodeId;
+  const weCalledBased = !!game && !!game.pending_stakes && game.pending_stakes === myNodeId;
   // Show modal when:
   // 1. Phase is StakePending and opponent called BASED (immediate response needed)
   // 2. User clicked End Turn while opponent has pending BASED
@@ -701,12 +740,15 @@ function App() {
         profile.targetType = 'card';
         break;
       case 'AreaDamageKitchen':
-        // Area damage targets the entire enemy kitchen zone
+      case 'ChainDamage':
+        // Area and chain damage target the enemy kitchen zone; the backend picks the cards
         profile.enemyKitchenZone = true;
         profile.requiresTarget = false;
         profile.targetType = 'zone';
         break;
       case 'Boost':
+      case 'Heal':
+      case 'Invest':
       case 'Protect':
       case 'Double':
         // Buff exploits target ally cards
@@ -717,11 +759,35 @@ function App() {
       case 'Debuff':
       case 'Execute':
       case 'Silence':
+      case 'Purge':
         // Debuff/removal exploits target enemy cards
         profile.enemyKitchenCard = true;
         profile.enemyFeedCard = true;
         profile.targetType = 'card';
         break;
+      case 'Steal':
+      case 'Demote':
+        // Steal and Demote only hit enemy posts already in the feed
+        profile.enemyFeedCard = true;
+        profile.targetType = 'card';
+        break;
+      case 'Clone':
+      case 'Sacrifice':
+        // Clone copies, and Sacrifice spends, one of our own kitchen memes
+        profile.allyKitchenCard = true;
+        profile.targetType = 'card';
+        break;
+      case 'CloneEnemy':
+        // CloneEnemy copies an enemy meme from their kitchen or the feed
+        profile.enemyKitchenCard = true;
+        profile.enemyFeedCard = true;
+        profile.targetType = 'card';
+        break;
+      case 'Bounce':
+        // Bounce returns an enemy kitchen meme to its owner's hand
+        profile.enemyKitchenCard = true;
+        profile.targetType = 'card';
+        break;
       case 'PinSlot':
       case 'MoveUp':
       case 'NukeBelow':
@@ -737,6 +803,9 @@ function App() {
         break;
       case 'Tax':
       case 'ManaBurn':
+      case 'Discard':
+      case 'Mill':
+      case 'ScoreTax':
         // These target the opponent directly (via their kitchen zone)
         profile.enemyKitchenZone = true;
         profile.requiresTarget = false;
@@ -744,6 +813,9 @@ function App() {
         break;
       case 'ShuffleFeed':
       case 'WipeBottom':
+      case 'FeedSabotage':
+      case 'SwapSlots':
+      case 'Reorder':
         // These target the feed zone
         profile.feedZone = true;
         profile.requiresTarget = false;
@@ -752,6 +824,9 @@ function App() {
       case 'ResurrectLast':
       case 'SpawnShitposts':
       case 'DiscountNext':
+      case 'Draw':
+      case 'Scry':
+      case 'Expose':
         // Self-targeting, no target needed
         profile.requiresTarget = false;
         profile.targetType = 'none';
@@ -798,7 +873,7 @@ function App() {
     previousOpponentAbyss.current = new Set();
     opponentAbyssInitialized.current = false;
     lastExploitTurn.current = null;
-  }, [game?.game_seed]);
+  }, [game?.id]);
 
   // Track opponent exploits when turn resolves
   useEffect(() => {
@@ -843,7 +918,7 @@ function App() {
 
     const exploits = newExploitCards.map((card) => ({ card_id: card.instance_id, target: null }));
     triggerOpponentExploitNotification(exploits, game.turn);
-  }, [game?.game_seed, game?.turn, opponentPlayer?.abyss, opponentPlayer?.node_id, triggerOpponentExploitNotification]);
+  }, [game?.id, game?.turn, opponentPlayer?.abyss, opponentPlayer?.node_id, triggerOpponentExploitNotification]);
 
   // Show pending exploits when BASED modal closes
   useEffect(() => {
@@ -1035,14 +1110,42 @@ function App() {
     setModalCard(null);
   };
 
-  const handleJoinLobbyClick = async (lobby: Lobby, deckCards: string[]) => {
+  const handleJoinLobbyClick = async (lobby: Lobby, deckCards: string[], invitePassword?: string | null) => {
     if (nodeId && lobby.host !== nodeId) {
-      await joinRemoteLobby(lobby.host, lobby.id, deckCards);
+      const password =
+        invitePassword !== undefined ? invitePassword : lobby.private ? window.prompt('Lobby password') : null;
+      await joinRemoteLobby(lobby.host, lobby.id, deckCards, false, password);
     } else {
       await joinLobby(lobby.id, deckCards);
     }
   };
 
+  // Private lobbies never show up in remote listings, so they're joined by
+  // an invite code of the form `host-node/lobby-id`.
+  const handleJoinByInvite = () => {
+    const code = inviteForm.code.trim();
+    const split = code.indexOf('/');
+    if (split <= 0 || split === code.length - 1) return;
+    const lobby: Lobby = {
+      id: code.slice(split + 1),
+      host: code.slice(0, split),
+      mode: 'Standard',
+      stakes: 1,
+      description: 'Private lobby',
+      opponent: null,
+      started: false,
+      rematch_count: 0,
+      host_cosmetics: null,
+      opponent_cosmetics: null,
+      full_disclosure: false,
+      opponent_full_disclosure: false,
+      private: true,
+    };
+    setPendingLobbyAction({ lobby, action: 'join', password: inviteForm.password || null });
+    setInviteForm({ code: '', password: '' });
+    closeAllModals();
+  };
+
   const handleEnterGame = async (lobby: Lobby) => {
     if (nodeId && lobby.host !== nodeId) {
       await syncRemoteGame(lobby.host);
@@ -1074,15 +1177,17 @@ function App() {
     setPendingReveal(null);
   }, [game?.turn, resetPlan]);
 
-  // Auto-enter duel when a game snapshot arrives (e.g., host started).
+  // Auto-enter duel when a game snapshot arrives (e.g., host started). A hosted lobby's
+  // placeholder game doesn't pull the host away from the lobby screen where they start it.
+  const gameStarted = !!snapshot?.game && snapshot.game.phase !== 'Lobby';
   useEffect(() => {
-    if (snapshot?.game && activeScreen !== 'duel') {
+    if (gameStarted && activeScreen !== 'duel') {
       setActiveScreen('duel');
     }
     if (!snapshot?.game && activeScreen === 'duel') {
       setActiveScreen('lobby');
     }
-  }, [snapshot?.game, activeScreen]);
+  }, [gameStarted, snapshot?.game, activeScreen]);
 
   // Joiner fallback: if a lobby we're in has started but we don't yet have the game,
   // pull the remote game from the host.
@@ -1559,7 +1664,10 @@ function App() {
       const meta = getPlayableMeta(card);
       // Allow dropping if exploit targets feed zone OR doesn't require a target
       if (meta.canPlay && (profile.feedZone || !profile.requiresTarget)) {
-        queueExploit(card.id, null);
+        // Dropping Reorder on the feed flips it top to bottom, or sorts it newest first if preferred.
+        const isReorder = getExploitKind(getExploitEffect(card)) === 'Reorder';
+        const target = isReorder && game ? { FeedOrder: reorderPermutation(game.feed, reorderNewestFirst) } : null;
+        queueExploit(card.id, target);
       } else {
         flashNoPlay(card.id, 'handleFeedDrop:exploitNoZone');
       }
@@ -1707,9 +1815,20 @@ function App() {
                   <span className="card-title">{lobby.host}</span>
                   <span className="pill">{lobby.mode}</span>
                   {lobby.started && <span className="pill success">Live</span>}
+                  {lobby.private && <span className="pill">Private</span>}
                 </div>
                 <p className="muted">{lobby.description}</p>
+                {isHost && lobby.private && (
+                  <p className="muted small">
+                    Invite code: <code>{`${lobby.host}/${lobby.id}`}</code>
+                  </p>
+                )}
                 <p className="muted">Stakes: {lobby.stakes} • Players: {lobby.opponent ? '2/2' : '1/2'}</p>
+                {lobby.series && (
+                  <p className="muted small">
+                    Best of {lobby.series.best_of}: Host {lobby.series.wins[0]} – {lobby.series.wins[1]} Opponent
+                  </p>
+                )}
               </div>
               <div className="lobby-actions-inline">
                 {!lobby.started && !isHost && !lobby.opponent && (
@@ -1898,6 +2017,20 @@ function App() {
               <span />
             </label>
           </div>
+          <div className="setting-row">
+            <div>
+              <p className="card-name">Reorder Newest First</p>
+              <p className="muted small">Algorithm Tweak puts fresh posts on top instead of flipping the feed</p>
+            </div>
+            <label className="toggle">
+              <input
+                type="checkbox"
+                checked={reorderNewestFirst}
+                onChange={(e) => setReorderNewestFirst(e.target.checked)}
+              />
+              <span />
+            </label>
+          </div>
         </div>
 
         <div className="surface settings-card">
@@ -2236,8 +2369,9 @@ function App() {
       <header className="duel-header surface">
         <div className="enemy-hand-line">
           <div className="enemy-hand-bubbles" aria-label="Opponent hand">
-            {Array.from({ length: opponentPlayer?.hand.length ?? 0 }).map((_, idx) => (
-              <span key={idx} className="card-bubble" />
+            {(opponentPlayer?.hand ?? []).map((card, idx) => (
+              // Cards we exposed come through face up; name them on hover.
+              <span key={idx} className="card-bubble" title={card.variant_id === 'hidden' ? undefined : card.name} />
             ))}
           </div>
           <div className="duel-top-actions">
@@ -2253,7 +2387,17 @@ function App() {
                 BASED
               </button>
               <span className="stakes-display">x{stakesMultiplier}</span>
+              {weCalledBased && (
+                <button className="ghost-btn compact" onClick={() => cancelBased(mySeat ?? 'Host')}>
+                  Cancel
+                </button>
+              )}
             </div>
+            {game?.rules?.max_turns ? (
+              <span className="stakes-display">
+                {game.turn + 1} / {game.rules.max_turns}
+              </span>
+            ) : null}
             <button className="icon-btn tiny" aria-label="Settings" onClick={() => setShowSettingsModal(true)}>
               <Icon name="settings" size={16} />
             </button>
@@ -2398,7 +2542,12 @@ function App() {
                   >
                     <span className="feed-prefix">#{idx + 1}</span>
                     <span className="feed-name ellipsis">{card.name}</span>
-                    <span className="feed-virality">{card.currentVirality}</span>
+                    <span
+                      className="feed-virality"
+                      title={projectedYield.has(card.id) ? `Yields +${projectedYield.get(card.id)} at turn end` : undefined}
+                    >
+                      {card.currentVirality}
+                    </span>
                     {isTargetedByExploit && (
                       <span className="exploit-indicator-small" title="Targeted by exploit">⚡</span>
                     )}
@@ -2601,6 +2750,35 @@ function App() {
                 Fetch from host
               </button>
             </div>
+            <div className="panel-header" style={{ marginTop: 12 }}>
+              <h3>Join with invite code</h3>
+            </div>
+            <input
+              placeholder="host.os/lobby-id"
+              value={inviteForm.code}
+              onChange={(e) => setInviteForm((prev) => ({ ...prev, code: e.target.value }))}
+            />
+            <input
+              type="password"
+              placeholder="Password"
+              value={inviteForm.password}
+              onChange={(e) => setInviteForm((prev) => ({ ...prev, password: e.target.value }))}
+              onKeyDown={(e) => {
+                if (e.key === 'Enter') {
+                  e.preventDefault();
+                  handleJoinByInvite();
+                }
+              }}
+            />
+            <div className="panel-actions" style={{ marginTop: 8 }}>
+              <button
+                className="ghost-btn compact"
+                onClick={handleJoinByInvite}
+                disabled={!/^[^/]+\/.+$/.test(inviteForm.code.trim())}
+              >
+                Join
+              </button>
+            </div>
             <div className="modal-results scroll-y">
               {error === 'cannot fetch remote lobbies from self' ? (
                 <p className="warning small">cannot fetch remote lobbies from self</p>
@@ -2704,6 +2882,19 @@ function App() {
               onChange={(e) => setHostForm((prev) => ({ ...prev, stakes: parseInt(e.target.value || '1', 10) }))}
             />
           </div>
+          <div className="stack">
+            <label className="muted small">Best of</label>
+            <select
+              value={hostForm.best_of}
+              onChange={(e) => setHostForm((prev) => ({ ...prev, best_of: parseInt(e.target.value, 10) }))}
+            >
+              {[1, 3, 5].map((n) => (
+                <option key={n} value={n}>
+                  {n === 1 ? 'Single game' : `Best of ${n}`}
+                </option>
+              ))}
+            </select>
+          </div>
           <div className="stack">
             <label className="muted small">Description</label>
             <textarea
@@ -2712,11 +2903,20 @@ function App() {
               onChange={(e) => setHostForm((prev) => ({ ...prev, description: e.target.value }))}
             />
           </div>
+          <div className="stack">
+            <label className="muted small">Password (leave empty for a public lobby)</label>
+            <input
+              type="password"
+              value={hostForm.password}
+              onChange={(e) => setHostForm((prev) => ({ ...prev, password: e.target.value }))}
+            />
+          </div>
           <button
             className="save-btn"
             onClick={async () => {
               const selectedDeck = decks.find((d) => d.id === selectedDeckId);
-              await hostLobby({ ...hostForm, deck: selectedDeck?.cards ?? [] });
+              const { password, ...config } = hostForm;
+              await hostLobby({ ...config, password: password || null, deck: selectedDeck?.cards ?? [] });
               setShowHostModal(false);
             }}
           >
@@ -2731,10 +2931,10 @@ function App() {
     const selectedDeck = decks.find((d) => d.id === selectedDeckId);
     const handleConfirm = async () => {
       if (!selectedDeck) return;
-      const { lobby, action } = pendingLobbyAction;
+      const { lobby, action, password } = pendingLobbyAction;
       setPendingLobbyAction(null);
       if (action === 'join') {
-        await handleJoinLobbyClick(lobby, selectedDeck.cards);
+        await handleJoinLobbyClick(lobby, selectedDeck.cards, password);
       } else {
         await startLobbyGame(lobby.id);
         setActiveScreen('duel');
@@ -2791,6 +2991,43 @@ function App() {
             Leave game
           </button>
           <p className="muted small">You will return to the lobby.</p>
+          <p className="muted small">Opponent stopped responding?</p>
+          <button
+            className="ghost-btn compact"
+            onClick={async () => {
+              setShowSettingsModal(false);
+              await resolveDisconnect('ClaimWin');
+            }}
+          >
+            Claim win
+          </button>
+          <button
+            className="ghost-btn compact"
+            onClick={async () => {
+              setShowSettingsModal(false);
+              await resolveDisconnect('Abandon');
+            }}
+          >
+            Abandon game
+          </button>
+          {(snapshot?.game_ids?.length ?? 0) > 1 && (
+            <>
+              <p className="muted small">Switch to another of your games:</p>
+              {snapshot?.game_ids?.map((id) => (
+                <button
+                  key={id}
+                  className="ghost-btn compact"
+                  disabled={id === game?.id}
+                  onClick={async () => {
+                    setShowSettingsModal(false);
+                    await selectGame(id);
+                  }}
+                >
+                  {id}
+                </button>
+              ))}
+            </>
+          )}
         </div>
       </div>
     );
@@ -2830,15 +3067,37 @@ function App() {
   };
 
   const renderWinLoseModal = () => {
-    if (!game?.winner) return null;
+    const outcome = game?.outcome;
+    const isDraw =
+      outcome === 'Draw' || (typeof outcome === 'object' && !!outcome && 'TurnLimit' in outcome && !outcome.TurnLimit);
+    if (!game?.winner && !isDraw) return null;
     const isWinner = game.winner === mySeat;
+    const folded = typeof outcome === 'object' && !!outcome && 'Folded' in outcome ? outcome.Folded : null;
+    const timedOut = typeof outcome === 'object' && !!outcome && 'Timeout' in outcome ? outcome.Timeout : null;
+    const currentLobby = lobbies.find(
+      (l) => l.started && (l.host === nodeId || l.opponent === nodeId),
+    );
     return (
       <div className="modal-overlay modal-entering" onClick={(e) => e.stopPropagation()}>
         <div className={`search-modal surface win-lose-modal ${isWinner ? 'win' : 'lose'}`}>
           <h2 className={`win-lose-title ${isWinner ? 'win' : 'lose'}`}>
-            {isWinner ? 'You Win!' : 'You Lose'}
+            {isDraw ? 'Draw' : isWinner ? 'You Win!' : 'You Lose'}
           </h2>
+          {folded && (
+            <p className="muted small">{folded === mySeat ? 'You folded' : 'Opponent folded'}</p>
+          )}
+          {timedOut && (
+            <p className="muted small">{timedOut === mySeat ? 'You timed out' : 'Opponent timed out'}</p>
+          )}
           <p className="muted">Stakes: x{stakesMultiplier}</p>
+          {currentLobby && (
+            <>
+              <p className="muted small">Game {currentLobby.rematch_count + 1}</p>
+              <button className="save-btn" onClick={() => rematch(currentLobby.id)}>
+                Rematch
+              </button>
+            </>
+          )}
           <button className="save-btn" onClick={handleLeaveGame}>
             Leave Game
           </button>
@@ -1,29 +1,65 @@
 // Zustand store for Hyperapp Skeleton state management
 import { create } from 'zustand';
-import type { McgState, GameSnapshot, Seat, TurnPlan } from '../types/mcg';
+import type { McgState, CardInstance, ChatMessage, DisconnectResolution, GameDiff, GameSnapshot, GameState, Phase, Seat, TurnPlan } from '../types/mcg';
 import { getNodeId } from '../types/global';
 
 type WsClientMessage =
   | { type: 'GetSnapshot' }
+  | { type: 'GetSnapshotWithoutCatalog' }
   | { type: 'NewGame'; data?: { opponent?: string | null } }
-  | { type: 'HostLobby'; data: { mode: string; stakes: number; description: string; deck: string[] } }
-  | { type: 'JoinLobby'; data: { lobby_id: string; deck: string[] } }
+  | {
+      type: 'HostLobby';
+      data: {
+        mode: string;
+        stakes: number;
+        description: string;
+        deck: string[];
+        full_disclosure?: boolean;
+        password?: string | null;
+      };
+    }
+  | { type: 'JoinLobby'; data: { lobby_id: string; deck: string[]; full_disclosure?: boolean } }
   | { type: 'StartLobbyGame'; data: { lobby_id: string } }
   | { type: 'FetchRemoteLobbies'; data: { host_node: string } }
-  | { type: 'JoinRemoteLobby'; data: { host_node: string; lobby_id: string; deck: string[] } }
+  | {
+      type: 'JoinRemoteLobby';
+      data: {
+        host_node: string;
+        lobby_id: string;
+        deck: string[];
+        full_disclosure?: boolean;
+        password?: string | null;
+      };
+    }
   | { type: 'SyncRemoteGame'; data: { host_node: string } }
+  | { type: 'SpectateRemote'; data: { host_node: string } }
+  | { type: 'Rematch'; data: { lobby_id: string } }
   | { type: 'CommitTurn'; data: { seat: Seat; plan: TurnPlan; salt: string; turn: number } }
+  | { type: 'RetractCommit'; data: { seat: Seat } }
   | { type: 'RevealTurn'; data: { seat: Seat; plan: TurnPlan; salt: string; turn: number } }
   | { type: 'Reset' }
   | { type: 'PlayLocalTurn'; data: { host_plan: TurnPlan; opponent_plan?: TurnPlan } }
   | { type: 'CallBased'; data: { seat: Seat } }
   | { type: 'AcceptBased'; data: { seat: Seat } }
-  | { type: 'FoldBased'; data: { seat: Seat } };
+  | { type: 'FoldBased'; data: { seat: Seat } }
+  | { type: 'CancelBased'; data: { seat: Seat } }
+  | { type: 'SendChat'; data: { text: string } }
+  | { type: 'PingOpponent' }
+  | { type: 'ResolveDisconnect'; data: { resolution: DisconnectResolution } }
+  | { type: 'ChooseDraw'; data: { keep: string } }
+  | { type: 'DiscardToHandSize'; data: { keep: string[] } };
 
 type WsServerEnvelope =
   | { id?: string | null; type: 'Snapshot'; data: GameSnapshot }
   | { id?: string | null; type: 'Error'; data: string }
-  | { id?: string | null; type: 'Ack'; data?: null };
+  | { id?: string | null; type: 'GameError'; data: { error: unknown; message: string } }
+  | { id?: string | null; type: 'Chat'; data: ChatMessage }
+  | { id?: string | null; type: 'UnsupportedMessage'; data?: null }
+  | { id?: string | null; type: 'Ack'; data?: null }
+  | { id?: string | null; type: 'TurnResolved'; data: { turn: number; from: number; events: unknown[] } }
+  | { id?: string | null; type: 'PhaseChanged'; data: Phase }
+  | { id?: string | null; type: 'Latency'; data: number }
+  | { id?: string | null; type: 'Diff'; data: GameDiff };
 
 type PendingRequest = {
   resolve: (message: WsServerEnvelope) => void;
@@ -32,6 +68,7 @@ type PendingRequest = {
 };
 
 const WS_TIMEOUT_MS = 12000;
+const MAX_CHAT_LOG = 100;
 const BASE_PATH = import.meta.env.BASE_URL || '/';
 const trimmedBase = BASE_PATH.endsWith('/') ? BASE_PATH.slice(0, -1) : BASE_PATH;
 const buildWsUrl = () => {
@@ -48,21 +85,45 @@ interface McgStore extends McgState {
   // Actions
   initialize: () => void;
   fetchSnapshot: () => Promise<void>;
+  selectGame: (gameId: string) => Promise<void>;
   startGame: (opponent?: string | null) => Promise<void>;
-  hostLobby: (config: { mode: string; stakes: number; description: string; deck: string[] }) => Promise<void>;
-  joinLobby: (lobbyId: string, deck: string[]) => Promise<void>;
-  joinRemoteLobby: (hostNode: string, lobbyId: string, deck: string[]) => Promise<void>;
+  hostLobby: (config: {
+    mode: string;
+    stakes: number;
+    description: string;
+    deck: string[];
+    full_disclosure?: boolean;
+    password?: string | null;
+    best_of?: number | null;
+  }) => Promise<void>;
+  joinLobby: (lobbyId: string, deck: string[], fullDisclosure?: boolean) => Promise<void>;
+  joinRemoteLobby: (
+    hostNode: string,
+    lobbyId: string,
+    deck: string[],
+    fullDisclosure?: boolean,
+    password?: string | null,
+  ) => Promise<void>;
   fetchRemoteLobbies: (hostNode: string) => Promise<void>;
   syncRemoteGame: (hostNode: string) => Promise<void>;
+  spectateRemote: (hostNode: string) => Promise<void>;
+  rematch: (lobbyId: string) => Promise<void>;
   startLobbyGame: (lobbyId: string) => Promise<void>;
   leaveGame: () => Promise<void>;
   commitTurn: (seat: Seat, plan: TurnPlan, salt: string, turn: number) => Promise<void>;
+  retractCommit: (seat: Seat) => Promise<void>;
   revealTurn: (seat: Seat, plan: TurnPlan, salt: string, turn: number) => Promise<void>;
   playTurn: (plan: import('../types/mcg').TurnPlan, opponentPlan?: import('../types/mcg').TurnPlan) => Promise<void>;
   playEmptyTurn: () => Promise<void>;
   callBased: (seat?: Seat) => Promise<void>;
   acceptBased: (seat?: Seat) => Promise<void>;
   foldBased: (seat?: Seat) => Promise<void>;
+  cancelBased: (seat?: Seat) => Promise<void>;
+  sendChat: (text: string) => Promise<void>;
+  pingOpponent: () => Promise<void>;
+  resolveDisconnect: (resolution: DisconnectResolution) => Promise<void>;
+  chooseDraw: (keep: string) => Promise<void>;
+  discardToHandSize: (keep: string[]) => Promise<void>;
   setError: (error: string | null) => void;
   clearError: () => void;
 }
@@ -94,18 +155,71 @@ export const useMcgStore = create<McgStore>((set, get) => {
       settlePending(message.id, message);
     }
     if (message.type === 'Snapshot') {
-      set({ snapshot: message.data, isLoading: false });
+      // Lean snapshots come without the catalog; keep the one we already have.
+      const previous = get().snapshot;
+      const snapshot =
+        message.data.catalog.length === 0 && previous?.catalog.length
+          ? { ...message.data, catalog: previous.catalog }
+          : message.data;
+      set({ snapshot, isLoading: false });
     } else if (message.type === 'Error') {
       set({ error: message.data, isLoading: false });
+    } else if (message.type === 'GameError') {
+      set({ error: message.data.message, isLoading: false });
+    } else if (message.type === 'Chat') {
+      const snapshot = get().snapshot;
+      if (snapshot) {
+        const chatLog = [...(snapshot.chat_log ?? []), message.data].slice(-MAX_CHAT_LOG);
+        set({ snapshot: { ...snapshot, chat_log: chatLog } });
+      }
+    } else if (message.type === 'TurnResolved') {
+      // A gap in the log means we missed a broadcast; only a snapshot can fill it.
+      const snapshot = get().snapshot;
+      if (snapshot?.game) {
+        const { from, events } = message.data;
+        if ((snapshot.total_events ?? 0) !== from) {
+          void get().fetchSnapshot();
+          return;
+        }
+        set({
+          snapshot: {
+            ...snapshot,
+            total_events: from + events.length,
+            game: { ...snapshot.game, events: [...(snapshot.game.events ?? []), ...events] },
+          },
+        });
+      }
+    } else if (message.type === 'PhaseChanged') {
+      // The diff that comes with it sets the phase too; this just lets the UI react a beat earlier.
+      const snapshot = get().snapshot;
+      if (snapshot?.game) {
+        set({ snapshot: { ...snapshot, game: { ...snapshot.game, phase: message.data } } });
+      }
+    } else if (message.type === 'Latency') {
+      set({ opponentLatencyMs: message.data });
+    } else if (message.type === 'Diff') {
+      const snapshot = get().snapshot;
+      if (snapshot?.game) {
+        set({
+          snapshot: {
+            ...snapshot,
+            game: applyDiff(snapshot.game, message.data),
+            projected_yield: message.data.projected_yield ?? snapshot.projected_yield,
+          },
+        });
+      }
     }
   };
 
-  const sendWs = (message: WsClientMessage): Promise<WsServerEnvelope> => {
+  const sendWs = (message: WsClientMessage, gameId?: string): Promise<WsServerEnvelope> => {
     if (!socket || socket.readyState !== WebSocket.OPEN) {
       return Promise.reject(new Error('WebSocket not connected'));
     }
     const id = crypto.randomUUID();
     const payload: Record<string, unknown> = { id, type: message.type };
+    if (gameId) {
+      payload.game_id = gameId;
+    }
     if ('data' in message && (message as any).data !== undefined) {
       payload.data = (message as any).data;
     }
@@ -176,13 +290,17 @@ export const useMcgStore = create<McgStore>((set, get) => {
     }
   };
 
-  const run = async (message: WsClientMessage) => {
+  const run = async (message: WsClientMessage, gameId?: string) => {
     set({ isLoading: true, error: null });
     await ensureSocketReady();
     try {
-      const response = await sendWs(message);
+      const response = await sendWs(message, gameId);
       if (response.type === 'Error') {
         set({ error: response.data });
+      } else if (response.type === 'GameError') {
+        set({ error: response.data.message });
+      } else if (response.type === 'UnsupportedMessage') {
+        set({ error: `Server does not support ${message.type}` });
       }
     } catch (error) {
       set({ error: getErrorMessage(error) });
@@ -194,6 +312,7 @@ export const useMcgStore = create<McgStore>((set, get) => {
   return {
     nodeId: null,
     isConnected: false,
+    opponentLatencyMs: null,
     snapshot: null,
     isLoading: false,
     error: null,
@@ -210,7 +329,12 @@ export const useMcgStore = create<McgStore>((set, get) => {
     },
 
     fetchSnapshot: async () => {
-      await run({ type: 'GetSnapshot' });
+      await run(get().snapshot?.catalog.length ? { type: 'GetSnapshotWithoutCatalog' } : { type: 'GetSnapshot' });
+    },
+
+    // Any message tagged with a game id switches to that game first; a snapshot request is the plain switch.
+    selectGame: async (gameId) => {
+      await run({ type: 'GetSnapshotWithoutCatalog' }, gameId);
     },
 
     startGame: async (opponent = null) => {
@@ -221,12 +345,15 @@ export const useMcgStore = create<McgStore>((set, get) => {
       await run({ type: 'HostLobby', data: config });
     },
 
-    joinLobby: async (lobbyId, deck) => {
-      await run({ type: 'JoinLobby', data: { lobby_id: lobbyId, deck } });
+    joinLobby: async (lobbyId, deck, fullDisclosure = false) => {
+      await run({ type: 'JoinLobby', data: { lobby_id: lobbyId, deck, full_disclosure: fullDisclosure } });
     },
 
-    joinRemoteLobby: async (hostNode, lobbyId, deck) => {
-      await run({ type: 'JoinRemoteLobby', data: { host_node: hostNode, lobby_id: lobbyId, deck } });
+    joinRemoteLobby: async (hostNode, lobbyId, deck, fullDisclosure = false, password = null) => {
+      await run({
+        type: 'JoinRemoteLobby',
+        data: { host_node: hostNode, lobby_id: lobbyId, deck, full_disclosure: fullDisclosure, password },
+      });
     },
 
     fetchRemoteLobbies: async (hostNode) => {
@@ -237,6 +364,13 @@ export const useMcgStore = create<McgStore>((set, get) => {
       await run({ type: 'SyncRemoteGame', data: { host_node: hostNode } });
     },
 
+    spectateRemote: async (hostNode) => {
+      await run({ type: 'SpectateRemote', data: { host_node: hostNode } });
+    },
+    rematch: async (lobbyId) => {
+      await run({ type: 'Rematch', data: { lobby_id: lobbyId } });
+    },
+
     startLobbyGame: async (lobbyId) => {
       await run({ type: 'StartLobbyGame', data: { lobby_id: lobbyId } });
     },
@@ -249,6 +383,10 @@ export const useMcgStore = create<McgStore>((set, get) => {
       await run({ type: 'CommitTurn', data: { seat, plan, salt, turn } });
     },
 
+    retractCommit: async (seat: Seat) => {
+      await run({ type: 'RetractCommit', data: { seat } });
+    },
+
     revealTurn: async (seat: Seat, plan: TurnPlan, salt: string, turn: number) => {
       await run({ type: 'RevealTurn', data: { seat, plan, salt, turn } });
     },
@@ -276,6 +414,39 @@ export const useMcgStore = create<McgStore>((set, get) => {
       await run({ type: 'FoldBased', data: { seat } });
     },
 
+    cancelBased: async (seat: Seat = 'Host') => {
+      await run({ type: 'CancelBased', data: { seat } });
+    },
+
+    sendChat: async (text: string) => {
+      await run({ type: 'SendChat', data: { text } });
+    },
+
+    chooseDraw: async (keep: string) => {
+      await run({ type: 'ChooseDraw', data: { keep } });
+    },
+
+    discardToHandSize: async (keep: string[]) => {
+      await run({ type: 'DiscardToHandSize', data: { keep } });
+    },
+
+    // Meant for polling, so it skips the loading flag and treats any failure as unreachable.
+    pingOpponent: async () => {
+      try {
+        await ensureSocketReady();
+        const response = await sendWs({ type: 'PingOpponent' });
+        if (response.type !== 'Latency') {
+          set({ opponentLatencyMs: null });
+        }
+      } catch {
+        set({ opponentLatencyMs: null });
+      }
+    },
+
+    resolveDisconnect: async (resolution) => {
+      await run({ type: 'ResolveDisconnect', data: { resolution } });
+    },
+
     // Error management
     setError: (error) => set({ error }),
     clearError: () => set({ error: null }),
@@ -286,6 +457,34 @@ function getErrorMessage(error: unknown): string {
   return error instanceof Error ? error.message : 'An unknown error occurred';
 }
 
+// Patches the feed slots and scores, then takes every other changed field as sent; the players in
+// `fields` bring the cards the diff only names as added.
+function applyDiff(game: GameState, diff: GameDiff): GameState {
+  const feed: (CardInstance | null)[] = [...game.feed];
+  for (const change of diff.feed) {
+    feed[change.slot] = change.card;
+  }
+  const removed = new Set(diff.removed);
+  const keep = (card: CardInstance) => !removed.has(card.instance_id);
+  return {
+    ...game,
+    turn: diff.turn,
+    phase: diff.phase ?? game.phase,
+    feed: feed.filter((card): card is CardInstance => card != null),
+    players: game.players.map((player) => {
+      const delta = diff.scores.find(([seat]) => seat === player.seat)?.[1] ?? 0;
+      return {
+        ...player,
+        score: player.score + delta,
+        hand: player.hand.filter(keep),
+        kitchen: player.kitchen.filter(keep),
+        abyss: player.abyss.filter(keep),
+      };
+    }),
+    ...diff.fields,
+  };
+}
+
 // Selector hooks for common use cases
 export const useNodeId = () => useMcgStore((state) => state.nodeId);
 export const useIsConnected = () => useMcgStore((state) => state.isConnected);
@@ -6,6 +6,8 @@ export interface McgState {
   // Connection state
   nodeId: string | null;
   isConnected: boolean;
+  // Last measured round trip to the seated opponent, in milliseconds
+  opponentLatencyMs: number | null;
   
   // App data (backend snapshot)
   snapshot: GameSnapshot | null;
@@ -23,6 +25,35 @@ export interface GameSnapshot {
   catalog: CardDefinition[];
   game: GameState | null;
   lobbies?: Lobby[];
+  chat_log?: ChatMessage[];
+  total_events?: number;
+  projected_yield?: [string, number][];
+  version?: number;
+  game_ids?: string[];
+}
+
+export interface FeedSlotChange {
+  slot: number;
+  card: CardInstance | null;
+}
+
+// Changes since the previous broadcast. Broadcasts send deltas in place of a snapshot when they
+// cover the whole change; `fields` holds every other top-level game field that changed.
+export interface GameDiff {
+  turn: number;
+  phase: Phase | null;
+  feed: FeedSlotChange[];
+  scores: [Seat, number][];
+  added: string[];
+  removed: string[];
+  fields?: Partial<GameState>;
+  projected_yield?: [string, number][] | null;
+}
+
+export interface ChatMessage {
+  from: string;
+  text: string;
+  turn: number;
 }
 
 export interface Lobby {
@@ -33,9 +64,28 @@ export interface Lobby {
   description: string;
   opponent: string | null;
   started: boolean;
+  rematch_count: number;
+  host_cosmetics: PlayerCosmetics | null;
+  opponent_cosmetics: PlayerCosmetics | null;
+  full_disclosure: boolean;
+  opponent_full_disclosure: boolean;
+  private?: boolean;
+  series?: Series | null;
+}
+
+export interface Series {
+  best_of: number;
+  wins: [number, number];
+}
+
+export interface PlayerCosmetics {
+  deck_back: string | null;
+  avatar: string | null;
+  title: string | null;
 }
 
 export interface GameState {
+  id?: string;
   feed: CardInstance[];
   players: PlayerState[];
   turn: number;
@@ -44,11 +94,51 @@ export interface GameState {
   stakes: number;
   pending_stakes: string | null;
   winner: Seat | null;
+  outcome?: GameOutcome | null;
+  full_disclosure: boolean;
+  // 0 in our view until a fully disclosed game is over.
   game_seed: number;
   next_instance: number;
+  score_log: ScoreDelta[];
+  plan_history?: [number, Seat, TurnPlan][];
+  domination_streak?: [number, number];
+  passed_last_turn?: [boolean, boolean];
+  card_stats?: Record<string, CardStats>;
+  rules?: GameRules;
+  events?: unknown[];
+}
+
+export interface GameRules {
+  max_turns?: number | null;
+  max_opening_cost?: number | null;
+  feed_decay?: number;
+}
+
+export interface CardStats {
+  posted: number;
+  score_yielded: number;
+  kills: number;
+  deaths: number;
+}
+
+export type GameOutcome =
+  | { Win: Seat }
+  | 'Draw'
+  | { TurnLimit: Seat | null }
+  | { Folded: Seat }
+  | { Timeout: Seat }
+  | 'Abandoned';
+
+export type DisconnectResolution = 'ClaimWin' | 'Abandon';
+
+export interface ScoreDelta {
+  turn: number;
+  seat: Seat;
+  amount: number;
+  reason: 'FeedYield' | 'Invest' | 'ScoreTax';
 }
 
-export type Phase = 'Lobby' | 'Commit' | 'Reveal' | 'Resolving' | 'StakePending' | 'GameOver';
+export type Phase = 'Lobby' | 'Commit' | 'Reveal' | 'Resolving' | 'StakePending' | 'Draft' | 'Discard' | 'GameOver';
 
 export interface PlayerState {
   seat: Seat;
@@ -62,9 +152,17 @@ export interface PlayerState {
   score: number;
   cost_discount: number;
   mana_tax_next: number;
+  overload_next?: number;
+  score_tax_used?: number;
   commit: TurnCommit | null;
   feed_locked: boolean;
-  pinned_slots: number[];
+  pinned_cards: string[];
+  revealed_deck_top?: string[];
+  exposed_hand?: string[] | null;
+  pending_draw?: CardInstance[];
+  plays_this_game: Record<string, number>;
+  action_points?: number | null;
+  cosmetics: PlayerCosmetics | null;
 }
 
 export interface TurnCommit {
@@ -107,9 +205,12 @@ export interface CardDefinition {
   cost: number;
   description: string;
   image?: string;
+  rarity?: Rarity;
   class: CardKind;
 }
 
+export type Rarity = 'Common' | 'Uncommon' | 'Rare';
+
 export type CardKind =
   | { Meme: MemeBlueprint }
   | { Exploit: ExploitEffect };
@@ -122,6 +223,7 @@ export interface MemeBlueprint {
   abilities: Ability[];
   volatile: number | null;
   initial_freeze: number | null;
+  post_cost: number;
 }
 
 export type Keyword =
@@ -133,7 +235,10 @@ export type Keyword =
   | 'Anchor'
   | 'Heavy'
   | { Gatekeeper: GatekeeperKeyword }
-  | 'HealKitchen';
+  | 'HealKitchen'
+  | { Thorns: ThornsKeyword }
+  | { Overload: OverloadKeyword }
+  | { Combo: ComboKeyword };
 
 export interface ShieldedKeyword {
   amount: number;
@@ -143,6 +248,18 @@ export interface GatekeeperKeyword {
   max_cost: number;
 }
 
+export interface ThornsKeyword {
+  amount: number;
+}
+
+export interface OverloadKeyword {
+  amount: number;
+}
+
+export interface ComboKeyword {
+  amount: number;
+}
+
 export type AbilityTrigger =
   | 'OnPlayKitchen'
   | 'OnPost'
@@ -161,7 +278,9 @@ export type AbilityEffect =
   | { GainMana: number }
   | { PingOpponentTop: number }
   | 'SelfDestructNext'
-  | { RandomizeVirality: RandomRange };
+  | { RandomizeVirality: RandomRange }
+  | { Scry: number }
+  | { CostAura: number };
 
 export interface Ability {
   trigger: AbilityTrigger;
@@ -200,7 +319,46 @@ export type ExploitEffect =
   | { ManaBurn: ManaBurnParams }
   | { WipeBottom: number }
   | { SpawnShitposts: number }
-  | 'Silence';
+  | 'Silence'
+  | { Draw: number }
+  | 'FeedSabotage'
+  | 'Steal'
+  | 'Clone'
+  | { Scry: number }
+  | 'CloneEnemy'
+  | { SwapSlots: SwapParams }
+  | 'Bounce'
+  | { ChainDamage: ChainParams }
+  | 'Expose'
+  | { Invest: InvestParams }
+  | { Discard: number }
+  | 'Reorder'
+  | { Heal: number }
+  | 'Purge'
+  | { Sacrifice: SacrificeParams }
+  | { Mill: number }
+  | { ScoreTax: number }
+  | 'Demote';
+
+export interface SwapParams {
+  a: number;
+  b: number;
+}
+
+export interface InvestParams {
+  cost_score: number;
+  virality: number;
+}
+
+export interface SacrificeParams {
+  damage: number;
+}
+
+export interface ChainParams {
+  amount: number;
+  targets: number;
+  falloff: number;
+}
 
 export interface DamageParams {
   amount: number;
@@ -223,7 +381,8 @@ export type Target =
   | 'AnyKitchen'
   | 'EnemyKitchen'
   | { FeedSlot: number }
-  | { Card: string };
+  | { Card: string }
+  | { FeedOrder: number[] };
 
 export interface CardInstance {
   instance_id: string;